@@ -134,5 +134,82 @@ fn host_inserts(c: &mut criterion::Criterion) {
     }
 }
 
+#[cfg(all(unix, feature = "shm"))]
+fn shm_message_sending(c: &mut criterion::Criterion) {
+    let socket_path = std::env::temp_dir().join("meadow_bench_shm.sock");
+    let sc = SledConfig::new().temporary(true);
+    let mut host = HostConfig::default()
+        .with_sled_config(sc)
+        .with_tcp_config(Some(
+            meadow::host::TcpConfig::default("lo").set_max_buffer_size(16 * KB),
+        ))
+        .with_shm_config(Some(ShmConfig::new(&socket_path)))
+        .build()
+        .unwrap();
+    host.start().unwrap();
+
+    // Compare both transports on payloads small enough for a single TCP read
+    for size in [1, KB, 4 * KB, 8 * KB].iter() {
+        let mut rng = rand::thread_rng();
+        let bytes: Vec<u8> = (0..*size).map(|_| rng.gen()).collect();
+
+        let tcp_cfg = NetworkConfig::<Blocking, Tcp>::default().set_max_buffer_size(16 * KB);
+        let tcp = NodeConfig::<Blocking, Tcp, Vec<u8>>::new("bytes")
+            .with_config(tcp_cfg)
+            .build()
+            .unwrap()
+            .activate()
+            .unwrap();
+        c.bench_function(&("tcp_bytes_".to_owned() + &size.to_string()), |b| {
+            b.iter(|| {
+                tcp.publish(bytes.clone()).unwrap();
+                let _result = tcp.request().unwrap();
+            });
+        });
+
+        let shm_cfg = NetworkConfig::<Blocking, Shm>::default().set_socket_path(&socket_path);
+        let shm = NodeConfig::<Blocking, Shm, Vec<u8>>::new("bytes")
+            .with_config(shm_cfg)
+            .build()
+            .unwrap()
+            .activate()
+            .unwrap();
+        c.bench_function(&("shm_bytes_".to_owned() + &size.to_string()), |b| {
+            b.iter(|| {
+                shm.publish(bytes.clone()).unwrap();
+                let _result = shm.request().unwrap();
+            });
+
+            let result = shm.request().unwrap();
+            assert_eq!(bytes, result.data);
+        });
+    }
+
+    // A full 640x480 RGB camera frame only fits through shared memory
+    let frame = vec![0u8; 640 * 480 * 3];
+    let shm_cfg = NetworkConfig::<Blocking, Shm>::default().set_socket_path(&socket_path);
+    let shm = NodeConfig::<Blocking, Shm, Vec<u8>>::new("frame")
+        .with_config(shm_cfg)
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+    c.bench_function("shm_publish_camera_frame", |b| {
+        b.iter(|| {
+            shm.publish(frame.clone()).unwrap();
+        });
+    });
+
+    host.stop().unwrap();
+}
+
+#[cfg(not(all(unix, feature = "shm")))]
 criterion_group!(benches, tcp_message_sending, host_inserts);
+#[cfg(all(unix, feature = "shm"))]
+criterion_group!(
+    benches,
+    tcp_message_sending,
+    host_inserts,
+    shm_message_sending
+);
 criterion_main!(benches);
@@ -0,0 +1,112 @@
+use criterion::{criterion_group, criterion_main};
+use meadow::prelude::*;
+use meadow::raw::{FromBytes, Immutable, IntoBytes, KnownLayout, Unaligned};
+use rand::prelude::*;
+use std::convert::TryInto;
+use zerocopy::little_endian::F32;
+
+pub const KB: usize = 1024;
+const POINTS: usize = 1024;
+
+/// A LiDAR scan with a fixed number of points, laid out the same on every machine
+#[derive(Debug, Clone, FromBytes, IntoBytes, KnownLayout, Immutable, Unaligned)]
+#[repr(C)]
+struct Scan {
+    ranges: [F32; POINTS],
+}
+
+fn scan() -> (Vec<f32>, Scan) {
+    let mut rng = rand::thread_rng();
+    let ranges: Vec<f32> = (0..POINTS).map(|_| rng.gen()).collect();
+    let mut scan = Scan {
+        ranges: [F32::ZERO; POINTS],
+    };
+    for (raw, range) in scan.ranges.iter_mut().zip(&ranges) {
+        *raw = F32::new(*range);
+    }
+    (ranges, scan)
+}
+
+/// Decoding a received scan, without any networking
+fn scan_decoding(c: &mut criterion::Criterion) {
+    let (ranges, scan) = scan();
+    let serialized = Msg::new(MsgType::Set, "scan", ranges)
+        .to_generic()
+        .unwrap()
+        .as_bytes()
+        .unwrap();
+    let raw = Msg::new(MsgType::Set, "scan", Raw(scan))
+        .to_generic()
+        .unwrap()
+        .as_bytes()
+        .unwrap();
+
+    c.bench_function("decode_scan_postcard", |b| {
+        b.iter(|| {
+            let generic: GenericMsg = postcard::from_bytes(&serialized).unwrap();
+            let msg: Msg<Vec<f32>> = generic.try_into().unwrap();
+            msg.data[POINTS - 1]
+        });
+    });
+    c.bench_function("decode_scan_raw", |b| {
+        b.iter(|| {
+            let generic: GenericMsg = postcard::from_bytes(&raw).unwrap();
+            let msg: Msg<Raw<Scan>> = generic.try_into().unwrap();
+            msg.data.0.ranges[POINTS - 1].get()
+        });
+    });
+    c.bench_function("decode_scan_view", |b| {
+        b.iter(|| {
+            let view = MsgView::<Scan>::from_bytes(&raw).unwrap();
+            view.data.ranges[POINTS - 1].get()
+        });
+    });
+}
+
+/// Requesting a scan from a Host over TCP
+fn tcp_scan_requests(c: &mut criterion::Criterion) {
+    let sc = SledConfig::new().temporary(true);
+    let mut host = HostConfig::default()
+        .with_sled_config(sc)
+        .with_tcp_config(Some(
+            meadow::host::TcpConfig::default("lo").set_max_buffer_size(16 * KB),
+        ))
+        .build()
+        .unwrap();
+    host.start().unwrap();
+    let cfg = NetworkConfig::<Blocking, Tcp>::default().set_max_buffer_size(16 * KB);
+    let (ranges, scan) = scan();
+
+    let node = NodeConfig::<Blocking, Tcp, Vec<f32>>::new("scan_postcard")
+        .with_config(cfg.clone())
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+    node.publish(ranges).unwrap();
+    c.bench_function("tcp_request_scan_postcard", |b| {
+        b.iter(|| node.request().unwrap().data[POINTS - 1]);
+    });
+
+    let node = NodeConfig::<Blocking, Tcp, Raw<Scan>>::new("scan_raw")
+        .with_config(cfg)
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+    node.publish(Raw(scan)).unwrap();
+    c.bench_function("tcp_request_scan_raw", |b| {
+        b.iter(|| node.request().unwrap().data.0.ranges[POINTS - 1].get());
+    });
+    c.bench_function("tcp_request_scan_view", |b| {
+        b.iter(|| {
+            node.request_view(|view| view.data.ranges[POINTS - 1].get())
+                .unwrap()
+        });
+    });
+
+    host.stop().unwrap();
+}
+
+criterion_group!(benches, scan_decoding, tcp_scan_requests);
+criterion_main!(benches);
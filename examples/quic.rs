@@ -8,7 +8,7 @@ fn main() -> Result<(), meadow::Error> {
 
     logging();
 
-    generate_certs(QuicCertGenConfig::default());
+    generate_certs(QuicCertGenConfig::default())?;
     let mut host: Host = HostConfig::default().build()?;
     host.start()?;
     debug!("Host should be running in the background");
@@ -43,7 +43,6 @@ fn main() -> Result<(), meadow::Error> {
 }
 
 #[cfg(not(feature = "quic"))]
-
 fn main() {
     panic!("Must enable the \"quic\" feature to run");
 }
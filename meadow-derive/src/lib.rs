@@ -0,0 +1,122 @@
+//! Derive macros for [`meadow`](https://docs.rs/meadow). Use them through `meadow::schema`
+//! rather than depending on this crate directly.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, parse_quote, Data, DeriveInput, Fields, LitStr};
+
+/// Derive `meadow::schema::Schema`, describing a type by its name and the layout of its fields.
+///
+/// The schema name defaults to the type's identifier, and can be pinned with
+/// `#[schema(name = "...")]` so that renaming the type doesn't change it. Each field is
+/// described by its own type's `Schema`, so every field type has to implement it, and
+/// changing the layout of a nested type changes the schema of the types containing it.
+#[proc_macro_derive(Schema, attributes(schema))]
+pub fn derive_schema(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn expand(mut input: DeriveInput) -> syn::Result<TokenStream2> {
+    let mut name = input.ident.to_string();
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("schema")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                name = meta.value()?.parse::<LitStr>()?.value();
+                Ok(())
+            } else {
+                Err(meta.error("expected `name = \"...\"`"))
+            }
+        })?;
+    }
+
+    let mut description = Description::default();
+    match &input.data {
+        Data::Struct(data) => {
+            description.text(format!("{} struct", name));
+            describe_fields(&mut description, &data.fields);
+        }
+        Data::Enum(data) => {
+            description.text(format!("{} enum {{ ", name));
+            for (i, variant) in data.variants.iter().enumerate() {
+                if i > 0 {
+                    description.text(", ");
+                }
+                description.text(variant.ident.to_string());
+                describe_fields(&mut description, &variant.fields);
+            }
+            description.text(" }");
+        }
+        Data::Union(_) => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "`Schema` can't be derived for unions",
+            ))
+        }
+    };
+    let parts = description.parts;
+
+    // Type parameters are described by their own schemas, like any other field
+    for param in input.generics.type_params_mut() {
+        param.bounds.push(parse_quote!(::meadow::schema::Schema));
+    }
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::meadow::schema::Schema for #ident #ty_generics #where_clause {
+            const NAME: &'static str = #name;
+            fn description() -> ::std::string::String {
+                [#(#parts),*].concat()
+            }
+        }
+    })
+}
+
+/// Pieces of a description, each an expression evaluating to a `String`
+#[derive(Default)]
+struct Description {
+    parts: Vec<TokenStream2>,
+}
+
+impl Description {
+    fn text(&mut self, text: impl Into<String>) {
+        let text = text.into();
+        self.parts.push(quote!(::std::string::String::from(#text)));
+    }
+
+    fn field(&mut self, ty: &syn::Type) {
+        self.parts
+            .push(quote!(<#ty as ::meadow::schema::Schema>::description()));
+    }
+}
+
+/// Fields in declaration order, which is also the order `postcard` encodes them in
+fn describe_fields(description: &mut Description, fields: &Fields) {
+    match fields {
+        Fields::Named(fields) => {
+            description.text(" { ");
+            for (i, f) in fields.named.iter().enumerate() {
+                let ident = f.ident.as_ref().map(|i| i.to_string()).unwrap_or_default();
+                let separator = if i > 0 { ", " } else { "" };
+                description.text(format!("{}{}: ", separator, ident));
+                description.field(&f.ty);
+            }
+            description.text(" }");
+        }
+        Fields::Unnamed(fields) => {
+            description.text("(");
+            for (i, f) in fields.unnamed.iter().enumerate() {
+                if i > 0 {
+                    description.text(", ");
+                }
+                description.field(&f.ty);
+            }
+            description.text(")");
+        }
+        Fields::Unit => {}
+    }
+}
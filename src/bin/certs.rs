@@ -1,11 +1,226 @@
+//! Small PKI tool for QUIC deployments.
+//!
+//! Creates a fleet certificate authority, issues Host and Node certificates signed by it, and
+//! writes them using the file names `QuicConfig` and the Quic `NetworkConfig` expect.
+
+#[cfg(feature = "quic")]
+use meadow::host::quic::*;
+#[cfg(feature = "quic")]
+use std::{net::IpAddr, path::PathBuf, time::Duration};
+
+#[cfg(feature = "quic")]
+const USAGE: &str = "Usage: certs [COMMAND] [OPTIONS]
+
+Commands:
+  self-signed      Self-signed Host certificate, the default when no command is given
+  ca               Create a fleet certificate authority
+  host             Issue a Host certificate signed by the fleet CA
+  node <NAME>      Issue a Node client certificate signed by the fleet CA
+  list             Show every certificate in the output directory
+  inspect <FILE>   Show the certificates in a PEM file
+
+Options:
+  --dir <DIR>      Directory certificates are written to and read from [default: target]
+  --name <NAME>    Common name of the certificate
+  --dns <NAME>     Add a DNS name the Host can be verified as [default: localhost]
+  --ip <ADDR>      Add an IP address the Host can be verified as
+  --days <DAYS>    Number of days until the certificate expires
+
+Files written to the output directory:
+  ca.pem, ca_key.pem                 Fleet CA, used by `QuicConfig::set_client_ca_path`
+                                     and `NetworkConfig::set_cert_path`
+  cert.pem, priv_key.pem             Host certificate, the `QuicConfig` defaults
+  <NAME>_cert.pem, <NAME>_key.pem    Node certificate, used by `NetworkConfig::set_client_cert`";
+
+#[cfg(feature = "quic")]
+#[derive(Debug, Default)]
+struct Args {
+    command: Option<String>,
+    operand: Option<String>,
+    dir: Option<PathBuf>,
+    name: Option<String>,
+    dns: Vec<String>,
+    ips: Vec<IpAddr>,
+    days: Option<u64>,
+}
+
 #[cfg(feature = "quic")]
-use meadow::host::quic::generate_certs;
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut parsed = Args::default();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("Missing value for {}", arg));
+        match arg.as_str() {
+            "--dir" => parsed.dir = Some(value()?.into()),
+            "--name" => parsed.name = Some(value()?),
+            "--dns" => parsed.dns.push(value()?),
+            "--ip" => {
+                let ip = value()?;
+                parsed.ips.push(
+                    ip.parse()
+                        .map_err(|_| format!("Invalid IP address {}", ip))?,
+                );
+            }
+            "--days" => {
+                let days = value()?;
+                parsed.days = Some(
+                    days.parse()
+                        .map_err(|_| format!("Invalid number of days {}", days))?,
+                );
+            }
+            flag if flag.starts_with("--") => return Err(format!("Unknown option {}", flag)),
+            _ if parsed.command.is_none() => parsed.command = Some(arg),
+            _ if parsed.operand.is_none() => parsed.operand = Some(arg),
+            _ => return Err(format!("Unexpected argument {}", arg)),
+        }
+    }
+    Ok(parsed)
+}
+
+#[cfg(feature = "quic")]
+impl Args {
+    fn dir(&self) -> PathBuf {
+        self.dir.clone().unwrap_or_else(|| PathBuf::from("target"))
+    }
+
+    /// Configuration shared by every certificate, writing to `<dir>/<cert>` and `<dir>/<key>`
+    fn cert_config(&self, cert: &str, key: &str) -> QuicCertGenConfig {
+        let mut config = QuicCertGenConfig::new()
+            .set_cert_pem_path(self.dir().join(cert))
+            .set_priv_key_pem_path(self.dir().join(key));
+        if let Some(name) = &self.name {
+            config = config.set_common_name(name);
+        }
+        if let Some(days) = self.days {
+            config = config.set_validity(Duration::from_secs(days * 24 * 60 * 60));
+        }
+        config
+    }
+
+    /// Configuration for a Host certificate, valid for the requested names
+    fn host_config(&self) -> QuicCertGenConfig {
+        let mut config = self.cert_config("cert.pem", "priv_key.pem");
+        if self.dns.is_empty() && self.ips.is_empty() {
+            config = config.add_dns_name("localhost");
+        }
+        for name in &self.dns {
+            config = config.add_dns_name(name);
+        }
+        for ip in &self.ips {
+            config = config.add_ip_address(*ip);
+        }
+        config
+    }
+}
+
+#[cfg(feature = "quic")]
+fn run(args: Args) -> Result<(), String> {
+    let dir = args.dir();
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Unable to create {:?}: {}", dir, e))?;
+    let (ca_cert, ca_key) = (dir.join("ca.pem"), dir.join("ca_key.pem"));
+
+    match args.command.as_deref().unwrap_or("self-signed") {
+        "self-signed" => {
+            generate_certs(args.host_config()).map_err(|e| e.to_string())?;
+            println!(
+                "Wrote {:?} and {:?}",
+                dir.join("cert.pem"),
+                dir.join("priv_key.pem")
+            );
+        }
+        "ca" => {
+            let mut config = args.cert_config("ca.pem", "ca_key.pem");
+            if args.name.is_none() {
+                config = config.set_common_name("meadow fleet CA");
+            }
+            generate_ca(config).map_err(|e| e.to_string())?;
+            println!("Wrote {:?} and {:?}", ca_cert, ca_key);
+        }
+        "host" => {
+            let mut config = args.host_config();
+            if args.name.is_none() {
+                config = config.set_common_name(args.dns.first().map_or("localhost", |n| n));
+            }
+            generate_signed_certs(config, &ca_cert, &ca_key).map_err(|e| e.to_string())?;
+            println!(
+                "Wrote {:?} and {:?}",
+                dir.join("cert.pem"),
+                dir.join("priv_key.pem")
+            );
+        }
+        "node" => {
+            let name = args
+                .operand
+                .clone()
+                .ok_or("The node command needs the Node's name")?;
+            let (cert, key) = (format!("{}_cert.pem", name), format!("{}_key.pem", name));
+            let mut config = args.cert_config(&cert, &key).set_client_auth(true);
+            if args.name.is_none() {
+                config = config.set_common_name(&name);
+            }
+            generate_signed_certs(config, &ca_cert, &ca_key).map_err(|e| e.to_string())?;
+            println!("Wrote {:?} and {:?}", dir.join(cert), dir.join(key));
+        }
+        "list" => {
+            let mut paths: Vec<PathBuf> = std::fs::read_dir(&dir)
+                .map_err(|e| format!("Unable to read {:?}: {}", dir, e))?
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| {
+                    path.extension().is_some_and(|ext| ext == "pem")
+                        && !path.to_string_lossy().ends_with("key.pem")
+                })
+                .collect();
+            paths.sort();
+            for path in paths {
+                print_certs(&path)?;
+            }
+        }
+        "inspect" => {
+            let path = args
+                .operand
+                .clone()
+                .ok_or("The inspect command needs a certificate file")?;
+            print_certs(&PathBuf::from(path))?;
+        }
+        command => return Err(format!("Unknown command {}", command)),
+    }
+    Ok(())
+}
+
 #[cfg(feature = "quic")]
-use meadow::host::quic::QuicCertGenConfig;
+fn print_certs(path: &PathBuf) -> Result<(), String> {
+    let infos = inspect_certs(path).map_err(|e| format!("{:?}: {}", path, e))?;
+    for info in infos {
+        println!("{}", path.display());
+        println!("\tsubject:    {}", info.subject);
+        println!("\tissuer:     {}", info.issuer);
+        if !info.subject_alt_names.is_empty() {
+            println!("\tnames:      {}", info.subject_alt_names.join(", "));
+        }
+        println!("\tvalid from: {}", info.not_before);
+        println!("\tvalid to:   {}", info.not_after);
+        if info.is_ca {
+            println!("\tcertificate authority");
+        }
+    }
+    Ok(())
+}
 
 fn main() {
     #[cfg(feature = "quic")]
-    generate_certs(QuicCertGenConfig::default());
+    {
+        let result = parse_args(std::env::args().skip(1)).and_then(|args| {
+            if args.command.as_deref() == Some("help") {
+                println!("{}", USAGE);
+                Ok(())
+            } else {
+                run(args)
+            }
+        });
+        if let Err(e) = result {
+            eprintln!("Error: {}\n\n{}", e, USAGE);
+            std::process::exit(1);
+        }
+    }
     #[cfg(not(feature = "quic"))]
     panic!("Must enable the \"quic\" feature to run");
 }
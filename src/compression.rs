@@ -0,0 +1,104 @@
+use crate::error::Error;
+
+use serde::{Deserialize, Serialize};
+
+/// Compression applied to a message's data payload, recorded in every `GenericMsg`.
+///
+/// The Host stores payloads as they were received, so compressed data also takes less space
+/// on disk, and Nodes decompress it transparently when it's requested or subscribed to.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum Compression {
+    /// Data is sent as-is, and the default
+    #[default]
+    None,
+    /// LZ4 block format, prefixed by the uncompressed length as a little-endian `u32`
+    Lz4,
+}
+
+/// Largest payload `Compression::decompress` will expand data to, so a corrupt or malicious
+/// length prefix can't make it allocate without bound
+pub const MAX_DECOMPRESSED_SIZE: usize = 64 * 1024 * 1024;
+
+impl Compression {
+    /// Compress `data`
+    pub fn compress(&self, data: &[u8]) -> Vec<u8> {
+        match self {
+            Compression::None => data.to_vec(),
+            Compression::Lz4 => lz4_flex::block::compress_prepend_size(data),
+        }
+    }
+
+    /// Decompress data that was compressed with this algorithm
+    pub fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, Error> {
+        match self {
+            Compression::None => Ok(data.to_vec()),
+            Compression::Lz4 => {
+                if data.len() < 4 {
+                    return Err(Error::Decompression);
+                }
+                let len = u32::from_le_bytes([data[0], data[1], data[2], data[3]]) as usize;
+                if len > MAX_DECOMPRESSED_SIZE {
+                    return Err(Error::Decompression);
+                }
+                lz4_flex::block::decompress_size_prepended(data).map_err(|_| Error::Decompression)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lz4_round_trip() {
+        let mut inputs: Vec<Vec<u8>> = vec![
+            Vec::new(),
+            b"pose".to_vec(),
+            b"abcabcabcabcabcabcabcabcabcabcabc".to_vec(),
+            vec![0; 100_000],
+        ];
+        // Pseudo-random, incompressible bytes
+        let mut x: u32 = 1;
+        inputs.push(
+            (0..5000)
+                .map(|_| {
+                    x = x.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                    (x >> 16) as u8
+                })
+                .collect(),
+        );
+        for input in inputs {
+            let compressed = Compression::Lz4.compress(&input);
+            assert_eq!(Compression::Lz4.decompress(&compressed).unwrap(), input);
+        }
+        assert!(Compression::Lz4.compress(&[0; 100_000]).len() < 500);
+
+        // Short inputs are all literals: length, then a token of 4 literals
+        let golden: &[u8] = &[4, 0, 0, 0, 0x40, b'p', b'o', b's', b'e'];
+        assert_eq!(Compression::Lz4.compress(b"pose"), golden);
+        // 15 `a`s: one literal, a match of 9 at offset 1, then the 5 final literals
+        let golden: &[u8] = &[
+            15, 0, 0, 0, 0x15, b'a', 1, 0, 0x50, b'a', b'a', b'a', b'a', b'a',
+        ];
+        assert_eq!(Compression::Lz4.decompress(golden).unwrap(), [b'a'; 15]);
+    }
+
+    #[test]
+    fn corrupt_lz4() {
+        let compressed = Compression::Lz4.compress(&[7; 1000]);
+        // Truncated, lying about its length, or pointing before the start of the data
+        for corrupt in [
+            &compressed[..compressed.len() - 1],
+            &compressed[..3],
+            &[0xff, 0xff, 0xff, 0xff, 0x00][..],
+            &[8, 0, 0, 0, 0x04, 9, 0][..],
+            &(MAX_DECOMPRESSED_SIZE as u32 + 1).to_le_bytes(),
+        ] {
+            assert_eq!(
+                Compression::Lz4.decompress(corrupt),
+                Err(Error::Decompression)
+            );
+        }
+    }
+}
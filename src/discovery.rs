@@ -0,0 +1,274 @@
+use crate::msg::{negotiate_version, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION};
+use crate::Error;
+
+use serde::{Deserialize, Serialize};
+use socket2::{Domain, Protocol, Socket, Type};
+use tokio::net::UdpSocket;
+use tokio::time::{sleep, timeout, Duration, Instant};
+use tracing::*;
+
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::str::FromStr;
+
+/// Default multicast group on which Hosts announce themselves
+pub const DEFAULT_GROUP: SocketAddrV4 = SocketAddrV4::new(Ipv4Addr::new(239, 255, 77, 77), 25_077);
+
+/// Transports a Host can advertise or accept connections over
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Transport {
+    Tcp,
+    Udp,
+    Quic,
+    /// Unix domain sockets, which are never announced
+    Uds,
+    /// Shared memory, which is never announced
+    Shm,
+    /// Channels within the Host's process, which are never announced
+    InProcess,
+}
+
+impl fmt::Display for Transport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Transport::Tcp => "tcp",
+            Transport::Udp => "udp",
+            Transport::Quic => "quic",
+            Transport::Uds => "uds",
+            Transport::Shm => "shm",
+            Transport::InProcess => "in-process",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Parses transport names as written in configuration files, e.g. `"quic"` or `"TCP"`
+impl FromStr for Transport {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "tcp" => Ok(Transport::Tcp),
+            "udp" => Ok(Transport::Udp),
+            "quic" => Ok(Transport::Quic),
+            "uds" => Ok(Transport::Uds),
+            "shm" => Ok(Transport::Shm),
+            "in-process" | "inprocess" => Ok(Transport::InProcess),
+            _ => Err(format!("unknown transport `{}`", s)),
+        }
+    }
+}
+
+/// Address at which a Host accepts connections over a given transport
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct HostEndpoint {
+    pub transport: Transport,
+    /// Listening address; an unspecified IP means the Host is reachable at the announcement's source address
+    pub addr: SocketAddr,
+}
+
+/// Datagram periodically multicast by a Host with discovery enabled
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Announcement {
+    pub version: u16,
+    pub name: String,
+    pub endpoints: Vec<HostEndpoint>,
+}
+
+/// Host-side configuration for announcing itself on a multicast group
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveryConfig {
+    /// Name Nodes use to find this Host
+    pub name: String,
+    pub group: SocketAddrV4,
+    /// Address of the local interface announcements are sent from
+    pub interface: Ipv4Addr,
+    /// Time between announcements
+    pub period: Duration,
+    pub ttl: u32,
+}
+
+impl DiscoveryConfig {
+    /// Announce the Host under `name` on the default multicast group
+    pub fn new(name: impl Into<String>) -> Self {
+        DiscoveryConfig {
+            name: name.into(),
+            group: DEFAULT_GROUP,
+            interface: Ipv4Addr::UNSPECIFIED,
+            period: Duration::from_millis(500),
+            ttl: 1,
+        }
+    }
+
+    /// Set the multicast group announcements are sent to
+    pub fn set_group(mut self, group: SocketAddrV4) -> Self {
+        self.group = group;
+        self
+    }
+
+    /// Set the address of the local interface to announce on, e.g. `127.0.0.1` for loopback
+    pub fn set_interface(mut self, interface: Ipv4Addr) -> Self {
+        self.interface = interface;
+        self
+    }
+
+    /// Set the time between announcements
+    pub fn set_period(mut self, period: Duration) -> Self {
+        self.period = period;
+        self
+    }
+
+    /// Set the multicast TTL, i.e. how many routers announcements may cross
+    pub fn set_ttl(mut self, ttl: u32) -> Self {
+        self.ttl = ttl;
+        self
+    }
+}
+
+/// Node-side configuration for locating a Host by name instead of address
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Discovery {
+    /// Name of the Host to connect to
+    pub host_name: String,
+    pub group: SocketAddrV4,
+    /// Address of the local interface on which to listen for announcements
+    pub interface: Ipv4Addr,
+    /// How long to wait for a matching announcement
+    pub timeout: Duration,
+}
+
+impl Discovery {
+    /// Look for a Host announcing itself as `host_name` on the default multicast group
+    pub fn new(host_name: impl Into<String>) -> Self {
+        Discovery {
+            host_name: host_name.into(),
+            group: DEFAULT_GROUP,
+            interface: Ipv4Addr::UNSPECIFIED,
+            timeout: Duration::from_secs(5),
+        }
+    }
+
+    /// Set the multicast group to listen on
+    pub fn set_group(mut self, group: SocketAddrV4) -> Self {
+        self.group = group;
+        self
+    }
+
+    /// Set the address of the local interface to listen on, e.g. `127.0.0.1` for loopback
+    pub fn set_interface(mut self, interface: Ipv4Addr) -> Self {
+        self.interface = interface;
+        self
+    }
+
+    /// Set how long to wait for a matching announcement
+    pub fn set_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+/// Periodically multicast `announcement` until the task is aborted
+pub(crate) async fn announce(cfg: DiscoveryConfig, announcement: Announcement) {
+    let bytes = match postcard::to_allocvec(&announcement) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            error!("Unable to encode discovery announcement: {}", e);
+            return;
+        }
+    };
+    let socket = match announce_socket(&cfg) {
+        Ok(socket) => socket,
+        Err(e) => {
+            error!("Unable to open discovery socket: {}", e);
+            return;
+        }
+    };
+
+    loop {
+        if let Err(e) = socket.send_to(&bytes, cfg.group).await {
+            warn!("Unable to send discovery announcement: {}", e);
+        }
+        sleep(cfg.period).await;
+    }
+}
+
+fn announce_socket(cfg: &DiscoveryConfig) -> Result<UdpSocket, Error> {
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+    socket.set_multicast_if_v4(&cfg.interface)?;
+    socket.set_multicast_loop_v4(true)?;
+    socket.set_multicast_ttl_v4(cfg.ttl)?;
+    socket.set_nonblocking(true)?;
+    socket.bind(&SocketAddr::new(IpAddr::V4(cfg.interface), 0).into())?;
+    Ok(UdpSocket::from_std(socket.into())?)
+}
+
+fn listen_socket(discovery: &Discovery) -> Result<UdpSocket, Error> {
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+    // Several Nodes on one machine may be listening for announcements at once
+    socket.set_reuse_address(true)?;
+    socket.set_nonblocking(true)?;
+    let bind_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), discovery.group.port());
+    socket.bind(&bind_addr.into())?;
+    socket.join_multicast_v4(discovery.group.ip(), &discovery.interface)?;
+    Ok(UdpSocket::from_std(socket.into())?)
+}
+
+/// Wait for the named Host to announce itself, returning its address for `transport`
+pub(crate) async fn discover(
+    discovery: &Discovery,
+    transport: Transport,
+) -> Result<SocketAddr, Error> {
+    let socket = listen_socket(discovery)?;
+    let deadline = Instant::now() + discovery.timeout;
+    let mut buf = vec![0u8; 1024];
+
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let (n, src) = match timeout(remaining, socket.recv_from(&mut buf)).await {
+            Ok(result) => result?,
+            Err(_elapsed) => return Err(Error::HostNotDiscovered(discovery.host_name.clone())),
+        };
+        let announcement: Announcement = match postcard::from_bytes(&buf[..n]) {
+            Ok(announcement) => announcement,
+            Err(e) => {
+                debug!(
+                    "Ignoring malformed discovery announcement from {}: {}",
+                    src, e
+                );
+                continue;
+            }
+        };
+        if announcement.name != discovery.host_name {
+            continue;
+        }
+        if negotiate_version(announcement.version, announcement.version).is_none() {
+            warn!(
+                "Host {} speaks protocol version {}, expected {} through {}",
+                announcement.name, announcement.version, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION
+            );
+            continue;
+        }
+
+        match announcement
+            .endpoints
+            .iter()
+            .find(|endpoint| endpoint.transport == transport)
+        {
+            Some(endpoint) => {
+                let mut addr = endpoint.addr;
+                if addr.ip().is_unspecified() {
+                    addr.set_ip(src.ip());
+                }
+                debug!("Discovered Host {} at {}", announcement.name, addr);
+                return Ok(addr);
+            }
+            None => {
+                warn!(
+                    "Host {} doesn't offer a {:?} endpoint",
+                    announcement.name, transport
+                );
+                return Err(Error::HostNotDiscovered(discovery.host_name.clone()));
+            }
+        }
+    }
+}
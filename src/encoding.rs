@@ -0,0 +1,110 @@
+use crate::error::Error;
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// Format a message's data payload is serialized in, recorded in every `GenericMsg`.
+///
+/// The rest of the message is always `postcard`-encoded (see `design/wire_format.md`), so any
+/// Host or tool can read the header and decode the payload in whichever format it names.
+/// Formats other than `Postcard` need their feature enabled in every binary that decodes them;
+/// the others return `Error::UnsupportedEncoding`.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    /// Compact binary format, and the default
+    #[default]
+    Postcard,
+    /// JSON text, readable from web dashboards and most languages (`json` feature)
+    Json,
+    /// Concise Binary Object Representation, per RFC 8949 (`cbor` feature)
+    Cbor,
+    /// MessagePack, with structs as maps keyed by field name (`msgpack` feature)
+    MessagePack,
+    /// `bincode`'s fixed-width binary format, for Rust peers that already speak it (`bincode` feature)
+    Bincode,
+}
+
+impl Encoding {
+    /// Serialize `data` in this format
+    pub fn encode<T: Serialize>(&self, data: &T) -> Result<Vec<u8>, Error> {
+        match self {
+            Encoding::Postcard => Ok(postcard::to_allocvec(data)?),
+            #[cfg(feature = "json")]
+            Encoding::Json => serde_json::to_vec(data).map_err(serialization),
+            #[cfg(feature = "cbor")]
+            Encoding::Cbor => {
+                let mut bytes = Vec::new();
+                ciborium::into_writer(data, &mut bytes).map_err(serialization)?;
+                Ok(bytes)
+            }
+            #[cfg(feature = "msgpack")]
+            Encoding::MessagePack => rmp_serde::to_vec_named(data).map_err(serialization),
+            #[cfg(feature = "bincode")]
+            Encoding::Bincode => bincode::serialize(data).map_err(serialization),
+            #[allow(unreachable_patterns)]
+            unsupported => Err(Error::UnsupportedEncoding(*unsupported)),
+        }
+    }
+
+    /// Deserialize data that was serialized in this format
+    pub fn decode<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, Error> {
+        match self {
+            Encoding::Postcard => Ok(postcard::from_bytes(bytes)?),
+            #[cfg(feature = "json")]
+            Encoding::Json => serde_json::from_slice(bytes).map_err(serialization),
+            #[cfg(feature = "cbor")]
+            Encoding::Cbor => ciborium::from_reader(bytes).map_err(serialization),
+            #[cfg(feature = "msgpack")]
+            Encoding::MessagePack => rmp_serde::from_slice(bytes).map_err(serialization),
+            #[cfg(feature = "bincode")]
+            Encoding::Bincode => bincode::deserialize(bytes).map_err(serialization),
+            #[allow(unreachable_patterns)]
+            unsupported => Err(Error::UnsupportedEncoding(*unsupported)),
+        }
+    }
+}
+
+#[cfg(any(
+    feature = "json",
+    feature = "cbor",
+    feature = "msgpack",
+    feature = "bincode"
+))]
+fn serialization(e: impl std::fmt::Display) -> Error {
+    Error::Serialization(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let data = (1u8, "pose".to_string(), vec![0.5f32]);
+        let encodings = [
+            Encoding::Postcard,
+            Encoding::Json,
+            Encoding::Cbor,
+            Encoding::MessagePack,
+            Encoding::Bincode,
+        ];
+        for encoding in encodings {
+            match encoding.encode(&data) {
+                Ok(bytes) => assert_eq!(
+                    encoding.decode::<(u8, String, Vec<f32>)>(&bytes),
+                    Ok(data.clone())
+                ),
+                Err(e) => assert_eq!(e, Error::UnsupportedEncoding(encoding)),
+            }
+        }
+        #[cfg(feature = "json")]
+        assert_eq!(
+            Encoding::Json.encode(&data).unwrap(),
+            br#"[1,"pose",[0.5]]"#
+        );
+        #[cfg(feature = "msgpack")]
+        assert_eq!(
+            Encoding::MessagePack.encode(&data).unwrap(),
+            [0x93, 0x01, 0xa4, b'p', b'o', b's', b'e', 0x91, 0xca, 0x3f, 0x00, 0x00, 0x00]
+        );
+    }
+}
@@ -18,6 +18,27 @@ pub enum HostError {
     /// Topic does not exist on Host
     #[error("Topic does not exist")]
     NonExistentTopic,
+    /// The Node isn't allowed to perform the operation on the topic
+    #[error("Not authorized to perform the operation on this topic")]
+    Unauthorized,
+    /// The Host refused the Node's handshake
+    #[error("Handshake rejected: {0}")]
+    HandshakeRejected(String),
+    /// The Node and Host have no protocol version in common
+    #[error("No common protocol version, this side speaks versions {min} through {max}")]
+    IncompatibleVersion { min: u16, max: u16 },
+    /// The data doesn't have the type the topic holds
+    #[error("Topic holds `{expected}`, not `{found}`")]
+    TypeMismatch { expected: String, found: String },
+    /// The Host is shutting down, and has closed the connection
+    #[error("The Host is shutting down")]
+    ShuttingDown,
+    /// The topic is maintained by the Host, and can't be published to
+    #[error("Topic is maintained by the Host and can't be published to")]
+    ReadOnlyTopic,
+    /// The Host doesn't know the Node, e.g. because it has restarted since the Node's handshake
+    #[error("The Host requires a handshake first")]
+    HandshakeRequired,
 }
 
 /// Enum for successful/failed Host operations
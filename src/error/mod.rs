@@ -48,10 +48,30 @@ pub enum Error {
     /// `TcpStream` connection attempt failure
     #[error("TcpStream connection attempt failure")]
     StreamConnection,
-    /// Transparent QUIC-related errors
-    #[cfg(feature = "quic")]
-    #[error(transparent)]
-    Quic(#[from] crate::error::quic::Quic),
+    /// No Unix domain socket path was provided
+    #[error("No Unix domain socket path was provided")]
+    NoSocketPath,
+    /// No handle to an in-process Host was provided
+    #[error("No handle to an in-process Host was provided")]
+    NoHostHandle,
+    /// No Host with the requested name announced itself before the discovery timeout
+    #[error("Host `{0}` wasn't discovered")]
+    HostNotDiscovered(String),
+    /// Shared-memory ring buffer could not be mapped
+    #[error("Shared-memory ring buffer could not be mapped")]
+    ShmMapping,
+    /// Shared-memory ring buffer doesn't have room for the message
+    #[error("Shared-memory ring buffer doesn't have room for the message")]
+    ShmFull,
+    /// Pre-shared key isn't 64 hexadecimal characters
+    #[error("Pre-shared key isn't 64 hexadecimal characters")]
+    InvalidPreSharedKey,
+    /// Datagram failed authentication with the pre-shared key or was a replay
+    #[error("Datagram failed authentication or was a replay")]
+    DatagramRejected,
+    /// The Host never answered a UDP Node's handshake
+    #[error("No reply to the handshake was received from the Host")]
+    HandshakeTimeout,
     /// Transparent `std::io` error
     #[error("std::io::Error-derived error")]
     Io {
@@ -68,6 +88,30 @@ pub enum Error {
     NoNthValue,
     #[error("Undefined error")]
     Undefined,
+    /// Data is in an encoding this build wasn't compiled with the feature for
+    #[error("This build doesn't support the {0:?} encoding")]
+    UnsupportedEncoding(crate::encoding::Encoding),
+    /// Data couldn't be serialized or deserialized in an encoding other than `postcard`
+    #[error("Serialization error: {0}")]
+    Serialization(String),
+    /// Compressed data was corrupt
+    #[error("Compressed data was corrupt")]
+    Decompression,
+    /// A transport a Node was configured to try isn't compiled into this build
+    #[error("This build doesn't support the {0:?} transport")]
+    UnsupportedTransport(crate::discovery::Transport),
+    /// None of a Node's transports could reach the Host, listing why each attempt failed
+    #[error("No transport could reach the Host: {0:?}")]
+    NoTransport(Vec<(crate::discovery::Transport, Error)>),
+    /// Shared-memory ring buffer's cursors or record lengths are out of bounds
+    #[error("Shared-memory ring buffer is corrupt")]
+    ShmCorrupted,
+    // Variants that only exist with a feature enabled must stay last, so that the wire index
+    // of every other variant is the same whichever features a build has
+    /// Transparent QUIC-related errors
+    #[cfg(feature = "quic")]
+    #[error(transparent)]
+    Quic(#[from] crate::error::quic::Quic),
 }
 
 /// This is the Result type used by meadow.
@@ -121,6 +165,7 @@ impl From<std::io::Error> for Error {
     }
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct IoError {
     kind: String,
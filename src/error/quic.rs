@@ -16,6 +16,18 @@ pub enum Quic {
     /// No certificate path was provided
     #[error("No certificate path was provided")]
     NoProvidedCertPath,
+    /// No server name was provided to verify the Host's certificate against
+    #[error("No server name was provided")]
+    NoProvidedServerName,
+    /// The server name isn't a valid DNS name
+    #[error("The server name isn't a valid DNS name")]
+    InvalidServerName,
+    /// Error generating or signing a certificate
+    #[error("Error generating or signing a certificate")]
+    CertGen,
+    /// Certificate couldn't be parsed
+    #[error("Certificate couldn't be parsed")]
+    InvalidCert,
     /// Transparent `quin::ConnectError`
     #[error("`quinn::ConnectError`-based error")]
     ConnectError,
@@ -112,3 +124,19 @@ impl From<rustls::Error> for crate::Error {
         Error::Quic(error.into())
     }
 }
+
+// ===== rcgen::RcgenError =====
+
+impl From<rcgen::RcgenError> for Quic {
+    fn from(error: rcgen::RcgenError) -> Self {
+        // TO_DO: This could be more fleshed out
+        Quic::CertGen
+    }
+}
+
+impl From<rcgen::RcgenError> for crate::Error {
+    fn from(error: rcgen::RcgenError) -> Self {
+        // TO_DO: This could be more fleshed out
+        Error::Quic(error.into())
+    }
+}
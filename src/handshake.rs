@@ -0,0 +1,236 @@
+use crate::error::{Error, HostError};
+use crate::msg::{
+    negotiate_version, GenericMsg, Msg, MsgType, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION,
+};
+use crate::schema::SchemaDescription;
+
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashMap;
+use std::convert::TryInto;
+use tracing::*;
+
+/// Optional capabilities a Node has enabled on its connection
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Feature {
+    /// Traffic is encrypted, by TLS, QUIC, or a pre-shared key
+    Encryption,
+    /// The Node authenticates itself with a client certificate
+    ClientCertificate,
+    /// Datagrams are sealed with a pre-shared key
+    PreSharedKey,
+}
+
+/// First message a Node sends after connecting, identifying itself to the Host.
+///
+/// The versions come first, so that any later protocol can still read them and reject the
+/// Node with a clear error rather than failing to decode the rest.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Handshake {
+    /// Newest version of the Host <=> Node protocol the Node speaks, or once accepted, the
+    /// version the Host chose
+    pub version: u16,
+    /// Oldest version of the protocol the Node can still speak
+    pub min_version: u16,
+    /// Name the Node is known by, which defaults to its topic
+    pub name: String,
+    pub features: Vec<Feature>,
+    /// Secret proving the Node may use `name`, checked against the Host's registered tokens
+    pub token: Option<String>,
+    /// Schema of the data the Node publishes and requests, if it has registered one, which the
+    /// Host adds to its schema registry
+    pub schema: Option<SchemaDescription>,
+}
+
+// Keep the token out of logs
+impl std::fmt::Debug for Handshake {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Handshake")
+            .field("version", &self.version)
+            .field("min_version", &self.min_version)
+            .field("name", &self.name)
+            .field("features", &self.features)
+            .field("token", &self.token.as_ref().map(|_| ".."))
+            .field("schema", &self.schema)
+            .finish()
+    }
+}
+
+impl Handshake {
+    /// Handshake for a Node called `name`, speaking every protocol version this build supports
+    pub fn new(name: impl Into<String>) -> Self {
+        Handshake {
+            version: PROTOCOL_VERSION,
+            min_version: MIN_PROTOCOL_VERSION,
+            name: name.into(),
+            features: Vec::new(),
+            token: None,
+            schema: None,
+        }
+    }
+
+    /// Wrap the handshake in the `GenericMsg` sent to the Host
+    pub fn to_generic(&self) -> Result<GenericMsg, Error> {
+        Msg::new(MsgType::Handshake, "", self.clone()).to_generic()
+    }
+
+    /// Unwrap a handshake from the first `GenericMsg` a Node sent
+    pub fn from_generic(msg: GenericMsg) -> Result<Handshake, Error> {
+        if msg.msg_type != MsgType::Handshake {
+            return Err(rejected("the first message wasn't a handshake"));
+        }
+        let msg: Msg<Handshake> = msg.try_into()?;
+        Ok(msg.data)
+    }
+
+    /// Check the handshake against the Host's requirements, dropping the token once verified
+    pub(crate) fn validate(
+        mut self,
+        max_name_size: usize,
+        tokens: &HashMap<String, String>,
+    ) -> Result<Handshake, Error> {
+        self.version = match negotiate_version(self.min_version, self.version) {
+            Some(version) => version,
+            None => return Err(incompatible()),
+        };
+        if self.name.is_empty() || self.name.len() > max_name_size {
+            return Err(rejected(format!(
+                "name must be between 1 and {} bytes",
+                max_name_size
+            )));
+        }
+        // Once any tokens are registered, every Node must present the one for its name
+        if !tokens.is_empty() {
+            let expected = tokens.get(&self.name);
+            if expected.is_none() || expected != self.token.as_ref() {
+                return Err(rejected(format!("invalid token for \"{}\"", self.name)));
+            }
+        }
+        self.token = None;
+        Ok(self)
+    }
+
+    /// Add the Node's schema, if it sent one, to the Host's schema registry
+    pub(crate) fn register_schema(&self, db: &sled::Db) {
+        if let Some(schema) = &self.schema {
+            if let Err(e) = crate::schema::record(db, schema) {
+                warn!("Unable to register schema {}: {}", schema.id(), e);
+            }
+        }
+    }
+}
+
+/// Host side of the handshake: decode and validate the first message a Node sent
+pub(crate) fn accept(
+    bytes: &[u8],
+    max_name_size: usize,
+    tokens: &HashMap<String, String>,
+) -> Result<Handshake, Error> {
+    let msg: GenericMsg = postcard::from_bytes(bytes)?;
+    Handshake::from_generic(msg)?.validate(max_name_size, tokens)
+}
+
+/// Reply telling the Node the protocol version the Host chose, or why its handshake was rejected
+pub(crate) fn reply(result: &Result<Handshake, Error>) -> Result<GenericMsg, Error> {
+    match result {
+        Ok(handshake) => Msg::new(MsgType::Handshake, "", handshake.version).to_generic(),
+        Err(e) => Ok(GenericMsg::result(Err(e.clone()))),
+    }
+}
+
+/// Node side of the handshake: check the Host's reply, returning the protocol version to speak
+pub(crate) fn confirm(msg: GenericMsg) -> Result<u16, Error> {
+    match msg.msg_type {
+        MsgType::Handshake => {
+            let msg: Msg<u16> = msg.try_into()?;
+            match negotiate_version(msg.data, msg.data) {
+                Some(version) => Ok(version),
+                None => Err(incompatible()),
+            }
+        }
+        MsgType::Result(Err(e)) => Err(e),
+        _ => Err(rejected("the Host didn't acknowledge the handshake")),
+    }
+}
+
+/// Error for a peer that speaks no protocol version in common with this build
+fn incompatible() -> Error {
+    Error::HostOperation(HostError::IncompatibleVersion {
+        min: MIN_PROTOCOL_VERSION,
+        max: PROTOCOL_VERSION,
+    })
+}
+
+pub(crate) fn rejected(reason: impl Into<String>) -> Error {
+    Error::HostOperation(HostError::HandshakeRejected(reason.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_handshakes() {
+        let tokens: HashMap<String, String> = [("pose".to_string(), "secret".to_string())].into();
+        let handshake = Handshake {
+            token: Some("secret".into()),
+            ..Handshake::new("pose")
+        };
+        let generic = handshake.to_generic().unwrap();
+        let validated = Handshake::from_generic(generic)
+            .unwrap()
+            .validate(100, &tokens)
+            .unwrap();
+        assert_eq!(validated.name, "pose");
+        assert_eq!(validated.token, None);
+
+        assert!(Handshake::new("pose").validate(100, &tokens).is_err());
+        assert!(Handshake::new("imu").validate(100, &tokens).is_err());
+        assert!(Handshake::new("pose")
+            .validate(100, &HashMap::new())
+            .is_ok());
+        assert!(Handshake::new("pose").validate(2, &HashMap::new()).is_err());
+        // Newer Nodes that can still speak our version are accepted with it
+        let newer = Handshake {
+            version: PROTOCOL_VERSION + 1,
+            ..Handshake::new("pose")
+        };
+        let validated = newer.validate(100, &HashMap::new()).unwrap();
+        assert_eq!(validated.version, PROTOCOL_VERSION);
+        let incompatible = Handshake {
+            version: PROTOCOL_VERSION + 2,
+            min_version: PROTOCOL_VERSION + 1,
+            ..Handshake::new("pose")
+        };
+        assert_eq!(
+            incompatible.validate(100, &HashMap::new()),
+            Err(Error::HostOperation(HostError::IncompatibleVersion {
+                min: MIN_PROTOCOL_VERSION,
+                max: PROTOCOL_VERSION,
+            }))
+        );
+        assert!(Handshake::from_generic(GenericMsg::topics()).is_err());
+
+        let bytes = Handshake::new("pose")
+            .to_generic()
+            .unwrap()
+            .as_bytes()
+            .unwrap();
+        let result = accept(&bytes, 100, &tokens);
+        assert!(confirm(reply(&result).unwrap()).is_err());
+        let result = accept(&bytes, 100, &HashMap::new());
+        assert_eq!(confirm(reply(&result).unwrap()), Ok(PROTOCOL_VERSION));
+    }
+
+    #[test]
+    fn handshake_encoding() {
+        let handshake = Handshake {
+            features: vec![Feature::Encryption],
+            token: Some("t".into()),
+            ..Handshake::new("pose")
+        };
+        // version, min_version, name, features, token, schema
+        let golden: &[u8] = &[1, 1, 4, b'p', b'o', b's', b'e', 1, 0, 1, 1, b't', 0];
+        assert_eq!(postcard::to_allocvec(&handshake).unwrap(), golden);
+    }
+}
@@ -0,0 +1,188 @@
+//! Heartbeats, which let the Host and its Nodes notice when the other side has gone quiet.
+//!
+//! Over TCP and UDP, Nodes send the Host a `MsgType::Heartbeat` every `interval`, which the
+//! Host answers in kind. The Host drops connections it hasn't heard anything from for longer
+//! than `timeout`, along with their subscriptions, and Nodes report the Host as lost once its
+//! answers stop. QUIC connections use QUIC's own keep-alives and idle timeout, configured from
+//! the same `HeartbeatConfig`. Local transports learn that the other side exited from the
+//! operating system instead, so don't send heartbeats.
+
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+use tokio::time::{interval_at, timeout, Duration, Instant, Interval, MissedTickBehavior};
+use tracing::*;
+
+use crate::error::Error;
+
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex as StdMutex};
+
+/// How often heartbeats are sent, and how long a peer may stay quiet before it's considered lost.
+///
+/// `timeout` should span several intervals, so a single late heartbeat isn't mistaken for a
+/// lost peer, and Nodes should send heartbeats at least as often as their Host expects them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HeartbeatConfig {
+    /// Time between heartbeats
+    pub interval: Duration,
+    /// Time without hearing from a peer after which it's considered lost
+    pub timeout: Duration,
+}
+
+impl Default for HeartbeatConfig {
+    fn default() -> Self {
+        HeartbeatConfig {
+            interval: Duration::from_secs(1),
+            timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+impl HeartbeatConfig {
+    pub fn new(interval: Duration, timeout: Duration) -> Self {
+        HeartbeatConfig { interval, timeout }
+    }
+
+    /// Liveness of a peer last heard from `silence` ago
+    pub fn liveness(&self, silence: Duration) -> Liveness {
+        if silence > self.timeout {
+            Liveness::Dead
+        } else if silence > self.interval * 2 {
+            Liveness::Late
+        } else {
+            Liveness::Alive
+        }
+    }
+
+    /// QUIC transport configuration that keeps connections alive at the heartbeat interval and
+    /// closes them once the peer has been quiet for the timeout
+    #[cfg(feature = "quic")]
+    pub(crate) fn quic_transport(&self) -> quinn::TransportConfig {
+        let mut transport = quinn::TransportConfig::default();
+        transport.keep_alive_interval(Some(self.interval));
+        transport.max_idle_timeout(std::convert::TryFrom::try_from(self.timeout).ok());
+        transport
+    }
+}
+
+/// How recently a peer was heard from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Liveness {
+    /// Heard from within the last two heartbeat intervals
+    Alive,
+    /// Has missed at least one heartbeat, but not yet timed out
+    Late,
+    /// Timed out or closed the connection
+    Dead,
+}
+
+/// When a Node last heard from its Host
+#[derive(Debug)]
+pub(crate) struct Pulse {
+    /// Heartbeats the Node sends, without which the Host is assumed alive until it hangs up
+    heartbeat: Option<HeartbeatConfig>,
+    last: StdMutex<Instant>,
+    lost: AtomicBool,
+}
+
+impl Default for Pulse {
+    fn default() -> Self {
+        Pulse::new(None)
+    }
+}
+
+impl Pulse {
+    pub(crate) fn new(heartbeat: Option<HeartbeatConfig>) -> Self {
+        Pulse {
+            heartbeat,
+            last: StdMutex::new(Instant::now()),
+            lost: AtomicBool::new(false),
+        }
+    }
+
+    /// Record hearing from the Host
+    pub(crate) fn beat(&self) {
+        if let Ok(mut last) = self.last.lock() {
+            *last = Instant::now();
+        }
+    }
+
+    /// Record that the connection to the Host has closed
+    pub(crate) fn lose(&self) {
+        self.lost.store(true, Ordering::Relaxed);
+    }
+
+    pub(crate) fn liveness(&self) -> Liveness {
+        if self.lost.load(Ordering::Relaxed) {
+            return Liveness::Dead;
+        }
+        match (&self.heartbeat, self.last.lock()) {
+            (Some(heartbeat), Ok(last)) => heartbeat.liveness(last.elapsed()),
+            _ => Liveness::Alive,
+        }
+    }
+}
+
+/// Task sending a Node's heartbeats, which stops along with the Node
+#[derive(Debug)]
+pub(crate) struct HeartbeatTask(JoinHandle<()>);
+
+impl From<JoinHandle<()>> for HeartbeatTask {
+    fn from(handle: JoinHandle<()>) -> Self {
+        HeartbeatTask(handle)
+    }
+}
+
+impl Drop for HeartbeatTask {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Ticks once every heartbeat interval, or never when heartbeats are disabled
+pub(crate) struct Ticker(Option<Interval>);
+
+impl Ticker {
+    pub(crate) fn new(heartbeat: Option<HeartbeatConfig>) -> Self {
+        Ticker(heartbeat.map(|heartbeat| {
+            let mut interval = interval_at(Instant::now() + heartbeat.interval, heartbeat.interval);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            interval
+        }))
+    }
+
+    pub(crate) async fn tick(&mut self) {
+        match &mut self.0 {
+            Some(interval) => {
+                interval.tick().await;
+            }
+            None => std::future::pending().await,
+        }
+    }
+}
+
+/// Run `exchange`, which sends a heartbeat and waits for the Host's answer, every interval.
+///
+/// Each answer arriving within the timeout is recorded in `pulse`, and the Host is lost once
+/// the connection closes.
+pub(crate) async fn run<F, Fut>(heartbeat: HeartbeatConfig, pulse: Arc<Pulse>, mut exchange: F)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<(), Error>>,
+{
+    let mut ticker = Ticker::new(Some(heartbeat));
+    loop {
+        ticker.tick().await;
+        match timeout(heartbeat.timeout, exchange()).await {
+            Ok(Ok(())) => pulse.beat(),
+            // The Host has hung up
+            Ok(Err(Error::AccessStream)) => {
+                pulse.lose();
+                break;
+            }
+            Ok(Err(e)) => debug!("Unable to exchange heartbeats with the Host: {}", e),
+            Err(_elapsed) => debug!("The Host didn't answer a heartbeat"),
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use crate::error::{Error, HostError};
+use crate::msg::{GenericMsg, MsgType};
+
+use tracing::*;
+
+use std::sync::Arc;
+
+/// Operations a Node can be allowed to perform on a topic
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    /// `Set` a topic's value
+    Publish,
+    /// `Get` a topic's current or n'th previous value
+    Request,
+    /// Have a topic's value streamed back periodically
+    Subscribe,
+}
+
+impl Operation {
+    /// Operation a message asks the Host to perform, if any needs authorizing
+    fn of(msg_type: &MsgType) -> Option<Operation> {
+        match msg_type {
+            MsgType::Set => Some(Operation::Publish),
+            MsgType::Get | MsgType::GetNth(_) => Some(Operation::Request),
+            MsgType::Subscribe => Some(Operation::Subscribe),
+            // Listing topic names and acknowledgements don't touch any topic's data
+            MsgType::Topics | MsgType::Result(_) | MsgType::Handshake | MsgType::Heartbeat => None,
+        }
+    }
+}
+
+/// A single grant of one operation on matching topics to matching Nodes
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Rule {
+    identity: String,
+    operation: Operation,
+    topic: String,
+}
+
+/// Host-side access control list, checked before every operation a Node requests.
+///
+/// Anything not explicitly allowed is denied. Identity and topic patterns may use `*` to
+/// match any run of characters, e.g. `allow_publish("controller", "cmd/*")`.
+///
+/// A Node's identity is the name the Host records for its connection: the common name of its
+/// client certificate over QUIC or TLS, otherwise the name sent in its handshake. Names are
+/// only trustworthy once the Host requires tokens, or over UDP, a pre-shared key.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccessControl {
+    rules: Vec<Rule>,
+}
+
+impl AccessControl {
+    /// Create an access control list that denies everything
+    pub fn new() -> Self {
+        AccessControl::default()
+    }
+
+    /// Allow Nodes matching `identity` to perform `operation` on topics matching `topic`
+    pub fn allow(
+        mut self,
+        identity: impl Into<String>,
+        operation: Operation,
+        topic: impl Into<String>,
+    ) -> Self {
+        self.rules.push(Rule {
+            identity: identity.into(),
+            operation,
+            topic: topic.into(),
+        });
+        self
+    }
+
+    /// Allow Nodes matching `identity` to publish to topics matching `topic`
+    pub fn allow_publish(self, identity: impl Into<String>, topic: impl Into<String>) -> Self {
+        self.allow(identity, Operation::Publish, topic)
+    }
+
+    /// Allow Nodes matching `identity` to request topics matching `topic`
+    pub fn allow_request(self, identity: impl Into<String>, topic: impl Into<String>) -> Self {
+        self.allow(identity, Operation::Request, topic)
+    }
+
+    /// Allow Nodes matching `identity` to subscribe to topics matching `topic`
+    pub fn allow_subscribe(self, identity: impl Into<String>, topic: impl Into<String>) -> Self {
+        self.allow(identity, Operation::Subscribe, topic)
+    }
+
+    /// Whether `identity` may perform `operation` on `topic`
+    pub fn is_allowed(&self, identity: &str, operation: Operation, topic: &str) -> bool {
+        self.rules.iter().any(|rule| {
+            rule.operation == operation
+                && matches(&rule.identity, identity)
+                && matches(&rule.topic, topic)
+        })
+    }
+
+    /// Check the operation `msg` asks for, logging and returning `HostError::Unauthorized` if denied
+    pub fn authorize(&self, identity: &str, msg: &GenericMsg) -> Result<(), Error> {
+        match Operation::of(&msg.msg_type) {
+            Some(operation) if !self.is_allowed(identity, operation, &msg.topic) => {
+                warn!(
+                    "Denied {:?} on topic \"{}\" to \"{}\"",
+                    operation, msg.topic, identity
+                );
+                Err(Error::HostOperation(HostError::Unauthorized))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Authorization of the messages arriving on one Node's connection
+#[derive(Debug, Clone)]
+pub struct Authorizer {
+    acl: Option<Arc<AccessControl>>,
+    identity: String,
+}
+
+impl Authorizer {
+    pub(crate) fn new(acl: Option<Arc<AccessControl>>, identity: impl Into<String>) -> Self {
+        Authorizer {
+            acl,
+            identity: identity.into(),
+        }
+    }
+
+    /// Check `msg` against the Host's access control list, if it has one
+    pub(crate) fn check(&self, msg: &GenericMsg) -> Result<(), Error> {
+        match &self.acl {
+            Some(acl) => acl.authorize(&self.identity, msg),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Match `text` against a pattern in which `*` stands for any run of characters
+fn matches(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    // `split` always yields at least one part
+    let first = parts.next().unwrap_or_default();
+    let mut rest = match text.strip_prefix(first) {
+        Some(rest) => rest,
+        None => return false,
+    };
+    let parts: Vec<&str> = parts.collect();
+    let (last, middle) = match parts.split_last() {
+        Some(split) => split,
+        // No `*` at all, so the pattern must match exactly
+        None => return rest.is_empty(),
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wildcard_patterns() {
+        assert!(matches("cmd/*", "cmd/throttle"));
+        assert!(matches("*", ""));
+        assert!(matches("*/pose", "gps/pose"));
+        assert!(matches("a*b*c", "aXXbYYc"));
+        assert!(!matches("a*b*c", "aXXcYYb"));
+        assert!(!matches("cmd/*", "telemetry/cmd/x"));
+        assert!(!matches("imu", "imu2"));
+        assert!(!matches("ab*ba", "aba"));
+    }
+
+    #[test]
+    fn deny_unless_allowed() {
+        let acl = AccessControl::new()
+            .allow_publish("controller", "cmd/*")
+            .allow_request("*", "*");
+        let set = GenericMsg {
+            msg_type: MsgType::Set,
+            topic: "cmd/throttle".into(),
+            ..GenericMsg::topics()
+        };
+        assert!(acl.authorize("controller", &set).is_ok());
+        assert_eq!(
+            acl.authorize("logger", &set),
+            Err(Error::HostOperation(HostError::Unauthorized))
+        );
+        let get = GenericMsg {
+            msg_type: MsgType::Get,
+            ..set
+        };
+        assert!(acl.authorize("logger", &get).is_ok());
+        assert!(!acl.is_allowed("controller", Operation::Subscribe, "cmd/throttle"));
+    }
+}
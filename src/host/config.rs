@@ -4,8 +4,10 @@ use chrono::Utc;
 // Tokio for async
 use tokio::sync::Mutex; // as TokioMutex;
                         // Multi-threading primitives
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::sync::Mutex as StdMutex;
+use std::time::Duration;
 // Misc other imports
 use crate::prelude::*;
 use std::result::Result;
@@ -21,10 +23,33 @@ pub struct HostConfig {
     pub udp_cfg: Option<host::UdpConfig>,
     #[cfg(feature = "quic")]
     pub quic_cfg: Option<host::QuicConfig>,
+    #[cfg(unix)]
+    pub uds_cfg: Option<host::UdsConfig>,
+    #[cfg(all(unix, feature = "shm"))]
+    pub shm_cfg: Option<host::ShmConfig>,
+    pub discovery_cfg: Option<DiscoveryConfig>,
+    /// Topics each Node is allowed to publish, request, and subscribe to; everything is allowed when unset
+    pub access_control: Option<host::AccessControl>,
+    /// Tokens Nodes must present in their handshake, by name; any Node may connect when empty
+    pub node_tokens: HashMap<String, String>,
+    /// Data types topics are registered with up front, rather than taking the type first published
+    pub topic_types: HashMap<String, String>,
+    /// Compression the Host applies to data it stores on each topic, if it isn't already compressed
+    pub topic_compression: HashMap<String, Compression>,
+    /// How long `Host::stop` waits for connections to finish in-flight requests before aborting them
+    pub shutdown_timeout: Duration,
+    /// How long a new connection has to complete its handshake before it's dropped
+    pub handshake_timeout: Duration,
+    /// Whether the Host owns its async runtime or runs on an external one
+    pub runtime_cfg: RuntimeConfig,
+    /// Heartbeats expected from Nodes, without which their connections are dropped; never
+    /// dropped for being quiet when unset
+    pub heartbeat: Option<HeartbeatConfig>,
 }
 
 impl Default for HostConfig {
     /// Create a new `HostConfig` with all default options
+    #[allow(clippy::needless_return)]
     fn default() -> HostConfig {
         // Default sled database configuration
         let date = Utc::now();
@@ -44,6 +69,19 @@ impl Default for HostConfig {
                 tcp_cfg: Some(host::TcpConfig::default("lo")),
                 udp_cfg: None,
                 quic_cfg: Some(host::QuicConfig::default()),
+                #[cfg(unix)]
+                uds_cfg: None,
+                #[cfg(all(unix, feature = "shm"))]
+                shm_cfg: None,
+                discovery_cfg: None,
+                access_control: None,
+                node_tokens: HashMap::new(),
+                topic_types: HashMap::new(),
+                topic_compression: HashMap::new(),
+                shutdown_timeout: Duration::from_secs(5),
+                handshake_timeout: Duration::from_secs(5),
+                runtime_cfg: RuntimeConfig::default(),
+                heartbeat: Some(HeartbeatConfig::default()),
             };
         }
         #[cfg(not(feature = "quic"))]
@@ -52,6 +90,19 @@ impl Default for HostConfig {
                 sled_cfg,
                 tcp_cfg: Some(host::TcpConfig::default("lo")),
                 udp_cfg: Some(host::UdpConfig::default("lo")),
+                #[cfg(unix)]
+                uds_cfg: None,
+                #[cfg(all(unix, feature = "shm"))]
+                shm_cfg: None,
+                discovery_cfg: None,
+                access_control: None,
+                node_tokens: HashMap::new(),
+                topic_types: HashMap::new(),
+                topic_compression: HashMap::new(),
+                shutdown_timeout: Duration::from_secs(5),
+                handshake_timeout: Duration::from_secs(5),
+                runtime_cfg: RuntimeConfig::default(),
+                heartbeat: Some(HeartbeatConfig::default()),
             };
         }
     }
@@ -83,25 +134,138 @@ impl HostConfig {
         self
     }
 
+    /// Assign a configuration to the Host's Unix domain socket listener
+    #[cfg(unix)]
+    pub fn with_uds_config(mut self, uds_cfg: Option<host::UdsConfig>) -> HostConfig {
+        self.uds_cfg = uds_cfg;
+        self
+    }
+
+    /// Assign a configuration to the Host's shared-memory listener
+    #[cfg(all(unix, feature = "shm"))]
+    pub fn with_shm_config(mut self, shm_cfg: Option<host::ShmConfig>) -> HostConfig {
+        self.shm_cfg = shm_cfg;
+        self
+    }
+
+    /// Have the Host periodically announce itself so Nodes can discover it by name
+    pub fn with_discovery_config(mut self, discovery_cfg: Option<DiscoveryConfig>) -> HostConfig {
+        self.discovery_cfg = discovery_cfg;
+        self
+    }
+
+    /// Restrict which topics each Node may publish, request, and subscribe to
+    pub fn with_access_control(
+        mut self,
+        access_control: Option<host::AccessControl>,
+    ) -> HostConfig {
+        self.access_control = access_control;
+        self
+    }
+
+    /// Require the Node called `name` to present `token` in its handshake.
+    ///
+    /// Once any token is registered, Nodes without one of their own are rejected.
+    pub fn with_node_token(
+        mut self,
+        name: impl Into<String>,
+        token: impl Into<String>,
+    ) -> HostConfig {
+        self.node_tokens.insert(name.into(), token.into());
+        self
+    }
+
+    /// Register `topic` as holding `T`, so publishing any other type to it is rejected
+    /// with `HostError::TypeMismatch`
+    pub fn with_topic_type<T: Message>(mut self, topic: impl Into<String>) -> HostConfig {
+        self.topic_types
+            .insert(topic.into(), crate::schema::data_type::<T>());
+        self
+    }
+
+    /// Compress data stored on `topic` that its publisher didn't compress, saving disk space.
+    /// Nodes requesting or subscribing to the topic decompress it transparently.
+    pub fn with_topic_compression(
+        mut self,
+        topic: impl Into<String>,
+        compression: Compression,
+    ) -> HostConfig {
+        self.topic_compression.insert(topic.into(), compression);
+        self
+    }
+
+    /// Set how long `Host::stop` waits for connections to finish their in-flight requests
+    /// before aborting them
+    pub fn with_shutdown_timeout(mut self, shutdown_timeout: Duration) -> HostConfig {
+        self.shutdown_timeout = shutdown_timeout;
+        self
+    }
+
+    /// Set how long a new connection has to complete its handshake before it's dropped
+    pub fn with_handshake_timeout(mut self, handshake_timeout: Duration) -> HostConfig {
+        self.handshake_timeout = handshake_timeout;
+        self
+    }
+
+    /// Run the Host on an external runtime, e.g. one the application already uses,
+    /// rather than creating its own
+    pub fn with_runtime_config(mut self, runtime_cfg: RuntimeConfig) -> HostConfig {
+        self.runtime_cfg = runtime_cfg;
+        self
+    }
+
+    /// Set how often Nodes are expected to send heartbeats and how long a quiet connection is
+    /// kept, or with `None`, keep connections until Nodes close them
+    pub fn with_heartbeat(mut self, heartbeat: Option<HeartbeatConfig>) -> HostConfig {
+        self.heartbeat = heartbeat;
+        self
+    }
+
     /// Construct a Host based on the `HostConfig`'s parameters
     pub fn build(self) -> Result<Host, Error> {
-        let runtime = match tokio::runtime::Runtime::new() {
-            Ok(runtime) => runtime,
-            Err(_e) => return Err(Error::RuntimeCreation),
+        let (runtime, rt_handle) = {
+            if self.runtime_cfg.owned_runtime {
+                let runtime = match tokio::runtime::Runtime::new() {
+                    Ok(runtime) => runtime,
+                    Err(_e) => return Err(Error::RuntimeCreation),
+                };
+                let handle = runtime.handle().clone();
+                (Some(runtime), handle)
+            } else if let Some(rt_handle) = self.runtime_cfg.rt_handle.clone() {
+                (None, rt_handle)
+            } else {
+                return Err(Error::RuntimeCreation);
+            }
         };
 
-        let connections = Arc::new(StdMutex::new(Vec::new()));
         let store: sled::Db = self.sled_cfg.open()?;
+        for (topic, data_type) in &self.topic_types {
+            host::register_type(&store, topic, data_type)?;
+        }
+        host::set_topic_compression(&store, &self.topic_compression)?;
+        let peer_timeout = self
+            .udp_cfg
+            .as_ref()
+            .and_then(|udp_cfg| udp_cfg.peer_timeout);
+        let registry = Arc::new(host::registry::Registry::new(self.heartbeat, peer_timeout));
 
         Ok(Host {
             cfg: self,
             runtime,
+            rt_handle,
             task_listen_tcp: None,
-            connections,
+            registry,
             task_listen_udp: None,
             #[cfg(feature = "quic")]
             task_listen_quic: None,
+            #[cfg(unix)]
+            task_listen_uds: None,
+            #[cfg(all(unix, feature = "shm"))]
+            task_listen_shm: None,
+            task_announce: None,
+            task_heartbeat: None,
             store,
+            shutdown: tokio::sync::watch::channel(false).0,
         })
     }
 }
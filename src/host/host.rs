@@ -1,25 +1,29 @@
 use postcard::to_allocvec;
 // Tokio for async
 use sled::Db;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::net::TcpListener;
 use tokio::net::UdpSocket;
-use tokio::runtime::Runtime;
+#[cfg(unix)]
+use tokio::net::UnixListener;
+use tokio::runtime::{Handle, Runtime};
+use tokio::sync::watch;
 use tokio::sync::Mutex; // as TokioMutex;
-use tokio::task::JoinHandle;
+use tokio::task::{JoinHandle, JoinSet};
 // QUIC requirements
 #[cfg(feature = "quic")]
 use futures_util::StreamExt;
 #[cfg(feature = "quic")]
 use quinn::Connection as QuicConnection;
 #[cfg(feature = "quic")]
-use quinn::{Endpoint, ServerConfig};
+use quinn::{Endpoint, EndpointConfig, ServerConfig, TokioRuntime};
 #[cfg(feature = "quic")]
 use std::{fs::File, io::BufReader};
 
 // Tracing for logging
 use tracing::*;
 // Multi-threading primitives
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::sync::Mutex as StdMutex;
 // Misc other imports
@@ -32,32 +36,49 @@ use std::result::Result;
 use crate::error::Quic::*;
 #[cfg(feature = "quic")]
 use crate::host::quic::*;
+#[cfg(feature = "tls")]
+use tokio_rustls::TlsAcceptor;
 
+use crate::discovery::{announce, Announcement, HostEndpoint, Transport};
+use crate::handshake::Handshake;
+use crate::host::acl::Authorizer;
+use crate::host::in_process::HostHandle;
+use crate::host::registry::{reap, Connection, ConnectionId, ConnectionInfo, Registry};
+#[cfg(all(unix, feature = "shm"))]
+use crate::host::shm::*;
+use crate::host::shutdown::{drain, Shutdown, ShutdownReport};
+use crate::host::stats::{HostStats, TopicInfo};
 use crate::host::tcp::*;
 use crate::host::udp::*;
+#[cfg(unix)]
+use crate::host::uds::*;
+use crate::msg::PROTOCOL_VERSION;
+use crate::networks::{bind_tcp, bind_udp};
 use crate::prelude::*;
-use crate::prelude::*;
+use crate::psk::DatagramCipher;
 use crate::*;
 
-/// Named task handle for each Hosted connection
-#[derive(Debug)]
-pub struct Connection {
-    handle: JoinHandle<()>,
-    stream_addr: SocketAddr,
-    name: String,
-}
-
 /// Central coordination process, which stores published data and responds to requests
 #[derive(Debug)]
 pub struct Host {
     pub(crate) cfg: HostConfig,
-    pub(crate) runtime: Runtime,
+    pub(crate) runtime: Option<Runtime>,
+    pub(crate) rt_handle: Handle,
     pub(crate) task_listen_tcp: Option<JoinHandle<()>>,
-    pub(crate) connections: Arc<StdMutex<Vec<Connection>>>,
+    pub(crate) registry: Arc<Registry>,
     pub(crate) task_listen_udp: Option<JoinHandle<()>>,
     #[cfg(feature = "quic")]
     pub(crate) task_listen_quic: Option<JoinHandle<()>>,
+    #[cfg(unix)]
+    pub(crate) task_listen_uds: Option<JoinHandle<()>>,
+    #[cfg(all(unix, feature = "shm"))]
+    pub(crate) task_listen_shm: Option<JoinHandle<()>>,
+    pub(crate) task_announce: Option<JoinHandle<()>>,
+    /// Drops connections whose Nodes have stopped sending heartbeats
+    pub(crate) task_heartbeat: Option<JoinHandle<()>>,
     pub(crate) store: sled::Db,
+    /// Signals every task the Host started once it begins shutting down
+    pub(crate) shutdown: watch::Sender<bool>,
 }
 
 pub trait Store {
@@ -92,9 +113,88 @@ pub(crate) trait GenericStore {
     ) -> Result<GenericMsg, crate::Error>;
 }
 
+/// Sled tree recording the data type each topic holds, keyed by topic name
+pub(crate) const TYPES_TREE: &str = "__meadow__types";
+
+/// Sled tree recording the compression the Host applies to each topic, keyed by topic name
+pub(crate) const COMPRESSION_TREE: &str = "__meadow__compression";
+
+/// Wait for a new connection's handshake, giving up once it has taken longer than `limit` or
+/// the Host begins shutting down. Failures are logged as coming from `peer`.
+async fn await_handshake<T>(
+    accepting: impl std::future::Future<Output = Result<T, Error>>,
+    limit: Duration,
+    mut shutdown: Shutdown,
+    peer: &str,
+) -> Option<T> {
+    tokio::select! {
+        accepted = tokio::time::timeout(limit, accepting) => match accepted {
+            Ok(Ok(accepted)) => Some(accepted),
+            Ok(Err(e)) => {
+                warn!("Handshake with {} failed: {}", peer, e);
+                None
+            }
+            Err(_elapsed) => {
+                warn!("Handshake with {} timed out", peer);
+                None
+            }
+        },
+        _ = shutdown.wait() => None,
+    }
+}
+
+/// Whether a sled tree holds the Host's own bookkeeping rather than a topic
+fn is_internal_tree(name: &str) -> bool {
+    name == "__sled__default" || name == TYPES_TREE || name == COMPRESSION_TREE
+}
+
+/// Replace the compression applied to each topic with `topics`
+pub(crate) fn set_topic_compression(
+    db: &sled::Db,
+    topics: &HashMap<String, Compression>,
+) -> Result<(), Error> {
+    let tree = db.open_tree(COMPRESSION_TREE)?;
+    tree.clear()?;
+    for (topic, compression) in topics {
+        tree.insert(topic.as_bytes(), postcard::to_allocvec(compression)?)?;
+    }
+    Ok(())
+}
+
+/// Compression the Host applies to `topic`, if any
+fn topic_compression(db: &sled::Db, topic: &str) -> Result<Option<Compression>, Error> {
+    match db.open_tree(COMPRESSION_TREE)?.get(topic)? {
+        Some(bytes) => Ok(Some(postcard::from_bytes(&bytes)?)),
+        None => Ok(None),
+    }
+}
+
+/// Record `data_type` as the type held by `topic`, unless it already holds another type
+pub(crate) fn register_type(db: &sled::Db, topic: &str, data_type: &str) -> Result<(), Error> {
+    let types = db.open_tree(TYPES_TREE)?;
+    match types.compare_and_swap(topic, None as Option<&[u8]>, Some(data_type.as_bytes()))? {
+        Ok(()) => Ok(()),
+        Err(sled::CompareAndSwapError {
+            current: Some(current),
+            ..
+        }) if current != data_type.as_bytes() => {
+            let expected = String::from_utf8_lossy(&current).to_string();
+            Err(Error::HostOperation(error::HostError::TypeMismatch {
+                expected,
+                found: data_type.to_string(),
+            }))
+        }
+        Err(_) => Ok(()),
+    }
+}
+
 impl GenericStore for sled::Db {
     #[tracing::instrument]
-    fn insert_generic(&mut self, msg: GenericMsg) -> Result<(), crate::Error> {
+    fn insert_generic(&mut self, mut msg: GenericMsg) -> Result<(), crate::Error> {
+        register_type(self, &msg.topic, &msg.data_type)?;
+        if let Some(compression) = topic_compression(self, &msg.topic)? {
+            msg = msg.compressed(compression);
+        }
         let bytes = msg.as_bytes()?;
         let tree = self.open_tree(msg.topic.as_bytes())?;
         tree.insert(msg.timestamp.to_string().as_bytes(), bytes)?;
@@ -186,12 +286,8 @@ impl Store for sled::Db {
                 }
             }
         }
-        // Remove default sled tree name
-        let index = strings
-            .iter()
-            .position(|x| *x == "__sled__default")
-            .unwrap();
-        strings.remove(index);
+        // Remove the trees that don't hold topics
+        strings.retain(|name| !is_internal_tree(name));
         strings.sort();
         Ok(strings)
     }
@@ -201,7 +297,7 @@ impl Store for Host {
     /// Insert a raw `Msg<T>`
     #[inline]
     fn insert_msg<T: Message>(&mut self, msg: Msg<T>) -> Result<(), crate::Error> {
-        self.db().insert_msg(msg)
+        Host::insert_msg(self, msg)
     }
 
     /// Insert a value using a default `Msg`
@@ -211,7 +307,7 @@ impl Store for Host {
         topic: impl Into<String> + std::fmt::Debug,
         data: T,
     ) -> Result<(), crate::Error> {
-        self.db().insert(topic, data)
+        Host::insert(self, topic, data)
     }
 
     /// Retrieve last message on a given topic
@@ -241,23 +337,29 @@ impl Store for Host {
 
 impl Drop for Host {
     fn drop(&mut self) {
-        if let Some(task) = &self.task_listen_tcp {
-            task.abort();
-            self.task_listen_tcp = None;
+        // Blocking on the Host's runtime from within another one would panic,
+        // so there its tasks can only be aborted
+        if Handle::try_current().is_err() {
+            if let Err(e) = self.stop() {
+                warn!("Unable to shut down Host: {}", e);
+            }
+            return;
         }
-        if let Some(task) = &self.task_listen_udp {
-            task.abort();
-            self.task_listen_udp = None;
+
+        if let Ok((listeners, udp, connections)) = self.begin_shutdown() {
+            for task in listeners.iter().chain(udp.iter()) {
+                task.abort();
+            }
+            for handle in connections.iter().filter_map(|c| c.handle.as_ref()) {
+                handle.abort();
+            }
         }
-        #[cfg(feature = "quic")]
-        if let Some(task) = &self.task_listen_quic {
-            task.abort();
-            self.task_listen_quic = None;
+        if let Err(e) = self.store.flush() {
+            warn!("Unable to flush Host store: {}", e);
         }
-        if let Ok(mut connections) = self.connections.lock() {
-            for connection in &mut *connections {
-                connection.handle.abort();
-            }
+        // Nor can an owned runtime be waited on as it's dropped
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
         }
     }
 }
@@ -273,20 +375,32 @@ impl Host {
         self.store.clone()
     }
 
-    /// Access Host's Tokio `Runtime`
-    pub fn runtime(&self) -> &Runtime {
+    /// Get reference to the `Host`'s Tokio runtime if it owns one
+    pub fn runtime(&self) -> &Option<Runtime> {
         &self.runtime
     }
 
+    /// Get reference to the handle of the runtime the `Host` runs on
+    pub fn rt_handle(&self) -> &Handle {
+        &self.rt_handle
+    }
+
+    /// Get a handle for connecting `InProcess` Nodes running in the same process as the Host
+    pub fn handle(&self) -> HostHandle {
+        HostHandle {
+            db: self.store.clone(),
+            rt_handle: self.rt_handle.clone(),
+            registry: Arc::clone(&self.registry),
+            acl: self.cfg.access_control.clone().map(Arc::new),
+            tokens: Arc::new(self.cfg.node_tokens.clone()),
+            shutdown: Shutdown::new(self.shutdown.subscribe()),
+        }
+    }
+
     /// Insert a raw `Msg<T>`
     pub fn insert_msg<T: Message>(&mut self, msg: Msg<T>) -> Result<(), crate::Error> {
         let generic: GenericMsg = msg.try_into()?;
-        let bytes = to_allocvec(&generic)?;
-
-        let tree = self.db().open_tree(generic.topic.as_bytes())?;
-        tree.insert(generic.timestamp.to_string().as_bytes(), bytes)?;
-
-        Ok(())
+        self.registry.insert_generic(&mut self.db(), generic)
     }
 
     /// Insert a value using a default `Msg`
@@ -315,10 +429,15 @@ impl Host {
         }
     }
 
-    /// Allow Host to begin accepting incoming connections
+    /// Allow Host to begin accepting incoming connections, which it may do again after `stop`
     #[tracing::instrument(skip(self))]
     pub fn start(&mut self) -> Result<(), crate::Error> {
-        let connections = self.connections.clone();
+        self.shutdown.send_replace(false);
+        let shutdown = Shutdown::new(self.shutdown.subscribe());
+        let registry = Arc::clone(&self.registry);
+        let acl = self.cfg.access_control.clone().map(Arc::new);
+        let tokens = Arc::new(self.cfg.node_tokens.clone());
+        let handshake_timeout = self.cfg.handshake_timeout;
 
         let db = self.store.clone();
 
@@ -326,24 +445,41 @@ impl Host {
         match &self.config().udp_cfg {
             None => warn!("Host has no UDP configuration"),
             Some(udp_cfg) => {
-                let ip = match get_ip(&udp_cfg.interface) {
-                    Ok(ip) => ip,
-                    Err(_e) => return Err(Error::InvalidInterface),
-                };
-
-                let addr = SocketAddr::new(IpAddr::V4(ip), udp_cfg.socket_num);
+                let addr = udp_cfg.socket_addr()?;
+                let ipv6_only = udp_cfg.ipv6_only;
 
                 let db = db.clone();
 
                 // Start the UDP listening socket
-                let (max_buffer_size_udp, _max_name_size_udp) =
+                let (max_buffer_size_udp, max_name_size_udp) =
                     (udp_cfg.max_buffer_size, udp_cfg.max_name_size);
-                let rt_handle = self.runtime.handle().clone();
-                let task_listen_udp = self.runtime.spawn(async move {
-                    match UdpSocket::bind(addr).await {
+                let cipher = udp_cfg
+                    .psk
+                    .as_ref()
+                    .map(|psk| Arc::new(DatagramCipher::new(psk)));
+                let rt_handle = self.rt_handle.clone();
+                let acl = acl.clone();
+                let tokens = tokens.clone();
+                let shutdown = shutdown.clone();
+                let registry = Arc::clone(&registry);
+                let task_listen_udp = self.rt_handle.spawn(async move {
+                    match bind_udp(addr, ipv6_only)
+                        .and_then(|socket| Ok(UdpSocket::from_std(socket)?))
+                    {
                         Ok(socket) => {
-                            process_udp(rt_handle.clone(), socket, db.clone(), max_buffer_size_udp)
-                                .await
+                            process_udp(
+                                rt_handle.clone(),
+                                socket,
+                                db.clone(),
+                                max_buffer_size_udp,
+                                max_name_size_udp,
+                                cipher,
+                                acl,
+                                tokens,
+                                shutdown,
+                                registry,
+                            )
+                            .await
                         }
                         Err(e) => {
                             error!("{}", e);
@@ -358,56 +494,323 @@ impl Host {
         match &self.config().tcp_cfg {
             None => warn!("Host has no TCP configuration"),
             Some(tcp_cfg) => {
-                let ip = match get_ip(&tcp_cfg.interface) {
-                    Ok(ip) => ip,
-                    Err(_e) => return Err(Error::InvalidInterface),
-                };
-
-                let addr = SocketAddr::new(IpAddr::V4(ip), tcp_cfg.socket_num);
+                let addr = tcp_cfg.socket_addr()?;
+                let ipv6_only = tcp_cfg.ipv6_only;
 
                 let (max_buffer_size_tcp, max_name_size_tcp) =
                     (tcp_cfg.max_buffer_size, tcp_cfg.max_name_size);
                 let db = db.clone();
-                let connections = Arc::clone(&connections);
-
-                let task_listen_tcp = self.runtime.spawn(async move {
-                    if let Ok(listener) = TcpListener::bind(addr).await {
-                        let connections = Arc::clone(&connections.clone());
+                let registry = Arc::clone(&registry);
+                let acl = acl.clone();
+                let tokens = tokens.clone();
+                #[cfg(feature = "tls")]
+                let acceptor = match &tcp_cfg.tls {
+                    Some(tls) => Some(TlsAcceptor::from(Arc::new(server_crypto_config(
+                        &tls.cert_path,
+                        &tls.key_path,
+                        tls.client_ca_path.as_deref(),
+                    )?))),
+                    None => None,
+                };
+                let shutdown = shutdown.clone();
 
+                let task_listen_tcp = self.rt_handle.spawn(async move {
+                    let listener = bind_tcp(addr, ipv6_only)
+                        .and_then(|listener| Ok(TcpListener::from_std(listener)?));
+                    if let Ok(listener) = listener {
                         loop {
                             if let Ok((stream, stream_addr)) = listener.accept().await {
-                                let (stream, name) = match crate::host::tcp::handshake(
+                                let db = db.clone();
+                                let registry = Arc::clone(&registry);
+                                let acl = acl.clone();
+                                let tokens = tokens.clone();
+                                #[cfg(feature = "tls")]
+                                let acceptor = acceptor.clone();
+                                let shutdown = shutdown.clone();
+                                // Handshakes run apart from the listener, so a Node that never
+                                // sends one can't hold up the connections after it
+                                tokio::spawn(async move {
+                                    #[cfg(feature = "tls")]
+                                    if let Some(acceptor) = &acceptor {
+                                        let accepting = crate::host::tcp::tls_handshake(
+                                            acceptor,
+                                            stream,
+                                            max_buffer_size_tcp,
+                                            max_name_size_tcp,
+                                            &tokens,
+                                        );
+                                        let peer = format!("TLS connection from {}", stream_addr);
+                                        let (stream, node_handshake, name) = match await_handshake(
+                                            accepting,
+                                            handshake_timeout,
+                                            shutdown.clone(),
+                                            &peer,
+                                        )
+                                        .await
+                                        {
+                                            Some(accepted) => accepted,
+                                            None => return,
+                                        };
+                                        debug!("Host received TLS connection from {:?}", &name);
+                                        node_handshake.register_schema(&db);
+
+                                        let auth = Authorizer::new(acl, name.clone());
+                                        let registered = registry.register(
+                                            node_handshake,
+                                            name,
+                                            Transport::Tcp,
+                                            stream_addr.to_string(),
+                                            |tracker| {
+                                                tokio::spawn(async move {
+                                                    process_tls(
+                                                        stream,
+                                                        db,
+                                                        max_buffer_size_tcp,
+                                                        auth,
+                                                        shutdown,
+                                                        tracker.clone(),
+                                                    )
+                                                    .await;
+                                                    tracker.close();
+                                                })
+                                            },
+                                        );
+                                        if let Err(e) = registered {
+                                            error!("Unable to register TLS connection: {}", e);
+                                        }
+                                        return;
+                                    }
+
+                                    let accepting = crate::host::tcp::handshake(
+                                        stream,
+                                        max_buffer_size_tcp,
+                                        max_name_size_tcp,
+                                        &tokens,
+                                    );
+                                    let peer = format!("TCP connection from {}", stream_addr);
+                                    let (stream, node_handshake) = match await_handshake(
+                                        accepting,
+                                        handshake_timeout,
+                                        shutdown.clone(),
+                                        &peer,
+                                    )
+                                    .await
+                                    {
+                                        Some(accepted) => accepted,
+                                        None => return,
+                                    };
+                                    let name = node_handshake.name.clone();
+                                    debug!("Host received connection from {:?}", &name);
+                                    node_handshake.register_schema(&db);
+
+                                    let auth = Authorizer::new(acl, name.clone());
+                                    let registered = registry.register(
+                                        node_handshake,
+                                        name,
+                                        Transport::Tcp,
+                                        stream_addr.to_string(),
+                                        |tracker| {
+                                            tokio::spawn(async move {
+                                                process_tcp(
+                                                    stream,
+                                                    db,
+                                                    max_buffer_size_tcp,
+                                                    auth,
+                                                    shutdown,
+                                                    tracker.clone(),
+                                                )
+                                                .await;
+                                                tracker.close();
+                                            })
+                                        },
+                                    );
+                                    if let Err(e) = registered {
+                                        error!("Unable to register TCP connection: {}", e);
+                                    }
+                                });
+                            }
+                        }
+                    }
+                });
+
+                self.task_listen_tcp = Some(task_listen_tcp);
+            }
+        }
+
+        // Start the Unix domain socket process
+        #[cfg(unix)]
+        match &self.config().uds_cfg {
+            None => debug!("Host has no UDS configuration"),
+            Some(uds_cfg) => {
+                // Clear out a stale socket file left behind by a previous Host
+                if uds_cfg.path.exists() {
+                    std::fs::remove_file(&uds_cfg.path)?;
+                }
+                let listener = {
+                    let _guard = self.rt_handle.enter();
+                    UnixListener::bind(&uds_cfg.path)?
+                };
+                if let Some(mode) = uds_cfg.permissions {
+                    use std::os::unix::fs::PermissionsExt;
+                    std::fs::set_permissions(&uds_cfg.path, std::fs::Permissions::from_mode(mode))?;
+                }
+                let path = uds_cfg.path.display().to_string();
+
+                let (max_buffer_size_uds, max_name_size_uds) =
+                    (uds_cfg.max_buffer_size, uds_cfg.max_name_size);
+                let db = db.clone();
+                let registry = Arc::clone(&registry);
+                let acl = acl.clone();
+                let tokens = tokens.clone();
+                let shutdown = shutdown.clone();
+
+                let task_listen_uds = self.rt_handle.spawn(async move {
+                    loop {
+                        if let Ok((stream, _addr)) = listener.accept().await {
+                            let db = db.clone();
+                            let registry = Arc::clone(&registry);
+                            let acl = acl.clone();
+                            let tokens = tokens.clone();
+                            let path = path.clone();
+                            let shutdown = shutdown.clone();
+                            tokio::spawn(async move {
+                                let accepting = crate::host::uds::handshake(
                                     stream,
-                                    max_buffer_size_tcp,
-                                    max_name_size_tcp,
+                                    max_buffer_size_uds,
+                                    max_name_size_uds,
+                                    &tokens,
+                                );
+                                let peer = format!("UDS connection on {}", path);
+                                let (stream, node_handshake) = match await_handshake(
+                                    accepting,
+                                    handshake_timeout,
+                                    shutdown.clone(),
+                                    &peer,
                                 )
                                 .await
                                 {
-                                    Ok((stream, name)) => (stream, name),
-                                    Err(_e) => continue,
+                                    Some(accepted) => accepted,
+                                    None => return,
                                 };
-                                debug!("Host received connection from {:?}", &name);
+                                let name = node_handshake.name.clone();
+                                debug!("Host received UDS connection from {:?}", &name);
+                                node_handshake.register_schema(&db);
 
-                                let connections = Arc::clone(&connections.clone());
-                                let db = db.clone();
-
-                                let handle = tokio::spawn(async move {
-                                    process_tcp(stream, db, max_buffer_size_tcp).await;
-                                });
-                                let connection = Connection {
-                                    handle,
-                                    stream_addr,
+                                let auth = Authorizer::new(acl, name.clone());
+                                let registered = registry.register(
+                                    node_handshake,
                                     name,
+                                    Transport::Uds,
+                                    path,
+                                    |tracker| {
+                                        tokio::spawn(async move {
+                                            process_uds(
+                                                stream,
+                                                db,
+                                                max_buffer_size_uds,
+                                                auth,
+                                                shutdown,
+                                                tracker.clone(),
+                                            )
+                                            .await;
+                                            tracker.close();
+                                        })
+                                    },
+                                );
+                                if let Err(e) = registered {
+                                    error!("Unable to register UDS connection: {}", e);
+                                }
+                            });
+                        }
+                    }
+                });
+
+                self.task_listen_uds = Some(task_listen_uds);
+            }
+        }
+
+        // Start the shared-memory process
+        #[cfg(all(unix, feature = "shm"))]
+        match &self.config().shm_cfg {
+            None => debug!("Host has no shared-memory configuration"),
+            Some(shm_cfg) => {
+                // Clear out a stale socket file left behind by a previous Host
+                if shm_cfg.path.exists() {
+                    std::fs::remove_file(&shm_cfg.path)?;
+                }
+                let listener = {
+                    let _guard = self.rt_handle.enter();
+                    UnixListener::bind(&shm_cfg.path)?
+                };
+                let path = shm_cfg.path.display().to_string();
+
+                let shm_cfg = shm_cfg.clone();
+                let db = db.clone();
+                let registry = Arc::clone(&registry);
+                let acl = acl.clone();
+                let tokens = tokens.clone();
+                let shutdown = shutdown.clone();
+
+                let task_listen_shm = self.rt_handle.spawn(async move {
+                    let mut id: usize = 0;
+                    loop {
+                        if let Ok((stream, _addr)) = listener.accept().await {
+                            id += 1;
+                            let shm_cfg = shm_cfg.clone();
+                            let db = db.clone();
+                            let registry = Arc::clone(&registry);
+                            let acl = acl.clone();
+                            let tokens = tokens.clone();
+                            let path = path.clone();
+                            let shutdown = shutdown.clone();
+                            tokio::spawn(async move {
+                                let accepting =
+                                    crate::host::shm::handshake(stream, &shm_cfg, id, &tokens);
+                                let peer = format!("shared-memory connection on {}", path);
+                                let (connection, node_handshake) = match await_handshake(
+                                    accepting,
+                                    handshake_timeout,
+                                    shutdown.clone(),
+                                    &peer,
+                                )
+                                .await
+                                {
+                                    Some(accepted) => accepted,
+                                    None => return,
                                 };
+                                let name = node_handshake.name.clone();
+                                debug!("Host received shared-memory connection from {:?}", &name);
+                                node_handshake.register_schema(&db);
 
-                                // TO_DO: We should re-evaluate how connections are stored
-                                connections.lock().unwrap().push(connection);
-                            }
+                                let auth = Authorizer::new(acl, name.clone());
+                                let registered = registry.register(
+                                    node_handshake,
+                                    name,
+                                    Transport::Shm,
+                                    path,
+                                    |tracker| {
+                                        tokio::spawn(async move {
+                                            process_shm(
+                                                connection,
+                                                db,
+                                                auth,
+                                                shutdown,
+                                                tracker.clone(),
+                                            )
+                                            .await;
+                                            tracker.close();
+                                        })
+                                    },
+                                );
+                                if let Err(e) = registered {
+                                    error!("Unable to register shared-memory connection: {}", e);
+                                }
+                            });
                         }
                     }
                 });
 
-                self.task_listen_tcp = Some(task_listen_tcp);
+                self.task_listen_shm = Some(task_listen_shm);
             }
         }
 
@@ -416,71 +819,133 @@ impl Host {
         match &self.config().quic_cfg {
             None => warn!("Host has no QUIC configuration"),
             Some(quic_cfg) => {
-                let ip = match get_ip(&quic_cfg.network_cfg.interface) {
-                    Ok(ip) => ip,
-                    Err(_e) => return Err(Error::InvalidInterface),
-                };
-
-                let addr = SocketAddr::new(IpAddr::V4(ip), quic_cfg.network_cfg.socket_num);
-                let (certs, key) = read_certs_from_file(&quic_cfg.cert_path, &quic_cfg.key_path)?;
+                let addr = quic_cfg.network_cfg.socket_addr()?;
+                let ipv6_only = quic_cfg.network_cfg.ipv6_only;
+                let mut server_config = server_config(quic_cfg)?;
+                if let Some(heartbeat) = &self.cfg.heartbeat {
+                    server_config.transport_config(Arc::new(heartbeat.quic_transport()));
+                }
 
                 debug!("Successfully read in QUIC certs");
 
-                let (max_buffer_size_quic, _max_name_size_quic) = (
+                let (max_buffer_size_quic, max_name_size_quic) = (
                     quic_cfg.network_cfg.max_buffer_size,
                     quic_cfg.network_cfg.max_name_size,
                 );
-                let server_config = ServerConfig::with_single_cert(certs, key)?;
-
-                let task_listen_quic = self.runtime.spawn(async move {
-                    if let Ok(endpoint) = Endpoint::server(server_config, addr) {
+                let acl = acl.clone();
+                let tokens = tokens.clone();
+                let shutdown = shutdown.clone();
+                let task_listen_quic = self.rt_handle.spawn(async move {
+                    let endpoint = bind_udp(addr, ipv6_only).and_then(|socket| {
+                        Ok(Endpoint::new(
+                            EndpointConfig::default(),
+                            Some(server_config),
+                            socket,
+                            TokioRuntime,
+                        )?)
+                    });
+                    if let Ok(endpoint) = endpoint {
                         debug!(
                             "Waiting for incoming QUIC connection on {:?}",
                             endpoint.local_addr()
                         );
-                        let connections = Arc::clone(&connections.clone());
                         loop {
                             if let Some(conn) = endpoint.accept().await {
-                                if let Ok(connection) = conn.await {
-                                    let db = db.clone();
-                                    let remote_addr = connection.remote_address();
+                                let db = db.clone();
+                                let registry = Arc::clone(&registry);
+                                let acl = acl.clone();
+                                let tokens = tokens.clone();
+                                let shutdown = shutdown.clone();
+                                tokio::spawn(async move {
+                                    let remote_addr = conn.remote_address();
+                                    let accepting = async {
+                                        let connection = conn.await?;
+                                        let node_handshake = crate::host::quic::handshake(
+                                            &connection,
+                                            max_buffer_size_quic,
+                                            max_name_size_quic,
+                                            &tokens,
+                                        )
+                                        .await?;
+                                        Ok((connection, node_handshake))
+                                    };
+                                    let peer = format!("QUIC connection from {}", remote_addr);
+                                    let (connection, node_handshake) = match await_handshake(
+                                        accepting,
+                                        handshake_timeout,
+                                        shutdown.clone(),
+                                        &peer,
+                                    )
+                                    .await
+                                    {
+                                        Some(accepted) => accepted,
+                                        None => return,
+                                    };
+                                    // Clients are known by their certificate's name when they authenticate with one
+                                    let name = peer_name(&connection)
+                                        .unwrap_or_else(|| node_handshake.name.clone());
+                                    let auth = Authorizer::new(acl, name.clone());
+                                    node_handshake.register_schema(&db);
 
                                     debug!(
                                         "Received QUIC connection from {:?}",
                                         &connection.remote_address()
                                     );
 
-                                    let handle = tokio::spawn(async move {
+                                    let mut shutdown = shutdown;
+                                    let registered = registry.register(
+                                        node_handshake,
+                                        name,
+                                        Transport::Quic,
+                                        remote_addr.to_string(),
+                                        |tracker| tokio::spawn(async move {
+                                        // Streams still being handled, so they can finish before the connection is closed
+                                        let mut streams = JoinSet::new();
                                         loop {
                                             let db = db.clone();
+                                            let auth = auth.clone();
                                             // TO_DO: Instead of having these buffers, is there a way that we can just use sled 
                                             // to hold our buffer space instead, removing the additional allocation?
                                             let mut buf = vec![0u8; max_buffer_size_quic];
-                                            match connection.accept_bi().await {
+                                            let accepted = tokio::select! {
+                                                accepted = connection.accept_bi() => accepted,
+                                                Some(_) = streams.join_next() => continue,
+                                                _ = shutdown.wait() => {
+                                                    // Requests already being handled are finished before the connection is closed
+                                                    while streams.join_next().await.is_some() {}
+                                                    // Closing the connection is the Node's notice
+                                                    connection.close(0u32.into(), b"The Host is shutting down");
+                                                    break;
+                                                }
+                                            };
+                                            match accepted {
                                                 Ok((send, recv)) => {
                                                     debug!("Host successfully received bi-directional stream from {}",connection.remote_address());
-                                                    tokio::spawn(async move {
+                                                    let tracker = tracker.clone();
+                                                    let shutdown = shutdown.clone();
+                                                    streams.spawn(async move {
                                                         process_quic(
                                                             (send, recv),
                                                             db.clone(),
                                                             &mut buf,
+                                                            auth,
+                                                            shutdown,
+                                                            tracker,
                                                         )
                                                         .await;
                                                     });
                                                 }
-                                                Err(_e) => {}
+                                                // The Node has closed the connection
+                                                Err(_e) => break,
                                             }
                                         }
-                                        });
-                                        let connection = Connection {
-                                            handle,
-                                            stream_addr: remote_addr,
-                                            name: "TO_DO: temp".to_string(),
-                                        };
-
-                                        connections.lock().unwrap().push(connection);
-                                }
-
+                                        tracker.close();
+                                        }),
+                                    );
+                                    if let Err(e) = registered {
+                                        error!("Unable to register QUIC connection: {}", e);
+                                    }
+                                });
                             }
                         }
                     }
@@ -491,24 +956,135 @@ impl Host {
             }
         }
 
+        // Start dropping connections that stop sending heartbeats, and UDP peers that go quiet
+        let registry = Arc::clone(&self.registry);
+        self.task_heartbeat = Some(self.rt_handle.spawn(reap(registry)));
+
+        // Start announcing the Host's endpoints
+        match &self.config().discovery_cfg {
+            None => debug!("Host has no discovery configuration"),
+            Some(discovery_cfg) => {
+                let announcement = self.announcement(&discovery_cfg.name)?;
+                debug!("Announcing Host as {:?}", &announcement);
+                let discovery_cfg = discovery_cfg.clone();
+                let task_announce = self.rt_handle.spawn(async move {
+                    announce(discovery_cfg, announcement).await;
+                });
+                self.task_announce = Some(task_announce);
+            }
+        }
+
         Ok(())
     }
 
-    /// Shuts down all networking connections and releases Host object handle
-    /// This also makes sure that temporary sled::Db's built are also dropped
-    /// following the shutdown of a Host
-    #[tracing::instrument]
-    pub fn stop(&self) -> Result<(), crate::Error> {
-        match self.connections.lock() {
-            Ok(connections) => {
-                for conn in &*connections {
-                    debug!("Aborting connection: {}", conn.name);
-                    conn.handle.abort();
+    /// Allow Host to begin accepting incoming connections from async code.
+    ///
+    /// Starting the Host only spawns its tasks, so this is the same as [`Host::start`], which
+    /// may also be called from async code.
+    pub async fn start_async(&mut self) -> Result<(), crate::Error> {
+        self.start()
+    }
+
+    /// Describe the endpoints the Host is listening on, for discovery by Nodes
+    fn announcement(&self, name: &str) -> Result<Announcement, crate::Error> {
+        let mut endpoints = Vec::new();
+        if let Some(tcp_cfg) = &self.cfg.tcp_cfg {
+            endpoints.push(HostEndpoint {
+                transport: Transport::Tcp,
+                addr: tcp_cfg.socket_addr()?,
+            });
+        }
+        if let Some(udp_cfg) = &self.cfg.udp_cfg {
+            endpoints.push(HostEndpoint {
+                transport: Transport::Udp,
+                addr: udp_cfg.socket_addr()?,
+            });
+        }
+        #[cfg(feature = "quic")]
+        if let Some(quic_cfg) = &self.cfg.quic_cfg {
+            endpoints.push(HostEndpoint {
+                transport: Transport::Quic,
+                addr: quic_cfg.network_cfg.socket_addr()?,
+            });
+        }
+
+        Ok(Announcement {
+            version: PROTOCOL_VERSION,
+            name: name.to_string(),
+            endpoints,
+        })
+    }
+
+    /// Shut the Host down in an orderly way, after which it may be started again.
+    ///
+    /// The Host stops accepting connections and tells connected Nodes it's shutting down with
+    /// `HostError::ShuttingDown`. Connections are given `HostConfig::shutdown_timeout` to finish
+    /// the requests they're handling before being aborted, and then the store is flushed to disk.
+    ///
+    /// This blocks on the Host's runtime, so from async code use [`Host::stop_async`] instead.
+    #[tracing::instrument(skip(self))]
+    pub fn stop(&mut self) -> Result<ShutdownReport, crate::Error> {
+        let rt_handle = self.rt_handle.clone();
+        rt_handle.block_on(self.stop_async())
+    }
+
+    /// Shut the Host down in an orderly way from async code, as [`Host::stop`] does
+    pub async fn stop_async(&mut self) -> Result<ShutdownReport, crate::Error> {
+        let started = Instant::now();
+        let (listeners, udp, connections) = self.begin_shutdown()?;
+        let (drained, aborted) =
+            drain(listeners, udp, connections, self.cfg.shutdown_timeout).await;
+        let flushed_bytes = self.store.flush_async().await?;
+
+        let report = ShutdownReport {
+            drained,
+            aborted,
+            flushed_bytes,
+            elapsed: started.elapsed(),
+        };
+        info!("Host shut down: {:?}", &report);
+        Ok(report)
+    }
+
+    /// Signal every task to shut down, handing back the listener tasks, the UDP task,
+    /// and the connections still open
+    #[allow(clippy::type_complexity)]
+    fn begin_shutdown(
+        &mut self,
+    ) -> Result<(Vec<JoinHandle<()>>, Option<JoinHandle<()>>, Vec<Connection>), crate::Error> {
+        // Connections leave the registry as soon as they close, which they start doing once
+        // signalled, so they're taken first to be sure they're waited on. Any registered in
+        // the meantime are taken with the rest afterwards.
+        let mut connections = self.registry.take()?;
+        self.shutdown.send_replace(true);
+
+        let mut listeners: Vec<JoinHandle<()>> = Vec::new();
+        listeners.extend(self.task_listen_tcp.take());
+        #[cfg(feature = "quic")]
+        listeners.extend(self.task_listen_quic.take());
+        #[cfg(unix)]
+        if let Some(task) = self.task_listen_uds.take() {
+            listeners.push(task);
+            if let Some(uds_cfg) = &self.cfg.uds_cfg {
+                if let Err(e) = std::fs::remove_file(&uds_cfg.path) {
+                    warn!("Unable to remove Host UDS socket file: {}", e);
                 }
-                Ok(())
             }
-            Err(_) => Err(crate::Error::LockFailure),
         }
+        #[cfg(all(unix, feature = "shm"))]
+        if let Some(task) = self.task_listen_shm.take() {
+            listeners.push(task);
+            if let Some(shm_cfg) = &self.cfg.shm_cfg {
+                if let Err(e) = std::fs::remove_file(&shm_cfg.path) {
+                    warn!("Unable to remove Host shared-memory socket file: {}", e);
+                }
+            }
+        }
+        listeners.extend(self.task_announce.take());
+        listeners.extend(self.task_heartbeat.take());
+
+        connections.extend(self.registry.take()?);
+        Ok((listeners, self.task_listen_udp.take(), connections))
     }
 
     /// Create a vector of topics based on UTF-8 Sled tree names
@@ -521,26 +1097,58 @@ impl Host {
                 strings.push(name.to_string());
             }
         }
-        // Remove default sled tree name
-        let index = strings.iter().position(|x| *x == "__sled__default");
-        if let Some(n) = index {
-            strings.remove(n);
-        }
+        // Remove the trees that don't hold topics
+        strings.retain(|name| !is_internal_tree(name));
         strings.sort();
         strings
     }
 
+    /// Description of the data held by `topic`, from the schema registry.
+    ///
+    /// Returns `None` when the topic's data type has no registered schema, such as data
+    /// labelled with its `type_name`.
+    pub fn schema(&self, topic: impl AsRef<str>) -> Result<Option<SchemaDescription>, Error> {
+        let types = self.store.open_tree(TYPES_TREE)?;
+        match types.get(topic.as_ref())? {
+            Some(data_type) => schema::lookup(&self.store, &String::from_utf8_lossy(&data_type)),
+            None => Err(Error::HostOperation(error::HostError::NonExistentTopic)),
+        }
+    }
+
+    /// Describe every connection open to the Host, in the order they were made.
+    ///
+    /// UDP Nodes are listed from their handshake on, as there's no connection to close.
+    pub fn connections(&self) -> Result<Vec<ConnectionInfo>, crate::Error> {
+        self.registry.list()
+    }
+
+    /// Describe the open connection with the given ID
+    pub fn connection(&self, id: ConnectionId) -> Option<ConnectionInfo> {
+        self.registry.get(id)
+    }
+
+    /// Describe every topic the Host holds, with how much has been published to each
+    pub fn topic_info(&self) -> Result<Vec<TopicInfo>, crate::Error> {
+        self.registry.stats.topics(&self.store)
+    }
+
+    /// Summarize everything that has passed through the Host since it was created
+    pub fn stats(&self) -> Result<HostStats, crate::Error> {
+        self.registry.stats.host(&self.store, self.registry.len())
+    }
+
     /// Print information about all Host connections
     pub fn print_connections(&mut self) -> Result<(), crate::Error> {
-        match self.connections.lock() {
-            Ok(connections) => {
-                for conn in &*connections {
-                    let name = conn.name.clone();
-                    println!("\t- {}:{}", name, &conn.stream_addr);
-                }
-                Ok(())
-            }
-            Err(_) => Err(crate::Error::LockFailure),
+        for (info, node_handshake) in self.registry.handshakes()? {
+            println!(
+                "\t- {}:{} over {} (protocol v{}, {:?})",
+                info.name,
+                info.remote_addr,
+                info.transport,
+                node_handshake.version,
+                node_handshake.features
+            );
         }
+        Ok(())
     }
 }
@@ -0,0 +1,200 @@
+// Tokio for async
+use tokio::runtime::Handle;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::sync::Mutex as TokioMutex;
+use tokio::time::Duration;
+// Tracing for logging
+use tracing::*;
+
+use crate::discovery::Transport;
+use crate::handshake::{rejected, Handshake};
+use crate::host::acl::{AccessControl, Authorizer};
+use crate::host::registry::{Registry, Tracker};
+use crate::host::shutdown::{notice, Shutdown};
+use crate::host::GenericStore;
+use crate::node::in_process::InProcessChannel;
+use crate::prelude::*;
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::sync::Arc;
+
+/// Cloneable handle for connecting Nodes in the same process directly to a `Host`.
+///
+/// Messages are passed over channels rather than sockets, so they skip the wire encoding
+/// entirely; only the payload is serialized, once, for the Host's store.
+#[derive(Debug, Clone)]
+pub struct HostHandle {
+    pub(crate) db: sled::Db,
+    pub(crate) rt_handle: Handle,
+    pub(crate) registry: Arc<Registry>,
+    pub(crate) acl: Option<Arc<AccessControl>>,
+    pub(crate) tokens: Arc<HashMap<String, String>>,
+    pub(crate) shutdown: Shutdown,
+}
+
+impl HostHandle {
+    /// Open a new connection to the Host once it accepts the Node's handshake
+    pub(crate) fn connect(&self, handshake: Handshake) -> Result<InProcessChannel, Error> {
+        if self.shutdown.is_stopping() {
+            return Err(Error::HostOperation(crate::error::HostError::ShuttingDown));
+        }
+        // Names aren't sent anywhere, so only the Host's tokens limit them
+        let handshake = handshake.validate(usize::MAX, &self.tokens)?;
+        let name = handshake.name.clone();
+        let (tx_request, rx_request) = unbounded_channel();
+        let (tx_response, rx_response) = unbounded_channel();
+
+        let db = self.db.clone();
+        let auth = Authorizer::new(self.acl.clone(), name.clone());
+        let shutdown = self.shutdown.clone();
+        debug!("Host received in-process connection from {:?}", &name);
+        handshake.register_schema(&self.db);
+        let rt_handle = &self.rt_handle;
+        self.registry.register(
+            handshake,
+            name,
+            Transport::InProcess,
+            "in-process".to_string(),
+            |tracker| {
+                rt_handle.spawn(async move {
+                    process_in_process(
+                        rx_request,
+                        tx_response,
+                        db,
+                        auth,
+                        shutdown,
+                        tracker.clone(),
+                    )
+                    .await;
+                    tracker.close();
+                })
+            },
+        )?;
+
+        Ok(InProcessChannel {
+            tx: tx_request,
+            rx: TokioMutex::new(rx_response),
+        })
+    }
+}
+
+/// Host process for handling messages from a Node in the same process
+#[tracing::instrument(skip_all)]
+#[inline]
+pub async fn process_in_process(
+    mut rx: UnboundedReceiver<GenericMsg>,
+    tx: UnboundedSender<GenericMsg>,
+    mut db: sled::Db,
+    auth: Authorizer,
+    mut shutdown: Shutdown,
+    tracker: Tracker,
+) {
+    loop {
+        let msg = tokio::select! {
+            msg = rx.recv() => match msg {
+                Some(msg) => msg,
+                None => break,
+            },
+            _ = shutdown.wait() => {
+                send_response(&tx, notice(), &tracker);
+                break;
+            }
+        };
+        // Messages aren't encoded in-process, so only their payloads are counted
+        tracker.received(msg.data.len());
+        if let Err(e) = auth.check(&msg) {
+            if !send_response(&tx, GenericMsg::result(Err(e)), &tracker) {
+                break;
+            }
+            continue;
+        }
+
+        let response = match &msg.msg_type {
+            MsgType::Subscribe => {
+                start_subscription(msg.clone(), db.clone(), &tx, &mut shutdown, &tracker).await;
+                return;
+            }
+            MsgType::Get => match tracker.get_generic_nth(&db, &msg.topic, 0) {
+                Ok(g) => g,
+                Err(e) => GenericMsg::result(Err(e)),
+            },
+            MsgType::GetNth(n) => match tracker.get_generic_nth(&db, &msg.topic, *n) {
+                Ok(g) => g,
+                Err(e) => GenericMsg::result(Err(e)),
+            },
+            MsgType::Set => GenericMsg::result(tracker.insert_generic(&mut db, msg)),
+            MsgType::Topics => match db.topics() {
+                Ok(topics) => {
+                    let msg = Msg::new(MsgType::Topics, "", topics);
+                    match msg.to_generic() {
+                        Ok(msg) => msg,
+                        Err(e) => GenericMsg::result(Err(e)),
+                    }
+                }
+                Err(e) => GenericMsg::result(Err(e)),
+            },
+            MsgType::Handshake => {
+                GenericMsg::result(Err(rejected("the handshake was already completed")))
+            }
+            MsgType::Heartbeat => GenericMsg::heartbeat(),
+            MsgType::Result(result) => {
+                if let Err(e) = result {
+                    error!("{}", e);
+                }
+                continue;
+            }
+        };
+
+        if !send_response(&tx, response, &tracker) {
+            // The Node has been dropped
+            break;
+        }
+    }
+}
+
+async fn start_subscription(
+    msg: GenericMsg,
+    db: sled::Db,
+    tx: &UnboundedSender<GenericMsg>,
+    shutdown: &mut Shutdown,
+    tracker: &Tracker,
+) {
+    let specialized: Msg<Duration> = match msg.clone().try_into() {
+        Ok(specialized) => specialized,
+        Err(e) => {
+            error!("{}", e);
+            return;
+        }
+    };
+    let rate = specialized.data;
+    tracker.subscribed(&msg.topic);
+
+    loop {
+        let response = match tracker.get_generic_nth(&db, &msg.topic, 0) {
+            Ok(g) => g,
+            Err(e) => GenericMsg::result(Err(e)),
+        };
+        if !send_response(tx, response, tracker) {
+            debug!("Ending in-process subscription on {}", &msg.topic);
+            break;
+        }
+        if !shutdown.sleep(rate).await {
+            send_response(tx, notice(), tracker);
+            break;
+        }
+    }
+}
+
+/// Send a response to the Node, returning whether it's still listening
+fn send_response(
+    tx: &UnboundedSender<GenericMsg>,
+    response: GenericMsg,
+    tracker: &Tracker,
+) -> bool {
+    let n = response.data.len();
+    if tx.send(response).is_err() {
+        return false;
+    }
+    tracker.sent(n);
+    true
+}
@@ -1,3 +1,4 @@
+mod acl;
 mod config;
 #[allow(clippy::module_inception)]
 pub mod host;
@@ -5,12 +6,29 @@ pub mod network_config;
 #[cfg(feature = "quic")]
 pub mod quic;
 
+mod in_process;
+mod registry;
+#[cfg(all(unix, feature = "shm"))]
+mod shm;
+mod shutdown;
+mod stats;
 mod tcp;
 mod udp;
+#[cfg(unix)]
+mod uds;
 
+pub use crate::host::acl::{AccessControl, Authorizer, Operation};
 pub use crate::host::config::*;
 pub use crate::host::host::*;
-pub use crate::host::network_config::{QuicConfig, TcpConfig, UdpConfig};
+pub use crate::host::in_process::HostHandle;
+pub use crate::host::network_config::{
+    QuicConfig, ShmConfig, TcpConfig, TlsConfig, UdpConfig, UdsConfig,
+};
+pub use crate::host::registry::{
+    Connection, ConnectionId, ConnectionInfo, CONNECTIONS_TOPIC, SYSTEM_TOPIC_PREFIX,
+};
+pub use crate::host::shutdown::ShutdownReport;
+pub use crate::host::stats::{HostStats, TopicInfo, STATS_TOPIC, TOPICS_TOPIC};
 
 #[cfg(feature = "quic")]
 pub use crate::host::quic::generate_certs;
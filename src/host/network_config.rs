@@ -1,12 +1,29 @@
+use crate::networks::get_ip;
+use crate::psk::PreSharedKey;
+use crate::Error;
+use std::net::{IpAddr, SocketAddr};
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 /// Configuration for network interfaces
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct NetworkConfig {
     pub interface: String,
     pub socket_num: u16,
+    /// Explicit address to listen on, taking precedence over `interface` and `socket_num`
+    pub bind_addr: Option<SocketAddr>,
+    /// Restrict sockets bound to IPv6 addresses to IPv6 traffic only, rather than dual-stack
+    pub ipv6_only: bool,
     pub max_buffer_size: usize,
     pub max_name_size: usize,
+    /// Encrypt TCP connections with TLS
+    #[cfg(feature = "tls")]
+    pub tls: Option<TlsConfig>,
+    /// Encrypt and authenticate UDP datagrams with a key shared by the Host and its Nodes
+    pub psk: Option<PreSharedKey>,
+    /// How long a UDP Node may send nothing before it's forgotten and has to shake hands again,
+    /// if ever
+    pub peer_timeout: Option<Duration>,
 }
 
 impl NetworkConfig {
@@ -15,14 +32,59 @@ impl NetworkConfig {
         NetworkConfig {
             interface: interface.into(),
             socket_num: 25_000,
+            bind_addr: None,
+            ipv6_only: false,
             max_buffer_size: 10_000,
             max_name_size: 100,
+            #[cfg(feature = "tls")]
+            tls: None,
+            psk: None,
+            peer_timeout: None,
+        }
+    }
+
+    /// Create a config listening on an explicit socket address, e.g. `[::]:25000` for all
+    /// IPv4 and IPv6 interfaces
+    pub fn from_addr(bind_addr: impl Into<SocketAddr>) -> Self {
+        let bind_addr = bind_addr.into();
+        NetworkConfig {
+            socket_num: bind_addr.port(),
+            bind_addr: Some(bind_addr),
+            ..NetworkConfig::default("")
+        }
+    }
+
+    /// Listen on an explicit socket address instead of the network interface's address
+    pub fn set_bind_addr(mut self, bind_addr: impl Into<SocketAddr>) -> NetworkConfig {
+        let bind_addr = bind_addr.into();
+        self.socket_num = bind_addr.port();
+        self.bind_addr = Some(bind_addr);
+        self
+    }
+
+    /// Set whether sockets bound to IPv6 addresses should refuse IPv4 traffic
+    pub fn set_ipv6_only(mut self, ipv6_only: bool) -> NetworkConfig {
+        self.ipv6_only = ipv6_only;
+        self
+    }
+
+    /// Resolve the socket address the Host should listen on
+    pub fn socket_addr(&self) -> Result<SocketAddr, Error> {
+        match self.bind_addr {
+            Some(bind_addr) => Ok(bind_addr),
+            None => {
+                let ip = get_ip(&self.interface)?;
+                Ok(SocketAddr::new(IpAddr::V4(ip), self.socket_num))
+            }
         }
     }
 
     /// Set the socket number on the network port
     pub fn set_socket_num(mut self, socket_num: impl Into<u16>) -> NetworkConfig {
         self.socket_num = socket_num.into();
+        if let Some(bind_addr) = &mut self.bind_addr {
+            bind_addr.set_port(self.socket_num);
+        }
         self
     }
 
@@ -37,6 +99,26 @@ impl NetworkConfig {
         self.max_name_size = max_name_size;
         self
     }
+
+    /// Require TCP connections to be encrypted with TLS; ignored by the UDP listener
+    #[cfg(feature = "tls")]
+    pub fn set_tls(mut self, tls: TlsConfig) -> NetworkConfig {
+        self.tls = Some(tls);
+        self
+    }
+
+    /// Require UDP datagrams to be encrypted with a pre-shared key; ignored by the TCP listener
+    pub fn set_psk(mut self, psk: PreSharedKey) -> NetworkConfig {
+        self.psk = Some(psk);
+        self
+    }
+
+    /// Forget UDP Nodes that send nothing for `peer_timeout`, even without heartbeats, rather
+    /// than keeping them until the Host stops; ignored by the TCP listener
+    pub fn set_peer_timeout(mut self, peer_timeout: Duration) -> NetworkConfig {
+        self.peer_timeout = Some(peer_timeout);
+        self
+    }
 }
 
 /// Strongly-typed alias of `NetworkConfig` for TCP configuration
@@ -50,19 +132,17 @@ pub struct QuicConfig {
     pub network_cfg: NetworkConfig,
     pub cert_path: PathBuf,
     pub key_path: PathBuf,
+    /// CA bundle used to verify client certificates; when set, Nodes must authenticate themselves
+    pub client_ca_path: Option<PathBuf>,
 }
 
 impl Default for QuicConfig {
     fn default() -> Self {
         QuicConfig {
-            network_cfg: NetworkConfig {
-                interface: "lo".into(),
-                socket_num: 25_000,
-                max_buffer_size: 10_000,
-                max_name_size: 100,
-            },
+            network_cfg: NetworkConfig::default("lo"),
             cert_path: Path::new("target").join("cert.pem"),
             key_path: Path::new("target").join("priv_key.pem"),
+            client_ca_path: None,
         }
     }
 }
@@ -71,14 +151,158 @@ impl QuicConfig {
     /// Create a new `QuicConfig` on a defined network interface
     pub fn new(interface: impl Into<String>) -> Self {
         QuicConfig {
-            network_cfg: NetworkConfig {
-                interface: interface.into(),
-                socket_num: 25_000,
-                max_buffer_size: 10_000,
-                max_name_size: 100,
-            },
+            network_cfg: NetworkConfig::default(interface),
             cert_path: Path::new("target").join("cert.pem"),
             key_path: Path::new("target").join("priv_key.pem"),
+            client_ca_path: None,
+        }
+    }
+
+    /// Create a new `QuicConfig` listening on an explicit socket address
+    pub fn from_addr(bind_addr: impl Into<SocketAddr>) -> Self {
+        QuicConfig {
+            network_cfg: NetworkConfig::from_addr(bind_addr),
+            ..QuicConfig::default()
+        }
+    }
+
+    /// Require connecting Nodes to present a certificate signed by one of the CAs in `path`
+    pub fn set_client_ca_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.client_ca_path = Some(path.into());
+        self
+    }
+}
+
+/// Certificate paths for encrypting the Host's TCP listener with TLS
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TlsConfig {
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+    /// CA bundle used to verify client certificates; when set, Nodes must authenticate themselves
+    pub client_ca_path: Option<PathBuf>,
+}
+
+impl Default for TlsConfig {
+    fn default() -> Self {
+        TlsConfig::new(
+            Path::new("target").join("cert.pem"),
+            Path::new("target").join("priv_key.pem"),
+        )
+    }
+}
+
+impl TlsConfig {
+    /// Create a new `TlsConfig` from the Host's certificate and private key
+    pub fn new(cert_path: impl Into<PathBuf>, key_path: impl Into<PathBuf>) -> Self {
+        TlsConfig {
+            cert_path: cert_path.into(),
+            key_path: key_path.into(),
+            client_ca_path: None,
+        }
+    }
+
+    /// Require connecting Nodes to present a certificate signed by one of the CAs in `path`
+    pub fn set_client_ca_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.client_ca_path = Some(path.into());
+        self
+    }
+}
+
+/// Configuration for the Host's Unix domain socket listener
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct UdsConfig {
+    /// Filesystem path at which the listening socket is created
+    pub path: PathBuf,
+    /// Unix file mode applied to the socket after binding, restricting which users may connect
+    pub permissions: Option<u32>,
+    pub max_buffer_size: usize,
+    pub max_name_size: usize,
+}
+
+impl Default for UdsConfig {
+    fn default() -> Self {
+        UdsConfig::new(Path::new("/tmp").join("meadow.sock"))
+    }
+}
+
+impl UdsConfig {
+    /// Create a new `UdsConfig` listening at the specified socket path
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        UdsConfig {
+            path: path.into(),
+            permissions: None,
+            max_buffer_size: 10_000,
+            max_name_size: 100,
         }
     }
+
+    /// Set the Unix file mode (e.g. `0o660`) of the socket once it has been created
+    pub fn set_permissions(mut self, mode: u32) -> UdsConfig {
+        self.permissions = Some(mode);
+        self
+    }
+
+    /// Set the maximum buffer size for packets intended to be received
+    pub fn set_max_buffer_size(mut self, max_buffer_size: usize) -> UdsConfig {
+        self.max_buffer_size = max_buffer_size;
+        self
+    }
+
+    /// Set the maximum buffer size for the name of each topic
+    pub fn set_max_name_size(mut self, max_name_size: usize) -> UdsConfig {
+        self.max_name_size = max_name_size;
+        self
+    }
+}
+
+/// Configuration for the Host's shared-memory listener
+///
+/// Nodes connect to a Unix domain socket at `path`, after which the Host creates a pair of
+/// ring buffers for the connection in `shm_dir`. Payloads are exchanged through the rings,
+/// while the socket only carries lightweight notifications.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ShmConfig {
+    /// Filesystem path at which the listening control socket is created
+    pub path: PathBuf,
+    /// Directory the ring buffer files are created in, ideally a memory-backed filesystem
+    pub shm_dir: PathBuf,
+    /// Size in bytes of each ring buffer, which bounds the largest message that can be sent
+    pub ring_size: usize,
+    pub max_name_size: usize,
+}
+
+impl Default for ShmConfig {
+    fn default() -> Self {
+        ShmConfig::new(Path::new("/tmp").join("meadow_shm.sock"))
+    }
+}
+
+impl ShmConfig {
+    /// Create a new `ShmConfig` listening at the specified control socket path
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        ShmConfig {
+            path: path.into(),
+            shm_dir: PathBuf::from("/dev/shm"),
+            ring_size: 8 * 1024 * 1024,
+            max_name_size: 100,
+        }
+    }
+
+    /// Set the directory in which ring buffer files are created
+    pub fn set_shm_dir(mut self, shm_dir: impl Into<PathBuf>) -> ShmConfig {
+        self.shm_dir = shm_dir.into();
+        self
+    }
+
+    /// Set the size in bytes of each connection's ring buffers
+    pub fn set_ring_size(mut self, ring_size: usize) -> ShmConfig {
+        self.ring_size = ring_size;
+        self
+    }
+
+    /// Set the maximum buffer size for the name of each topic
+    pub fn set_max_name_size(mut self, max_name_size: usize) -> ShmConfig {
+        self.max_name_size = max_name_size;
+        self
+    }
 }
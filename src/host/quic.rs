@@ -2,50 +2,231 @@ use crate::error::{
     Error, HostOperation,
     Quic::{self, *},
 };
+use crate::handshake::{self, rejected, Handshake};
+use crate::host::acl::Authorizer;
+use crate::host::registry::Tracker;
+use crate::host::shutdown::Shutdown;
 use crate::host::GenericStore;
 use crate::prelude::*;
 use futures_util::lock::Mutex;
 use futures_util::StreamExt;
 use quinn::Connection as QuicConnection;
-use std::convert::TryInto;
+use std::collections::HashMap;
+use std::convert::{TryFrom, TryInto};
 use std::sync::{Arc, Mutex as StdMutex};
 use tokio::sync::Mutex as TokioMutex;
-use tokio::time::{sleep, Duration};
+use tokio::time::Duration;
 
 use chrono::Utc;
+use chrono::{DateTime, Datelike, Timelike};
 use postcard::from_bytes;
 use postcard::to_allocvec;
 use quinn::{Endpoint, RecvStream, SendStream, ServerConfig};
-use std::path::PathBuf;
+use rcgen::{
+    BasicConstraints, CertificateParams, DistinguishedName, DnType, ExtendedKeyUsagePurpose, IsCa,
+    KeyPair, KeyUsagePurpose, SanType,
+};
+use rustls::server::AllowAnyAuthenticatedClient;
+use rustls::RootCertStore;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
 use std::{fs, fs::File, io::BufReader};
 use tracing::*;
+use x509_parser::extensions::GeneralName;
 
 /// Configuration struct for generating QUIC private key and certificates
 #[derive(Debug, Clone)]
 pub struct QuicCertGenConfig {
-    subject_alt_names: Vec<String>,
+    subject_alt_names: Vec<SanType>,
+    common_name: Option<String>,
+    validity: Option<std::time::Duration>,
+    client_auth: bool,
     cert_pem_path: PathBuf,
     priv_key_pem_path: PathBuf,
 }
 
 impl Default for QuicCertGenConfig {
+    /// Certificate for `localhost`, written to `target/cert.pem` and `target/priv_key.pem`
     fn default() -> QuicCertGenConfig {
+        QuicCertGenConfig::new().add_dns_name("localhost")
+    }
+}
+
+impl QuicCertGenConfig {
+    /// Create a configuration with no subject alternative names, written to the default paths
+    pub fn new() -> Self {
         QuicCertGenConfig {
-            subject_alt_names: vec!["localhost".into()],
+            subject_alt_names: Vec::new(),
+            common_name: None,
+            validity: None,
+            client_auth: false,
             cert_pem_path: "target/cert.pem".into(),
             priv_key_pem_path: "target/priv_key.pem".into(),
         }
     }
+
+    /// Add a DNS name the Host can be verified as, e.g. the `server_name` Nodes connect with
+    pub fn add_dns_name(mut self, name: impl Into<String>) -> Self {
+        self.subject_alt_names.push(SanType::DnsName(name.into()));
+        self
+    }
+
+    /// Add an IP address the Host can be verified as
+    pub fn add_ip_address(mut self, ip: impl Into<IpAddr>) -> Self {
+        self.subject_alt_names.push(SanType::IpAddress(ip.into()));
+        self
+    }
+
+    /// Set the subject's common name, which a Host records as the name of an authenticated Node
+    pub fn set_common_name(mut self, name: impl Into<String>) -> Self {
+        self.common_name = Some(name.into());
+        self
+    }
+
+    /// Limit the certificate to expiring `validity` from now
+    pub fn set_validity(mut self, validity: std::time::Duration) -> Self {
+        self.validity = Some(validity);
+        self
+    }
+
+    /// Issue the certificate for a Node authenticating itself to a Host, rather than for a Host
+    pub fn set_client_auth(mut self, client_auth: bool) -> Self {
+        self.client_auth = client_auth;
+        self
+    }
+
+    /// Set the path the PEM-encoded certificate is written to
+    pub fn set_cert_pem_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.cert_pem_path = path.into();
+        self
+    }
+
+    /// Set the path the PEM-encoded private key is written to
+    pub fn set_priv_key_pem_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.priv_key_pem_path = path.into();
+        self
+    }
+
+    fn params(&self) -> CertificateParams {
+        let mut params = CertificateParams::default();
+        params.subject_alt_names = self.subject_alt_names.clone();
+        if let Some(name) = &self.common_name {
+            params.distinguished_name = DistinguishedName::new();
+            params
+                .distinguished_name
+                .push(DnType::CommonName, name.as_str());
+        }
+        if let Some(validity) = self.validity {
+            // Validity starts at the beginning of the current UTC day, tolerating some clock skew
+            let now = Utc::now();
+            let today = now.date_naive();
+            let elapsed = std::time::Duration::from_secs(now.num_seconds_from_midnight() as u64);
+            params.not_before =
+                rcgen::date_time_ymd(today.year(), today.month() as u8, today.day() as u8);
+            params.not_after = params.not_before + elapsed + validity;
+        }
+        params
+    }
+
+    fn write(&self, cert_pem: String, priv_key_pem: String) -> Result<(), Error> {
+        fs::write(&self.cert_pem_path, cert_pem)?;
+        fs::write(&self.priv_key_pem_path, priv_key_pem)?;
+        Ok(())
+    }
+}
+
+/// Generate a self-signed certificate and private key
+pub fn generate_certs(config: QuicCertGenConfig) -> Result<(), Error> {
+    let cert = rcgen::Certificate::from_params(config.params())?;
+    config.write(cert.serialize_pem()?, cert.serialize_private_key_pem())
+}
+
+/// Generate a certificate authority, which signs the certificates of a fleet's Host and Nodes.
+///
+/// Nodes trust the CA certificate to identify the Host, and a Host with
+/// `QuicConfig::set_client_ca_path` trusts it to identify Nodes.
+pub fn generate_ca(config: QuicCertGenConfig) -> Result<(), Error> {
+    let mut params = config.params();
+    params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    params.key_usages = vec![KeyUsagePurpose::KeyCertSign, KeyUsagePurpose::CrlSign];
+    let cert = rcgen::Certificate::from_params(params)?;
+    config.write(cert.serialize_pem()?, cert.serialize_private_key_pem())
 }
 
-pub fn generate_certs(config: QuicCertGenConfig) {
-    let cert = rcgen::generate_simple_self_signed(config.subject_alt_names)
-        .expect("Error generating self-signed certificate");
-    let cert_pem = cert.serialize_pem().expect("Error serialzing ");
-    fs::write(config.cert_pem_path, cert_pem).expect("Error writing certificate to file");
+/// Generate a certificate and private key signed by the CA created with [`generate_ca`]
+pub fn generate_signed_certs(
+    config: QuicCertGenConfig,
+    ca_cert_path: impl Into<PathBuf>,
+    ca_key_path: impl Into<PathBuf>,
+) -> Result<(), Error> {
+    let ca_key = KeyPair::from_pem(&fs::read_to_string(ca_key_path.into())?)?;
+    let ca_params =
+        CertificateParams::from_ca_cert_pem(&fs::read_to_string(ca_cert_path.into())?, ca_key)?;
+    let ca = rcgen::Certificate::from_params(ca_params)?;
 
-    let priv_key_pem = cert.serialize_private_key_pem();
-    fs::write(config.priv_key_pem_path, priv_key_pem).expect("Error writing private key to file");
+    let mut params = config.params();
+    params.extended_key_usages = if config.client_auth {
+        vec![ExtendedKeyUsagePurpose::ClientAuth]
+    } else {
+        vec![ExtendedKeyUsagePurpose::ServerAuth]
+    };
+    let cert = rcgen::Certificate::from_params(params)?;
+    config.write(
+        cert.serialize_pem_with_signer(&ca)?,
+        cert.serialize_private_key_pem(),
+    )
+}
+
+/// Summary of a certificate, as shown by the `certs` tool
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CertInfo {
+    pub subject: String,
+    pub issuer: String,
+    pub subject_alt_names: Vec<String>,
+    pub not_before: DateTime<Utc>,
+    pub not_after: DateTime<Utc>,
+    pub is_ca: bool,
+}
+
+/// Describe every certificate in a PEM file
+pub fn inspect_certs(cert_path: impl Into<PathBuf>) -> Result<Vec<CertInfo>, Error> {
+    let file = File::open::<PathBuf>(cert_path.into())?;
+    let mut reader = BufReader::new(file);
+    let mut infos = Vec::new();
+    for der in rustls_pemfile::certs(&mut reader)? {
+        let (_, cert) =
+            x509_parser::parse_x509_certificate(&der).map_err(|_| Error::Quic(InvalidCert))?;
+        let subject_alt_names = match cert.subject_alternative_name() {
+            Ok(Some(extension)) => extension
+                .value
+                .general_names
+                .iter()
+                .map(|name| match name {
+                    GeneralName::DNSName(name) => name.to_string(),
+                    GeneralName::IPAddress(bytes) => match bytes.len() {
+                        4 => IpAddr::from(<[u8; 4]>::try_from(*bytes).unwrap()).to_string(),
+                        16 => IpAddr::from(<[u8; 16]>::try_from(*bytes).unwrap()).to_string(),
+                        _ => format!("{:?}", bytes),
+                    },
+                    other => format!("{:?}", other),
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        infos.push(CertInfo {
+            subject: cert.subject().to_string(),
+            issuer: cert.issuer().to_string(),
+            subject_alt_names,
+            not_before: timestamp(cert.validity().not_before.timestamp())?,
+            not_after: timestamp(cert.validity().not_after.timestamp())?,
+            is_ca: cert.is_ca(),
+        });
+    }
+    Ok(infos)
+}
+
+fn timestamp(secs: i64) -> Result<DateTime<Utc>, Error> {
+    DateTime::<Utc>::from_timestamp(secs, 0).ok_or(Error::Quic(InvalidCert))
 }
 
 pub fn read_certs_from_file(
@@ -73,10 +254,94 @@ pub fn read_certs_from_file(
     }
 }
 
-pub async fn process_quic(stream: (SendStream, RecvStream), mut db: sled::Db, buf: &mut [u8]) {
+/// Read every certificate in a PEM bundle into a store of trusted roots
+pub(crate) fn read_root_store(path: impl Into<PathBuf>) -> Result<RootCertStore, crate::Error> {
+    let file = File::open::<PathBuf>(path.into())?;
+    let mut reader = BufReader::new(file);
+    let mut roots = RootCertStore::empty();
+    for cert in rustls_pemfile::certs(&mut reader)? {
+        if let Err(e) = roots.add(&rustls::Certificate(cert)) {
+            error!("Error adding certificate: {:?}", e);
+            return Err(Error::Quic(Webpki));
+        }
+    }
+    Ok(roots)
+}
+
+/// Build the Host's TLS configuration, requiring client certificates if `client_ca_path` is set
+pub(crate) fn server_crypto_config(
+    cert_path: &Path,
+    key_path: &Path,
+    client_ca_path: Option<&Path>,
+) -> Result<rustls::ServerConfig, crate::Error> {
+    let (certs, key) = read_certs_from_file(cert_path, key_path)?;
+    let builder = rustls::ServerConfig::builder()
+        .with_safe_default_cipher_suites()
+        .with_safe_default_kx_groups()
+        .with_protocol_versions(&[&rustls::version::TLS13])?;
+    let crypto = match client_ca_path {
+        Some(path) => builder
+            .with_client_cert_verifier(AllowAnyAuthenticatedClient::new(read_root_store(path)?))
+            .with_single_cert(certs, key)?,
+        None => builder.with_no_client_auth().with_single_cert(certs, key)?,
+    };
+    Ok(crypto)
+}
+
+/// Build the Host's QUIC server configuration, requiring client certificates if a CA bundle is set
+pub(crate) fn server_config(cfg: &QuicConfig) -> Result<ServerConfig, crate::Error> {
+    let mut crypto =
+        server_crypto_config(&cfg.cert_path, &cfg.key_path, cfg.client_ca_path.as_deref())?;
+    // Mirrors quinn's `ServerConfig::with_single_cert`
+    crypto.max_early_data_size = u32::MAX;
+    Ok(ServerConfig::with_crypto(Arc::new(crypto)))
+}
+
+/// Common name of the certificate a client authenticated with, if any
+pub(crate) fn peer_name(connection: &QuicConnection) -> Option<String> {
+    let certs = connection
+        .peer_identity()?
+        .downcast::<Vec<rustls::Certificate>>()
+        .ok()?;
+    common_name(certs.first()?)
+}
+
+pub(crate) fn common_name(cert: &rustls::Certificate) -> Option<String> {
+    let (_, cert) = x509_parser::parse_x509_certificate(&cert.0).ok()?;
+    let name = cert.subject().iter_common_name().next()?.as_str().ok()?;
+    Some(name.to_string())
+}
+
+/// Accept the handshake a Node sends on the first stream it opens over a QUIC connection
+#[tracing::instrument(skip(connection, tokens))]
+pub async fn handshake(
+    connection: &QuicConnection,
+    max_buffer_size: usize,
+    max_name_size: usize,
+    tokens: &HashMap<String, String>,
+) -> Result<Handshake, Error> {
+    let (mut tx, mut rx) = connection.accept_bi().await?;
+    let mut buf = vec![0u8; max_buffer_size];
+    let n = rx.read(&mut buf).await?.ok_or(Error::Quic(Connection))?;
+    let result = handshake::accept(&buf[..n], max_name_size, tokens);
+    tx.write_all(&handshake::reply(&result)?.as_bytes()?)
+        .await?;
+    tx.finish().await?;
+    result
+}
+
+pub(crate) async fn process_quic(
+    stream: (SendStream, RecvStream),
+    mut db: sled::Db,
+    buf: &mut [u8],
+    auth: Authorizer,
+    mut shutdown: Shutdown,
+    tracker: Tracker,
+) {
     let (mut tx, mut rx) = stream;
 
     if let Ok(Some(n)) = rx.read(buf).await {
+        tracker.received(n);
         let bytes = &buf[..n];
         let msg: GenericMsg = match from_bytes(bytes) {
             Ok(msg) => msg,
@@ -87,77 +352,159 @@ pub async fn process_quic(stream: (SendStream, RecvStream), mut db: sled::Db, bu
         };
         info!("{:?}", &msg);
 
-        match msg.msg_type {
+        if let Err(e) = auth.check(&msg) {
+            if let Err(e) = write_response(&mut tx, &GenericMsg::result(Err(e)), &tracker).await {
+                error!("{}", e);
+            }
+            return;
+        }
+
+        let response = match msg.msg_type {
             MsgType::Result(result) => {
                 if let Err(e) = result {
                     error!("Received {}", e);
                 }
+                return;
             }
-            MsgType::Set => {
-                let response = GenericMsg::result(db.insert_generic(msg));
-                if let Ok(return_bytes) = response.as_bytes() {
-                    if let Err(e) = tx.write(&return_bytes).await {
-                        error!("{}", e);
-                    }
-                }
-            }
-            MsgType::Get => {
-                let response = match db.get_generic_nth(&msg.topic, 0) {
-                    Ok(g) => g,
-                    Err(e) => GenericMsg::result(Err(e)),
-                };
-                if let Ok(return_bytes) = response.as_bytes() {
-                    if let Err(e) = tx.write(&return_bytes).await {
-                        error!("{}", e);
-                    }
-                }
+            MsgType::Handshake => {
+                GenericMsg::result(Err(rejected("the handshake was already completed")))
             }
-            MsgType::GetNth(n) => {
-                let response = match db.get_generic_nth(&msg.topic, n) {
-                    Ok(g) => g,
-                    Err(e) => GenericMsg::result(Err(e)),
-                };
-                if let Ok(return_bytes) = response.as_bytes() {
-                    if let Err(e) = tx.write(&return_bytes).await {
-                        error!("{}", e);
-                    }
-                }
-            }
-            MsgType::Topics => {
-                let response = match db.topics() {
-                    Ok(mut topics) => {
-                        topics.sort();
-                        let msg = Msg::new(MsgType::Topics, "", topics);
-                        match msg.to_generic() {
-                            Ok(msg) => msg,
-                            Err(e) => GenericMsg::result(Err(e)),
-                        }
-                    }
-                    Err(e) => GenericMsg::result(Err(e)),
-                };
-                if let Ok(return_bytes) = response.as_bytes() {
-                    if let Err(e) = tx.write(&return_bytes).await {
-                        error!("{}", e);
+            MsgType::Heartbeat => GenericMsg::heartbeat(),
+            MsgType::Set => GenericMsg::result(tracker.insert_generic(&mut db, msg)),
+            MsgType::Get => match tracker.get_generic_nth(&db, &msg.topic, 0) {
+                Ok(g) => g,
+                Err(e) => GenericMsg::result(Err(e)),
+            },
+            MsgType::GetNth(n) => match tracker.get_generic_nth(&db, &msg.topic, n) {
+                Ok(g) => g,
+                Err(e) => GenericMsg::result(Err(e)),
+            },
+            MsgType::Topics => match db.topics() {
+                Ok(mut topics) => {
+                    topics.sort();
+                    let msg = Msg::new(MsgType::Topics, "", topics);
+                    match msg.to_generic() {
+                        Ok(msg) => msg,
+                        Err(e) => GenericMsg::result(Err(e)),
                     }
                 }
-            }
+                Err(e) => GenericMsg::result(Err(e)),
+            },
             MsgType::Subscribe => {
                 let specialized: Msg<Duration> = msg.clone().try_into().unwrap();
                 let rate = specialized.data;
+                tracker.subscribed(&msg.topic);
 
                 loop {
-                    let response = match db.get_generic_nth(&msg.topic, 0) {
+                    let response = match tracker.get_generic_nth(&db, &msg.topic, 0) {
                         Ok(g) => g,
                         Err(e) => GenericMsg::result(Err(e)),
                     };
-                    if let Ok(return_bytes) = response.as_bytes() {
-                        if let Err(e) = tx.write(&return_bytes).await {
-                            error!("{}", e);
-                        }
+                    if let Err(e) = write_response(&mut tx, &response, &tracker).await {
+                        // The connection has closed, so there's no one left to send to
+                        debug!("Ending QUIC subscription on {}: {}", &msg.topic, e);
+                        return;
+                    }
+                    if !shutdown.sleep(rate).await {
+                        return;
                     }
-                    sleep(rate).await;
                 }
             }
+        };
+
+        if let Err(e) = write_response(&mut tx, &response, &tracker).await {
+            error!("{}", e);
         }
     }
 }
+
+async fn write_response(
+    tx: &mut SendStream,
+    response: &GenericMsg,
+    tracker: &Tracker,
+) -> Result<(), Error> {
+    let return_bytes = response.as_bytes()?;
+    tx.write_all(&return_bytes).await?;
+    tracker.sent(return_bytes.len());
+    Ok(())
+}
+
+#[test]
+fn common_name_from_certificate() {
+    let mut params = rcgen::CertificateParams::new(vec!["localhost".to_string()]);
+    params
+        .distinguished_name
+        .push(rcgen::DnType::CommonName, "pose_node");
+    let cert = rcgen::Certificate::from_params(params).unwrap();
+    let der = rustls::Certificate(cert.serialize_der().unwrap());
+    assert_eq!(common_name(&der), Some("pose_node".to_string()));
+}
+
+#[test]
+fn generated_subject_alt_names() {
+    let dir = std::env::temp_dir();
+    let cert_path = dir.join("meadow_generated_subject_alt_names_cert.pem");
+    let key_path = dir.join("meadow_generated_subject_alt_names_key.pem");
+    generate_certs(
+        QuicCertGenConfig::new()
+            .add_dns_name("meadow-host.example")
+            .add_ip_address([10, 0, 0, 7])
+            .set_cert_pem_path(&cert_path)
+            .set_priv_key_pem_path(&key_path),
+    )
+    .unwrap();
+    let (certs, _key) = read_certs_from_file(&cert_path, &key_path).unwrap();
+    let (_, cert) = x509_parser::parse_x509_certificate(&certs[0].0).unwrap();
+    let names = cert.subject_alternative_name().unwrap().unwrap().value;
+    assert_eq!(
+        names.general_names,
+        vec![
+            GeneralName::DNSName("meadow-host.example"),
+            GeneralName::IPAddress(&[10, 0, 0, 7]),
+        ]
+    );
+}
+
+#[test]
+fn inspect_signed_certs() {
+    let dir = std::env::temp_dir();
+    let (ca_cert, ca_key) = (
+        dir.join("meadow_inspect_ca.pem"),
+        dir.join("meadow_inspect_ca_key.pem"),
+    );
+    let (host_cert, host_key) = (
+        dir.join("meadow_inspect_host.pem"),
+        dir.join("meadow_inspect_host_key.pem"),
+    );
+    generate_ca(
+        QuicCertGenConfig::new()
+            .set_common_name("fleet")
+            .set_cert_pem_path(&ca_cert)
+            .set_priv_key_pem_path(&ca_key),
+    )
+    .unwrap();
+    generate_signed_certs(
+        QuicCertGenConfig::new()
+            .add_dns_name("meadow-host.example")
+            .add_ip_address([10, 0, 0, 7])
+            .set_validity(std::time::Duration::from_secs(30 * 24 * 60 * 60))
+            .set_cert_pem_path(&host_cert)
+            .set_priv_key_pem_path(&host_key),
+        &ca_cert,
+        &ca_key,
+    )
+    .unwrap();
+
+    let ca = inspect_certs(&ca_cert).unwrap();
+    assert!(ca[0].is_ca);
+    assert_eq!(ca[0].subject, "CN=fleet");
+
+    let host = inspect_certs(&host_cert).unwrap();
+    assert!(!host[0].is_ca);
+    assert_eq!(host[0].issuer, "CN=fleet");
+    assert_eq!(
+        host[0].subject_alt_names,
+        vec!["meadow-host.example".to_string(), "10.0.0.7".to_string()]
+    );
+    assert_eq!((host[0].not_after - host[0].not_before).num_days(), 30);
+}
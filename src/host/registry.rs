@@ -0,0 +1,423 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+use tracing::*;
+
+use crate::discovery::Transport;
+use crate::error::{Error, HostError};
+use crate::handshake::Handshake;
+use crate::heartbeat::{HeartbeatConfig, Liveness};
+use crate::host::stats::{Stats, STATS_TOPIC, TOPICS_TOPIC};
+use crate::host::GenericStore;
+use crate::msg::{GenericMsg, Msg, MsgType};
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::Duration;
+
+/// Prefix of every topic maintained by the Host, which Nodes can't publish to
+pub const SYSTEM_TOPIC_PREFIX: &str = "_meadow/";
+
+/// Topic on which the Host answers requests and subscriptions with its open connections,
+/// as a `Vec<ConnectionInfo>`
+pub const CONNECTIONS_TOPIC: &str = "_meadow/connections";
+
+/// Identifier the Host gives each connection, unique for the lifetime of the `Host`
+pub type ConnectionId = u64;
+
+/// Description of a connection open to the Host
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ConnectionInfo {
+    pub id: ConnectionId,
+    /// Identity the Host authorizes the Node's operations with
+    pub name: String,
+    pub transport: Transport,
+    /// Address the Node connected from, or the socket path for local transports
+    pub remote_addr: String,
+    pub connected_at: DateTime<Utc>,
+    /// When the Host last received a message on the connection
+    pub last_activity: DateTime<Utc>,
+    /// How recently the Node was heard from, judged against the Host's heartbeats
+    pub liveness: Liveness,
+    /// Topics the Node has subscribed to over the connection
+    pub subscriptions: Vec<String>,
+    pub msgs_received: u64,
+    pub bytes_received: u64,
+    pub msgs_sent: u64,
+    pub bytes_sent: u64,
+}
+
+impl ConnectionInfo {
+    fn new(id: ConnectionId, name: String, transport: Transport, remote_addr: String) -> Self {
+        let now = Utc::now();
+        ConnectionInfo {
+            id,
+            name,
+            transport,
+            remote_addr,
+            connected_at: now,
+            last_activity: now,
+            liveness: Liveness::Alive,
+            subscriptions: Vec::new(),
+            msgs_received: 0,
+            bytes_received: 0,
+            msgs_sent: 0,
+            bytes_sent: 0,
+        }
+    }
+}
+
+/// Task and description of each Hosted connection
+#[derive(Debug)]
+pub struct Connection {
+    /// Task serving the connection; UDP peers share the socket's task instead
+    pub(crate) handle: Option<JoinHandle<()>>,
+    pub(crate) info: Arc<StdMutex<ConnectionInfo>>,
+    /// Handshake the Node connected with, without its token
+    pub(crate) handshake: Handshake,
+}
+
+/// Connections open to a Host, by ID
+#[derive(Debug)]
+pub(crate) struct Registry {
+    next_id: AtomicU64,
+    connections: StdMutex<HashMap<ConnectionId, Connection>>,
+    /// Heartbeats Nodes are expected to send, if connections are dropped for being quiet
+    heartbeat: Option<HeartbeatConfig>,
+    /// Time after which a quiet UDP peer is forgotten, with or without heartbeats, if set
+    peer_timeout: Option<Duration>,
+    /// Activity across every connection, including those since closed
+    pub(crate) stats: Stats,
+}
+
+impl Registry {
+    pub(crate) fn new(heartbeat: Option<HeartbeatConfig>, peer_timeout: Option<Duration>) -> Self {
+        Registry {
+            next_id: AtomicU64::new(0),
+            connections: StdMutex::new(HashMap::new()),
+            heartbeat,
+            peer_timeout,
+            stats: Stats::default(),
+        }
+    }
+
+    /// Register a connection and spawn the task serving it, which is handed the connection's
+    /// `Tracker`. The registry stays locked until the task is recorded, so it can't be
+    /// pruned before it's been added.
+    pub(crate) fn register(
+        self: &Arc<Self>,
+        handshake: Handshake,
+        name: String,
+        transport: Transport,
+        remote_addr: String,
+        spawn: impl FnOnce(Tracker) -> JoinHandle<()>,
+    ) -> Result<ConnectionId, Error> {
+        let mut connections = self.connections.lock().map_err(|_| Error::LockFailure)?;
+        let tracker = self.tracker(name, transport, remote_addr);
+        let id = tracker.id;
+        let info = Arc::clone(&tracker.info);
+        let handle = spawn(tracker);
+        connections.insert(
+            id,
+            Connection {
+                handle: Some(handle),
+                info,
+                handshake,
+            },
+        );
+        Ok(id)
+    }
+
+    /// Register a UDP peer, which has no task of its own
+    pub(crate) fn register_peer(
+        self: &Arc<Self>,
+        handshake: Handshake,
+        remote_addr: String,
+    ) -> Result<Tracker, Error> {
+        let mut connections = self.connections.lock().map_err(|_| Error::LockFailure)?;
+        let tracker = self.tracker(handshake.name.clone(), Transport::Udp, remote_addr);
+        connections.insert(
+            tracker.id,
+            Connection {
+                handle: None,
+                info: Arc::clone(&tracker.info),
+                handshake,
+            },
+        );
+        Ok(tracker)
+    }
+
+    fn tracker(
+        self: &Arc<Self>,
+        name: String,
+        transport: Transport,
+        remote_addr: String,
+    ) -> Tracker {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        Tracker {
+            id,
+            info: Arc::new(StdMutex::new(ConnectionInfo::new(
+                id,
+                name,
+                transport,
+                remote_addr,
+            ))),
+            registry: Arc::clone(self),
+        }
+    }
+
+    /// Remove a connection that has closed
+    pub(crate) fn remove(&self, id: ConnectionId) {
+        if let Ok(mut connections) = self.connections.lock() {
+            connections.remove(&id);
+        }
+    }
+
+    /// Remove every connection, handing them back so their tasks can be stopped
+    pub(crate) fn take(&self) -> Result<Vec<Connection>, Error> {
+        let mut connections = self.connections.lock().map_err(|_| Error::LockFailure)?;
+        Ok(connections
+            .drain()
+            .map(|(_, connection)| connection)
+            .collect())
+    }
+
+    /// Describe every open connection, in the order they were made
+    pub(crate) fn list(&self) -> Result<Vec<ConnectionInfo>, Error> {
+        Ok(self
+            .handshakes()?
+            .into_iter()
+            .map(|(info, _)| info)
+            .collect())
+    }
+
+    /// Describe every open connection along with the handshake it was made with
+    pub(crate) fn handshakes(&self) -> Result<Vec<(ConnectionInfo, Handshake)>, Error> {
+        let connections = self.connections.lock().map_err(|_| Error::LockFailure)?;
+        let mut list: Vec<(ConnectionInfo, Handshake)> = connections
+            .values()
+            .filter_map(|connection| {
+                let info = self.describe(connection)?;
+                Some((info, connection.handshake.clone()))
+            })
+            .collect();
+        list.sort_by_key(|(info, _)| info.id);
+        Ok(list)
+    }
+
+    /// Describe an open connection
+    pub(crate) fn get(&self, id: ConnectionId) -> Option<ConnectionInfo> {
+        let connections = self.connections.lock().ok()?;
+        self.describe(connections.get(&id)?)
+    }
+
+    /// Number of open connections
+    pub(crate) fn len(&self) -> usize {
+        match self.connections.lock() {
+            Ok(connections) => connections.len(),
+            Err(_) => 0,
+        }
+    }
+
+    /// Whether a connection is still open
+    pub(crate) fn contains(&self, id: ConnectionId) -> bool {
+        match self.connections.lock() {
+            Ok(connections) => connections.contains_key(&id),
+            Err(_) => false,
+        }
+    }
+
+    /// Snapshot of a connection's description, with its liveness as of now
+    fn describe(&self, connection: &Connection) -> Option<ConnectionInfo> {
+        let mut info = connection.info.lock().ok()?.clone();
+        info.liveness = self.liveness(&info);
+        Some(info)
+    }
+
+    fn liveness(&self, info: &ConnectionInfo) -> Liveness {
+        match self.heartbeat {
+            // QUIC closes quiet connections itself, and local transports don't send heartbeats
+            Some(heartbeat) if matches!(info.transport, Transport::Tcp | Transport::Udp) => {
+                let silence = (Utc::now() - info.last_activity)
+                    .to_std()
+                    .unwrap_or_default();
+                heartbeat.liveness(silence)
+            }
+            _ => Liveness::Alive,
+        }
+    }
+
+    /// Store a published message, unless it's to one of the Host's own topics
+    pub(crate) fn insert_generic(&self, db: &mut sled::Db, msg: GenericMsg) -> Result<(), Error> {
+        if is_system_topic(&msg.topic) {
+            return Err(Error::HostOperation(HostError::ReadOnlyTopic));
+        }
+        let published = msg.clone();
+        db.insert_generic(msg)?;
+        self.stats.published(&published);
+        Ok(())
+    }
+
+    /// Whether a connection should be dropped: it has missed its heartbeats, or is a UDP peer
+    /// that has been quiet for longer than the peer timeout, if one is set. Without a connection
+    /// to close, these are the only ways to learn that a UDP Node has gone.
+    fn expired(&self, info: &ConnectionInfo) -> bool {
+        if info.liveness == Liveness::Dead {
+            return true;
+        }
+        let silence = (Utc::now() - info.last_activity)
+            .to_std()
+            .unwrap_or_default();
+        match self.peer_timeout {
+            Some(peer_timeout) => info.transport == Transport::Udp && silence > peer_timeout,
+            None => false,
+        }
+    }
+
+    /// Remove every connection that has timed out, stopping the tasks serving them.
+    ///
+    /// UDP peers' subscriptions notice they've been removed and stop on their own.
+    pub(crate) fn reap(&self) -> Result<Vec<ConnectionInfo>, Error> {
+        let mut connections = self.connections.lock().map_err(|_| Error::LockFailure)?;
+        let dead: Vec<ConnectionId> = connections
+            .iter()
+            .filter_map(|(id, connection)| {
+                let info = self.describe(connection)?;
+                self.expired(&info).then_some(*id)
+            })
+            .collect();
+        let mut reaped = Vec::with_capacity(dead.len());
+        for id in dead {
+            if let Some(connection) = connections.remove(&id) {
+                if let Some(handle) = &connection.handle {
+                    handle.abort();
+                }
+                reaped.extend(self.describe(&connection));
+            }
+        }
+        reaped.sort_by_key(|info| info.id);
+        Ok(reaped)
+    }
+}
+
+/// How often connections are checked for having timed out when Nodes don't send heartbeats
+pub(crate) const REAP_PERIOD: Duration = Duration::from_secs(1);
+
+/// Periodically drop the connections of Nodes that have stopped sending heartbeats, and of
+/// UDP peers that have gone quiet
+pub(crate) async fn reap(registry: Arc<Registry>) {
+    let period = match registry.heartbeat {
+        Some(heartbeat) => heartbeat.interval,
+        None => REAP_PERIOD,
+    };
+    let mut interval = tokio::time::interval(period);
+    loop {
+        interval.tick().await;
+        match registry.reap() {
+            Ok(reaped) => {
+                for info in reaped {
+                    warn!(
+                        "Dropping {} connection from {} ({}), which stopped responding",
+                        info.transport, info.name, info.remote_addr
+                    );
+                }
+            }
+            Err(e) => error!("Unable to check connections for heartbeats: {}", e),
+        }
+    }
+}
+
+/// Records the activity of a single connection in the Host's registry
+#[derive(Debug, Clone)]
+pub(crate) struct Tracker {
+    id: ConnectionId,
+    info: Arc<StdMutex<ConnectionInfo>>,
+    registry: Arc<Registry>,
+}
+
+impl Tracker {
+    /// Name of the Node on the other end of the connection
+    pub(crate) fn name(&self) -> String {
+        match self.info.lock() {
+            Ok(info) => info.name.clone(),
+            Err(_) => String::new(),
+        }
+    }
+
+    /// Record a message of `bytes` received from the Node
+    pub(crate) fn received(&self, bytes: usize) {
+        if let Ok(mut info) = self.info.lock() {
+            info.msgs_received += 1;
+            info.bytes_received += bytes as u64;
+            info.last_activity = Utc::now();
+        }
+        self.registry.stats.received(bytes);
+    }
+
+    /// Record a message of `bytes` sent to the Node
+    pub(crate) fn sent(&self, bytes: usize) {
+        if let Ok(mut info) = self.info.lock() {
+            info.msgs_sent += 1;
+            info.bytes_sent += bytes as u64;
+        }
+        self.registry.stats.sent(bytes);
+    }
+
+    /// Record a subscription the Node made to `topic`
+    pub(crate) fn subscribed(&self, topic: &str) {
+        if let Ok(mut info) = self.info.lock() {
+            if !info.subscriptions.iter().any(|t| t == topic) {
+                info.subscriptions.push(topic.to_string());
+            }
+        }
+    }
+
+    /// Remove the connection from the registry once it has closed
+    pub(crate) fn close(&self) {
+        self.registry.remove(self.id);
+    }
+
+    /// Whether the connection is still registered, rather than closed or timed out
+    pub(crate) fn is_open(&self) -> bool {
+        self.registry.contains(self.id)
+    }
+
+    /// Store a message the Node published
+    pub(crate) fn insert_generic(&self, db: &mut sled::Db, msg: GenericMsg) -> Result<(), Error> {
+        self.registry.insert_generic(db, msg)
+    }
+
+    /// Look up the message `n` back on `topic`, answering for the Host's own topics
+    pub(crate) fn get_generic_nth(
+        &self,
+        db: &sled::Db,
+        topic: &str,
+        n: usize,
+    ) -> Result<GenericMsg, Error> {
+        // Only the present state of the Host is known
+        if is_computed_topic(topic) && n != 0 {
+            return Err(Error::NoNthValue);
+        }
+        let registry = &self.registry;
+        match topic {
+            CONNECTIONS_TOPIC => Msg::new(MsgType::Set, topic, registry.list()?).to_generic(),
+            TOPICS_TOPIC => Msg::new(MsgType::Set, topic, registry.stats.topics(db)?).to_generic(),
+            STATS_TOPIC => {
+                let stats = registry.stats.host(db, registry.len())?;
+                Msg::new(MsgType::Set, topic, stats).to_generic()
+            }
+            _ => db.get_generic_nth(topic, n),
+        }
+    }
+}
+
+/// Whether `topic` is maintained by the Host rather than published to by Nodes
+pub(crate) fn is_system_topic(topic: &str) -> bool {
+    topic.starts_with(SYSTEM_TOPIC_PREFIX)
+}
+
+/// Whether `topic` is answered by the Host itself rather than its store
+fn is_computed_topic(topic: &str) -> bool {
+    matches!(topic, CONNECTIONS_TOPIC | TOPICS_TOPIC | STATS_TOPIC)
+}
@@ -0,0 +1,249 @@
+// Tokio for async
+use tokio::net::UnixStream;
+use tokio::time::Duration;
+// Tracing for logging
+use tracing::*;
+
+use crate::error::Error;
+use crate::handshake::{self, rejected, Handshake};
+use crate::host::acl::Authorizer;
+use crate::host::network_config::ShmConfig;
+use crate::host::registry::Tracker;
+use crate::host::shutdown::{notice, Shutdown};
+use crate::host::GenericStore;
+use crate::node::shm::ring::ShmRing;
+use crate::node::shm::{notify, read_some, wait_for_notification, write_all, ShmRings};
+use crate::prelude::*;
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::result::Result;
+
+/// Host-side half of a shared-memory connection
+#[derive(Debug)]
+pub struct ShmConnection {
+    control: UnixStream,
+    /// Ring the Node publishes requests into
+    rx: ShmRing,
+    /// Ring the Host places responses into
+    tx: ShmRing,
+}
+
+/// Initiate a shared-memory connection with a Node, creating the ring buffers it will use
+#[inline]
+#[tracing::instrument(skip(stream, tokens))]
+pub async fn handshake(
+    stream: UnixStream,
+    cfg: &ShmConfig,
+    id: usize,
+    tokens: &HashMap<String, String>,
+) -> Result<(ShmConnection, Handshake), Error> {
+    // Room for the name, plus the version, features, and token around it
+    let mut buf = vec![0u8; cfg.max_name_size + 1024];
+    let n = read_some(&stream, &mut buf).await?;
+    let handshake = match handshake::accept(&buf[..n], cfg.max_name_size, tokens) {
+        Ok(handshake) => handshake,
+        Err(e) => {
+            write_all(
+                &stream,
+                &postcard::to_allocvec(&Err::<(u16, ShmRings), _>(e.clone()))?,
+            )
+            .await?;
+            return Err(e);
+        }
+    };
+
+    let base = format!("meadow-{}-{}", std::process::id(), id);
+    let rings = ShmRings {
+        request: cfg.shm_dir.join(format!("{}-req", base)),
+        response: cfg.shm_dir.join(format!("{}-rsp", base)),
+    };
+
+    let result = async {
+        let rx = ShmRing::create(&rings.request, cfg.ring_size)?;
+        let tx = ShmRing::create(&rings.response, cfg.ring_size)?;
+        write_all(
+            &stream,
+            &postcard::to_allocvec(&Ok::<_, Error>((handshake.version, &rings)))?,
+        )
+        .await?;
+        // Wait for the Node to map both rings
+        read_some(&stream, &mut buf).await?;
+        Ok::<(ShmRing, ShmRing), Error>((rx, tx))
+    }
+    .await;
+
+    // Both sides hold their own mappings by now, so the backing files are no longer needed
+    for path in [&rings.request, &rings.response] {
+        if let Err(e) = std::fs::remove_file(path) {
+            debug!("Unable to remove ring buffer file {:?}: {}", path, e);
+        }
+    }
+
+    let (rx, tx) = result?;
+    Ok((
+        ShmConnection {
+            control: stream,
+            rx,
+            tx,
+        },
+        handshake,
+    ))
+}
+
+/// Host process for handling messages arriving through a Node's shared-memory ring
+#[tracing::instrument(skip_all)]
+#[inline]
+pub async fn process_shm(
+    mut connection: ShmConnection,
+    mut db: sled::Db,
+    auth: Authorizer,
+    mut shutdown: Shutdown,
+    tracker: Tracker,
+) {
+    loop {
+        // Drain everything the Node has published before waiting on the next notification
+        while let Some(msg) = connection.rx.pop_sized::<GenericMsg>() {
+            let msg = match msg {
+                Ok((msg, n)) => {
+                    tracker.received(n);
+                    msg
+                }
+                // Nothing more can be read from the ring, so the connection is dropped
+                Err(Error::ShmCorrupted) => {
+                    error!("Closing shared-memory connection: {}", Error::ShmCorrupted);
+                    return;
+                }
+                Err(e) => {
+                    error!("Had received malformed Msg over shared memory: {}", e);
+                    continue;
+                }
+            };
+
+            if let Err(e) = auth.check(&msg) {
+                if let Err(e) =
+                    write_response(&mut connection, &GenericMsg::result(Err(e)), &tracker).await
+                {
+                    error!("Error sending data back over shared memory: {:?}", e);
+                }
+                continue;
+            }
+
+            let response = match &msg.msg_type {
+                MsgType::Subscribe => {
+                    start_subscription(
+                        msg.clone(),
+                        db.clone(),
+                        &mut connection,
+                        &mut shutdown,
+                        &tracker,
+                    )
+                    .await;
+                    return;
+                }
+                MsgType::Get => match tracker.get_generic_nth(&db, &msg.topic, 0) {
+                    Ok(g) => g,
+                    Err(e) => GenericMsg::result(Err(e)),
+                },
+                MsgType::GetNth(n) => match tracker.get_generic_nth(&db, &msg.topic, *n) {
+                    Ok(g) => g,
+                    Err(e) => GenericMsg::result(Err(e)),
+                },
+                MsgType::Set => GenericMsg::result(tracker.insert_generic(&mut db, msg)),
+                MsgType::Topics => match db.topics() {
+                    Ok(topics) => {
+                        let msg = Msg::new(MsgType::Topics, "", topics);
+                        match msg.to_generic() {
+                            Ok(msg) => msg,
+                            Err(e) => GenericMsg::result(Err(e)),
+                        }
+                    }
+                    Err(e) => GenericMsg::result(Err(e)),
+                },
+                MsgType::Handshake => {
+                    GenericMsg::result(Err(rejected("the handshake was already completed")))
+                }
+                MsgType::Heartbeat => GenericMsg::heartbeat(),
+                MsgType::Result(result) => {
+                    if let Err(e) = result {
+                        error!("{}", e);
+                    }
+                    continue;
+                }
+            };
+
+            if let Err(e) = write_response(&mut connection, &response, &tracker).await {
+                error!("Error sending data back over shared memory: {:?}", e);
+            }
+        }
+
+        tokio::select! {
+            notified = wait_for_notification(&connection.control) => {
+                if let Err(e) = notified {
+                    debug!("Ending shared-memory connection: {:?}", e);
+                    break;
+                }
+            }
+            _ = shutdown.wait() => {
+                let _ = write_response(&mut connection, &notice(), &tracker).await;
+                break;
+            }
+        }
+    }
+}
+
+async fn write_response(
+    connection: &mut ShmConnection,
+    response: &GenericMsg,
+    tracker: &Tracker,
+) -> Result<(), Error> {
+    // A response too large for the ring is reported back in its place,
+    // so the Node isn't left waiting on a reply that never arrives
+    let n = match connection.tx.push(response) {
+        Ok(n) => n,
+        Err(e) => connection.tx.push(&GenericMsg::result(Err(e)))?,
+    };
+    tracker.sent(n);
+    notify(&connection.control).await
+}
+
+async fn start_subscription(
+    msg: GenericMsg,
+    db: sled::Db,
+    connection: &mut ShmConnection,
+    shutdown: &mut Shutdown,
+    tracker: &Tracker,
+) {
+    let specialized: Msg<Duration> = match msg.clone().try_into() {
+        Ok(specialized) => specialized,
+        Err(e) => {
+            error!("{}", e);
+            return;
+        }
+    };
+    let rate = specialized.data;
+    tracker.subscribed(&msg.topic);
+
+    loop {
+        let response = match tracker.get_generic_nth(&db, &msg.topic, 0) {
+            Ok(g) => g,
+            Err(e) => GenericMsg::result(Err(e)),
+        };
+        // A subscriber that has fallen behind simply misses this update
+        match connection.tx.push(&response) {
+            Ok(n) => tracker.sent(n),
+            Err(e) => debug!("Skipping shared-memory subscription update: {}", e),
+        }
+        if let Err(e) = notify(&connection.control).await {
+            // The Node has hung up, so there's no one left to send to
+            debug!(
+                "Ending shared-memory subscription on {}: {:?}",
+                &msg.topic, e
+            );
+            break;
+        }
+        if !shutdown.sleep(rate).await {
+            let _ = write_response(connection, &notice(), tracker).await;
+            break;
+        }
+    }
+}
@@ -0,0 +1,100 @@
+// Tokio for async
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, timeout_at, Duration, Instant};
+// Tracing for logging
+use tracing::*;
+
+use crate::error::{Error, HostError};
+use crate::host::registry::Connection;
+use crate::msg::GenericMsg;
+
+/// Summary of an orderly `Host` shutdown, returned by `Host::stop`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShutdownReport {
+    /// Connections that finished their in-flight requests and closed
+    pub drained: usize,
+    /// Connections still busy when the shutdown timeout expired, which were aborted
+    pub aborted: usize,
+    /// Bytes written to disk when the store was flushed
+    pub flushed_bytes: usize,
+    /// Time the shutdown took
+    pub elapsed: Duration,
+}
+
+/// Tells the Host's tasks when it begins shutting down
+#[derive(Debug, Clone)]
+pub(crate) struct Shutdown(watch::Receiver<bool>);
+
+impl Shutdown {
+    pub(crate) fn new(rx: watch::Receiver<bool>) -> Self {
+        Shutdown(rx)
+    }
+
+    /// Whether the Host has begun shutting down
+    pub(crate) fn is_stopping(&self) -> bool {
+        *self.0.borrow()
+    }
+
+    /// Wait until the Host begins shutting down
+    pub(crate) async fn wait(&mut self) {
+        // The sender is only dropped along with the Host, which is as good as a shutdown
+        let _ = self.0.wait_for(|stopping| *stopping).await;
+    }
+
+    /// Sleep for `duration`, returning `false` early if the Host begins shutting down
+    pub(crate) async fn sleep(&mut self, duration: Duration) -> bool {
+        tokio::select! {
+            _ = sleep(duration) => true,
+            _ = self.wait() => false,
+        }
+    }
+}
+
+/// Stop a Host's tasks in order, returning how many connections drained and how many were aborted.
+///
+/// Listeners are aborted first so no new connections arrive. Connections, and the UDP socket's
+/// task, have already been told to shut down, so each is given until `timeout` to finish the
+/// requests it was handling.
+pub(crate) async fn drain(
+    listeners: Vec<JoinHandle<()>>,
+    udp: Option<JoinHandle<()>>,
+    connections: Vec<Connection>,
+    timeout: Duration,
+) -> (usize, usize) {
+    for listener in listeners {
+        listener.abort();
+        let _ = listener.await;
+    }
+
+    let deadline = Instant::now() + timeout;
+    if let Some(mut udp) = udp {
+        if timeout_at(deadline, &mut udp).await.is_err() {
+            udp.abort();
+        }
+    }
+
+    let (mut drained, mut aborted) = (0, 0);
+    // UDP peers have no task of their own, so they aren't counted
+    for (mut handle, info) in connections
+        .into_iter()
+        .filter_map(|connection| Some((connection.handle?, connection.info)))
+    {
+        match timeout_at(deadline, &mut handle).await {
+            Ok(_) => drained += 1,
+            Err(_) => {
+                if let Ok(info) = info.lock() {
+                    debug!("Aborting connection: {}", info.name);
+                }
+                handle.abort();
+                aborted += 1;
+            }
+        }
+    }
+    (drained, aborted)
+}
+
+/// Final message sent to a Node as the Host closes its connection
+pub(crate) fn notice() -> GenericMsg {
+    GenericMsg::result(Err(Error::HostOperation(HostError::ShuttingDown)))
+}
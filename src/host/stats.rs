@@ -0,0 +1,206 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+use crate::host::host::TYPES_TREE;
+use crate::msg::GenericMsg;
+use crate::prelude::Store;
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex as StdMutex;
+
+/// Topic on which the Host answers requests and subscriptions with the topics it holds,
+/// as a `Vec<TopicInfo>`
+pub const TOPICS_TOPIC: &str = "_meadow/topics";
+
+/// Topic on which the Host answers requests and subscriptions with its overall activity,
+/// as a `HostStats`
+pub const STATS_TOPIC: &str = "_meadow/stats";
+
+/// Seconds over which publishing rates are averaged
+const RATE_WINDOW: i64 = 10;
+
+/// Description of a topic held by the Host
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TopicInfo {
+    pub name: String,
+    /// Identity of the data type the topic holds, if it has been published to
+    pub data_type: Option<String>,
+    /// Messages published to the topic since the Host was created
+    pub msgs_published: u64,
+    /// Total size of the payloads published since the Host was created
+    pub bytes_published: u64,
+    /// Messages published per second, averaged over the last 10 seconds
+    pub rate: f64,
+    /// Size of the latest message's payload, as stored
+    pub last_size: Option<u64>,
+    /// Timestamp of the latest message
+    pub last_update: Option<DateTime<Utc>>,
+}
+
+/// Overall activity of a Host
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HostStats {
+    /// When the Host was created, from which the counts below start
+    pub since: DateTime<Utc>,
+    /// Connections currently open
+    pub connections: usize,
+    /// Topics currently held
+    pub topics: usize,
+    /// Messages published to any topic
+    pub msgs_published: u64,
+    /// Messages published per second across all topics, averaged over the last 10 seconds
+    pub publish_rate: f64,
+    pub msgs_received: u64,
+    pub bytes_received: u64,
+    pub msgs_sent: u64,
+    pub bytes_sent: u64,
+    /// Space the store takes up on disk
+    pub store_size: u64,
+}
+
+/// Messages counted in each of the last few seconds
+#[derive(Debug, Default)]
+struct Rate {
+    /// Count per second, by Unix timestamp, oldest first
+    buckets: VecDeque<(i64, u64)>,
+}
+
+impl Rate {
+    fn record(&mut self, now: DateTime<Utc>) {
+        let second = now.timestamp();
+        match self.buckets.back_mut() {
+            Some((last, count)) if *last == second => *count += 1,
+            _ => self.buckets.push_back((second, 1)),
+        }
+        while let Some((oldest, _)) = self.buckets.front() {
+            if *oldest > second - RATE_WINDOW {
+                break;
+            }
+            self.buckets.pop_front();
+        }
+    }
+
+    fn per_second(&self, now: DateTime<Utc>) -> f64 {
+        let start = now.timestamp() - RATE_WINDOW;
+        let count: u64 = self
+            .buckets
+            .iter()
+            .filter(|(second, _)| *second > start)
+            .map(|(_, count)| count)
+            .sum();
+        count as f64 / RATE_WINDOW as f64
+    }
+}
+
+/// Publishing activity on a single topic
+#[derive(Debug, Default)]
+struct TopicActivity {
+    msgs: u64,
+    bytes: u64,
+    rate: Rate,
+}
+
+/// Counts of everything that has passed through the Host
+#[derive(Debug)]
+pub(crate) struct Stats {
+    since: DateTime<Utc>,
+    topics: StdMutex<HashMap<String, TopicActivity>>,
+    msgs_received: AtomicU64,
+    bytes_received: AtomicU64,
+    msgs_sent: AtomicU64,
+    bytes_sent: AtomicU64,
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        Stats {
+            since: Utc::now(),
+            topics: StdMutex::new(HashMap::new()),
+            msgs_received: AtomicU64::new(0),
+            bytes_received: AtomicU64::new(0),
+            msgs_sent: AtomicU64::new(0),
+            bytes_sent: AtomicU64::new(0),
+        }
+    }
+}
+
+impl Stats {
+    /// Record a message published to `topic`
+    pub(crate) fn published(&self, msg: &GenericMsg) {
+        if let Ok(mut topics) = self.topics.lock() {
+            let activity = topics.entry(msg.topic.clone()).or_default();
+            activity.msgs += 1;
+            activity.bytes += msg.data.len() as u64;
+            activity.rate.record(Utc::now());
+        }
+    }
+
+    /// Record a message of `bytes` received from any connection
+    pub(crate) fn received(&self, bytes: usize) {
+        self.msgs_received.fetch_add(1, Ordering::Relaxed);
+        self.bytes_received
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// Record a message of `bytes` sent on any connection
+    pub(crate) fn sent(&self, bytes: usize) {
+        self.msgs_sent.fetch_add(1, Ordering::Relaxed);
+        self.bytes_sent.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// Describe every topic in `db`, sorted by name
+    pub(crate) fn topics(&self, db: &sled::Db) -> Result<Vec<TopicInfo>, Error> {
+        let types = db.open_tree(TYPES_TREE)?;
+        let activity = self.topics.lock().map_err(|_| Error::LockFailure)?;
+        let now = Utc::now();
+        db.topics()?
+            .into_iter()
+            .map(|name| {
+                let data_type = types
+                    .get(&name)?
+                    .map(|data_type| String::from_utf8_lossy(&data_type).to_string());
+                let latest = match db.open_tree(name.as_bytes())?.last()? {
+                    Some((_timestamp, bytes)) => Some(postcard::from_bytes::<GenericMsg>(&bytes)?),
+                    None => None,
+                };
+                let (msgs_published, bytes_published, rate) = match activity.get(&name) {
+                    Some(topic) => (topic.msgs, topic.bytes, topic.rate.per_second(now)),
+                    None => (0, 0, 0.0),
+                };
+                Ok(TopicInfo {
+                    name,
+                    data_type,
+                    msgs_published,
+                    bytes_published,
+                    rate,
+                    last_size: latest.as_ref().map(|msg| msg.data.len() as u64),
+                    last_update: latest.map(|msg| msg.timestamp),
+                })
+            })
+            .collect()
+    }
+
+    /// Summarize the Host's activity, given how many connections are open
+    pub(crate) fn host(&self, db: &sled::Db, connections: usize) -> Result<HostStats, Error> {
+        let topics = db.topics()?.len();
+        let activity = self.topics.lock().map_err(|_| Error::LockFailure)?;
+        let now = Utc::now();
+        Ok(HostStats {
+            since: self.since,
+            connections,
+            topics,
+            msgs_published: activity.values().map(|topic| topic.msgs).sum(),
+            publish_rate: activity
+                .values()
+                .map(|topic| topic.rate.per_second(now))
+                .sum(),
+            msgs_received: self.msgs_received.load(Ordering::Relaxed),
+            bytes_received: self.bytes_received.load(Ordering::Relaxed),
+            msgs_sent: self.msgs_sent.load(Ordering::Relaxed),
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+            store_size: db.size_on_disk()?,
+        })
+    }
+}
@@ -2,8 +2,8 @@ use tokio::io::AsyncWriteExt;
 // Tokio for async
 use tokio::net::TcpStream;
 use tokio::sync::Mutex;
-use tokio::time::{sleep, Duration}; // as TokioMutex;
-                                    // Tracing for logging
+use tokio::time::{sleep_until, Duration, Instant};
+// Tracing for logging
 use tracing::*;
 // Postcard is the default de/serializer
 use postcard::*;
@@ -13,50 +13,89 @@ use std::sync::Arc;
 use chrono::Utc;
 
 use crate::error::{Error, HostOperation};
+use crate::handshake::{self, rejected, Handshake};
+use crate::host::acl::Authorizer;
+use crate::host::registry::Tracker;
+use crate::host::shutdown::{notice, Shutdown};
 use crate::host::GenericStore;
 use crate::prelude::*;
+use std::collections::HashMap;
 use std::convert::TryInto;
 use std::result::Result;
 
-/// Initiate a TCP connection with a Node
+#[cfg(feature = "tls")]
+use crate::host::quic::common_name;
+#[cfg(feature = "tls")]
+use tokio::io::AsyncReadExt;
+#[cfg(feature = "tls")]
+use tokio_rustls::{server::TlsStream, TlsAcceptor};
+
+/// Initiate a TCP connection with a Node, validating its handshake
 #[inline]
-#[tracing::instrument]
+#[tracing::instrument(skip(tokens))]
 pub async fn handshake(
     stream: TcpStream,
     max_buffer_size: usize,
     max_name_size: usize,
-) -> Result<(TcpStream, String), Error> {
-    // Handshake
+    tokens: &HashMap<String, String>,
+) -> Result<(TcpStream, Handshake), Error> {
     let mut buf = vec![0u8; max_buffer_size];
-    // debug!("Starting handshake");
-    let mut _name: String = String::with_capacity(max_name_size);
-
-    stream.readable().await?;
 
-    let n = stream.try_read_buf(&mut buf)?;
-    let name = std::str::from_utf8(&buf[..n])?.to_string();
+    // Readiness can be spurious, so reads and writes are retried until they don't block
+    let n = loop {
+        stream.readable().await?;
+        match stream.try_read(&mut buf) {
+            // The Node hung up without sending a handshake
+            Ok(0) => return Err(Error::AccessStream),
+            Ok(n) => break n,
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => continue,
+            Err(e) => return Err(e.into()),
+        }
+    };
+    let result = handshake::accept(&buf[..n], max_name_size, tokens);
 
-    // debug!("Returning from handshake: ({:?}, {})", &stream, &_name);
-    Ok((stream, name))
+    let reply = handshake::reply(&result)?.as_bytes()?;
+    loop {
+        stream.writable().await?;
+        match stream.try_write(&reply) {
+            Ok(_) => break,
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok((stream, result?))
 }
 
 /// Host process for handling incoming connections from Nodes
 #[tracing::instrument(skip_all)]
 #[inline]
-pub async fn process_tcp(stream: TcpStream, mut db: sled::Db, max_buffer_size: usize) {
+pub async fn process_tcp(
+    stream: TcpStream,
+    mut db: sled::Db,
+    max_buffer_size: usize,
+    auth: Authorizer,
+    mut shutdown: Shutdown,
+    tracker: Tracker,
+) {
     let mut buf = vec![0u8; max_buffer_size];
     loop {
-        if let Err(e) = stream.readable().await {
-            error!("{}", e);
+        // Requests already being handled are finished before the connection is closed
+        tokio::select! {
+            readable = stream.readable() => {
+                if let Err(e) = readable {
+                    error!("{}", e);
+                }
+            }
+            _ = shutdown.wait() => {
+                let _ = write_response(&stream, &notice(), &tracker).await;
+                break;
+            }
         }
         // dbg!(&count);
         match stream.try_read(&mut buf) {
             Ok(0) => break, // TO_DO: break or continue?
             Ok(n) => {
-                if let Err(e) = stream.writable().await {
-                    error!("{}", e);
-                }
-
+                tracker.received(n);
                 let bytes = &buf[..n];
                 let msg: GenericMsg = match from_bytes(bytes) {
                     Ok(msg) => {
@@ -71,71 +110,62 @@ pub async fn process_tcp(stream: TcpStream, mut db: sled::Db, max_buffer_size: u
 
                 info!("{:?}", msg.msg_type);
 
-                match &msg.msg_type {
-                    MsgType::Subscribe => {
-                        start_subscription(msg.clone(), db.clone(), &stream).await;
+                if let Err(e) = auth.check(&msg) {
+                    if let Err(e) =
+                        write_response(&stream, &GenericMsg::result(Err(e)), &tracker).await
+                    {
+                        error!("Error sending data back on TCP: {:?}", e);
                     }
-                    MsgType::Get => {
-                        let response = match db.get_generic_nth(&msg.topic, 0) {
-                            Ok(g) => g,
-                            Err(e) => GenericMsg::result(Err(e)),
-                        };
-                        if let Ok(return_bytes) = response.as_bytes() {
-                            if let Ok(()) = stream.writable().await {
-                                if let Err(e) = stream.try_write(&return_bytes) {
-                                    error!("Error sending data back on TCP/TOPICS: {:?}", e);
-                                }
-                            }
-                        }
+                    continue;
+                }
+
+                let response = match &msg.msg_type {
+                    MsgType::Subscribe => {
+                        start_subscription(
+                            msg.clone(),
+                            db.clone(),
+                            &stream,
+                            &mut buf,
+                            &mut shutdown,
+                            &tracker,
+                        )
+                        .await;
+                        continue;
                     }
-                    MsgType::GetNth(n) => {
-                        let response = match db.get_generic_nth(&msg.topic, *n) {
-                            Ok(g) => g,
-                            Err(e) => GenericMsg::result(Err(e)),
-                        };
-                        if let Ok(return_bytes) = response.as_bytes() {
-                            if let Ok(()) = stream.writable().await {
-                                if let Err(e) = stream.try_write(&return_bytes) {
-                                    error!("Error sending data back on TCP/TOPICS: {:?}", e);
-                                }
+                    MsgType::Get => match tracker.get_generic_nth(&db, &msg.topic, 0) {
+                        Ok(g) => g,
+                        Err(e) => GenericMsg::result(Err(e)),
+                    },
+                    MsgType::GetNth(n) => match tracker.get_generic_nth(&db, &msg.topic, *n) {
+                        Ok(g) => g,
+                        Err(e) => GenericMsg::result(Err(e)),
+                    },
+                    MsgType::Set => GenericMsg::result(tracker.insert_generic(&mut db, msg)),
+                    MsgType::Topics => match db.topics() {
+                        Ok(mut topics) => {
+                            topics.sort();
+                            let msg = Msg::new(MsgType::Topics, "", topics);
+                            match msg.to_generic() {
+                                Ok(msg) => msg,
+                                Err(e) => GenericMsg::result(Err(e)),
                             }
                         }
-                    }
-                    MsgType::Set => {
-                        let response = GenericMsg::result(db.insert_generic(msg));
-                        if let Ok(return_bytes) = response.as_bytes() {
-                            if let Ok(()) = stream.writable().await {
-                                if let Err(e) = stream.try_write(&return_bytes) {
-                                    error!("Error sending data back on TCP/TOPICS: {:?}", e);
-                                }
-                            }
+                        Err(e) => GenericMsg::result(Err(e)),
+                    },
+                    MsgType::Result(result) => {
+                        if let Err(e) = result {
+                            error!("{}", e);
                         }
+                        continue;
                     }
-                    MsgType::Topics => {
-                        let response = match db.topics() {
-                            Ok(mut topics) => {
-                                topics.sort();
-                                let msg = Msg::new(MsgType::Topics, "", topics);
-                                match msg.to_generic() {
-                                    Ok(msg) => msg,
-                                    Err(e) => GenericMsg::result(Err(e)),
-                                }
-                            }
-                            Err(e) => GenericMsg::result(Err(e)),
-                        };
-
-                        if let Ok(return_bytes) = response.as_bytes() {
-                            if let Ok(()) = stream.writable().await {
-                                if let Err(e) = stream.try_write(&return_bytes) {
-                                    error!("Error sending data back on TCP/TOPICS: {:?}", e);
-                                }
-                            }
-                        }
+                    MsgType::Handshake => {
+                        GenericMsg::result(Err(rejected("the handshake was already completed")))
                     }
-                    MsgType::Result(result) => match result {
-                        Ok(_) => (),
-                        Err(e) => error!("{}", e),
-                    },
+                    MsgType::Heartbeat => GenericMsg::heartbeat(),
+                };
+
+                if let Err(e) = write_response(&stream, &response, &tracker).await {
+                    error!("Error sending data back on TCP: {:?}", e);
                 }
             }
             Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
@@ -144,27 +174,298 @@ pub async fn process_tcp(stream: TcpStream, mut db: sled::Db, max_buffer_size: u
             }
             Err(e) => {
                 error!("Error: {:?}", e);
+                break;
             }
         }
     }
 }
 
-async fn start_subscription(msg: GenericMsg, db: sled::Db, stream: &TcpStream) {
-    let specialized: Msg<Duration> = msg.clone().try_into().unwrap();
+async fn write_response(
+    stream: &TcpStream,
+    response: &GenericMsg,
+    tracker: &Tracker,
+) -> Result<(), Error> {
+    let return_bytes = response.as_bytes()?;
+    stream.writable().await?;
+    let n = stream.try_write(&return_bytes)?;
+    tracker.sent(n);
+    Ok(())
+}
+
+async fn start_subscription(
+    msg: GenericMsg,
+    db: sled::Db,
+    stream: &TcpStream,
+    buf: &mut [u8],
+    shutdown: &mut Shutdown,
+    tracker: &Tracker,
+) {
+    let specialized: Msg<Duration> = match msg.clone().try_into() {
+        Ok(specialized) => specialized,
+        Err(e) => {
+            error!("{}", e);
+            return;
+        }
+    };
     let rate = specialized.data;
+    tracker.subscribed(&msg.topic);
 
+    let mut next = Instant::now();
     loop {
-        let response = match db.get_generic_nth(&msg.topic, 0) {
-            Ok(g) => g,
-            Err(e) => GenericMsg::result(Err(e)),
+        // Heartbeats from the Node are answered between sends
+        tokio::select! {
+            _ = sleep_until(next) => {
+                let response = match tracker.get_generic_nth(&db, &msg.topic, 0) {
+                    Ok(g) => g,
+                    Err(e) => GenericMsg::result(Err(e)),
+                };
+                if let Err(e) = write_response(stream, &response, tracker).await {
+                    // The Node has hung up, so there's no one left to send to
+                    debug!("Ending TCP subscription on {}: {:?}", &msg.topic, e);
+                    break;
+                }
+                next = Instant::now() + rate;
+            }
+            readable = stream.readable() => {
+                if let Err(e) = readable {
+                    error!("{}", e);
+                }
+                match stream.try_read(buf) {
+                    Ok(0) => break,
+                    Ok(n) => {
+                        tracker.received(n);
+                        if let Err(e) = answer_heartbeat(&buf[..n], stream, tracker).await {
+                            debug!("Ending TCP subscription on {}: {:?}", &msg.topic, e);
+                            break;
+                        }
+                    }
+                    Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => continue,
+                    Err(e) => {
+                        error!("Error: {:?}", e);
+                        break;
+                    }
+                }
+            }
+            _ = shutdown.wait() => break,
+        }
+    }
+}
+
+/// Answer a heartbeat a subscribed Node sent, ignoring anything else it sent
+async fn answer_heartbeat(
+    bytes: &[u8],
+    stream: &TcpStream,
+    tracker: &Tracker,
+) -> Result<(), Error> {
+    match from_bytes::<GenericMsg>(bytes) {
+        Ok(msg) if msg.msg_type == MsgType::Heartbeat => {
+            write_response(stream, &GenericMsg::heartbeat(), tracker).await
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Complete the TLS handshake with a Node, then read its name.
+///
+/// Nodes that authenticated with a client certificate are known by its common name instead.
+#[cfg(feature = "tls")]
+#[inline]
+#[tracing::instrument(skip_all)]
+pub async fn tls_handshake(
+    acceptor: &TlsAcceptor,
+    stream: TcpStream,
+    max_buffer_size: usize,
+    max_name_size: usize,
+    tokens: &HashMap<String, String>,
+) -> Result<(TlsStream<TcpStream>, Handshake, String), Error> {
+    let mut stream = acceptor.accept(stream).await?;
+    let mut buf = vec![0u8; max_buffer_size];
+    let n = stream.read(&mut buf).await?;
+    let result = handshake::accept(&buf[..n], max_name_size, tokens);
+    stream
+        .write_all(&handshake::reply(&result)?.as_bytes()?)
+        .await?;
+    let handshake = result?;
+
+    let certificate_name = stream
+        .get_ref()
+        .1
+        .peer_certificates()
+        .and_then(|certs| certs.first())
+        .and_then(common_name);
+    let name = certificate_name.unwrap_or_else(|| handshake.name.clone());
+    Ok((stream, handshake, name))
+}
+
+/// Host process for handling TLS-encrypted connections from Nodes
+#[cfg(feature = "tls")]
+#[tracing::instrument(skip_all)]
+#[inline]
+pub async fn process_tls(
+    mut stream: TlsStream<TcpStream>,
+    mut db: sled::Db,
+    max_buffer_size: usize,
+    auth: Authorizer,
+    mut shutdown: Shutdown,
+    tracker: Tracker,
+) {
+    let mut buf = vec![0u8; max_buffer_size];
+    loop {
+        let read = tokio::select! {
+            read = stream.read(&mut buf) => read,
+            _ = shutdown.wait() => {
+                let _ = write_tls(&mut stream, &notice(), &tracker).await;
+                break;
+            }
         };
-        if let Ok(return_bytes) = response.as_bytes() {
-            if let Ok(()) = stream.writable().await {
-                if let Err(e) = stream.try_write(&return_bytes) {
-                    error!("Error sending data back on TCP/TOPICS: {:?}", e);
+        let n = match read {
+            Ok(0) => break,
+            Ok(n) => {
+                tracker.received(n);
+                n
+            }
+            Err(e) => {
+                error!("Error: {:?}", e);
+                break;
+            }
+        };
+        let bytes = &buf[..n];
+        let msg: GenericMsg = match from_bytes(bytes) {
+            Ok(msg) => msg,
+            Err(e) => {
+                error!("Had received Msg of {} bytes: {:?}, Error: {}", n, bytes, e);
+                continue;
+            }
+        };
+
+        if let Err(e) = auth.check(&msg) {
+            if let Err(e) = write_tls(&mut stream, &GenericMsg::result(Err(e)), &tracker).await {
+                error!("Error sending data back on TLS: {:?}", e);
+            }
+            continue;
+        }
+
+        let response = match &msg.msg_type {
+            MsgType::Subscribe => {
+                start_tls_subscription(
+                    msg.clone(),
+                    db.clone(),
+                    &mut stream,
+                    &mut buf,
+                    &mut shutdown,
+                    &tracker,
+                )
+                .await;
+                continue;
+            }
+            MsgType::Get => match tracker.get_generic_nth(&db, &msg.topic, 0) {
+                Ok(g) => g,
+                Err(e) => GenericMsg::result(Err(e)),
+            },
+            MsgType::GetNth(n) => match tracker.get_generic_nth(&db, &msg.topic, *n) {
+                Ok(g) => g,
+                Err(e) => GenericMsg::result(Err(e)),
+            },
+            MsgType::Set => GenericMsg::result(tracker.insert_generic(&mut db, msg)),
+            MsgType::Topics => match db.topics() {
+                Ok(mut topics) => {
+                    topics.sort();
+                    let msg = Msg::new(MsgType::Topics, "", topics);
+                    match msg.to_generic() {
+                        Ok(msg) => msg,
+                        Err(e) => GenericMsg::result(Err(e)),
+                    }
+                }
+                Err(e) => GenericMsg::result(Err(e)),
+            },
+            MsgType::Handshake => {
+                GenericMsg::result(Err(rejected("the handshake was already completed")))
+            }
+            MsgType::Heartbeat => GenericMsg::heartbeat(),
+            MsgType::Result(result) => {
+                if let Err(e) = result {
+                    error!("{}", e);
+                }
+                continue;
+            }
+        };
+
+        if let Err(e) = write_tls(&mut stream, &response, &tracker).await {
+            error!("Error sending data back on TLS: {:?}", e);
+        }
+    }
+}
+
+#[cfg(feature = "tls")]
+async fn write_tls(
+    stream: &mut TlsStream<TcpStream>,
+    response: &GenericMsg,
+    tracker: &Tracker,
+) -> Result<(), Error> {
+    let return_bytes = response.as_bytes()?;
+    stream.write_all(&return_bytes).await?;
+    stream.flush().await?;
+    tracker.sent(return_bytes.len());
+    Ok(())
+}
+
+#[cfg(feature = "tls")]
+async fn start_tls_subscription(
+    msg: GenericMsg,
+    db: sled::Db,
+    stream: &mut TlsStream<TcpStream>,
+    buf: &mut [u8],
+    shutdown: &mut Shutdown,
+    tracker: &Tracker,
+) {
+    let specialized: Msg<Duration> = match msg.clone().try_into() {
+        Ok(specialized) => specialized,
+        Err(e) => {
+            error!("{}", e);
+            return;
+        }
+    };
+    let rate = specialized.data;
+    tracker.subscribed(&msg.topic);
+
+    let mut next = Instant::now();
+    loop {
+        // Heartbeats from the Node are answered between sends
+        tokio::select! {
+            _ = sleep_until(next) => {
+                let response = match tracker.get_generic_nth(&db, &msg.topic, 0) {
+                    Ok(g) => g,
+                    Err(e) => GenericMsg::result(Err(e)),
+                };
+                if let Err(e) = write_tls(stream, &response, tracker).await {
+                    // The Node has hung up, so there's no one left to send to
+                    debug!("Ending TLS subscription on {}: {:?}", &msg.topic, e);
+                    break;
+                }
+                next = Instant::now() + rate;
+            }
+            read = stream.read(buf) => {
+                let n = match read {
+                    Ok(0) => break,
+                    Ok(n) => n,
+                    Err(e) => {
+                        error!("Error: {:?}", e);
+                        break;
+                    }
+                };
+                tracker.received(n);
+                let heartbeat = matches!(
+                    from_bytes::<GenericMsg>(&buf[..n]),
+                    Ok(msg) if msg.msg_type == MsgType::Heartbeat
+                );
+                if heartbeat {
+                    if let Err(e) = write_tls(stream, &GenericMsg::heartbeat(), tracker).await {
+                        debug!("Ending TLS subscription on {}: {:?}", &msg.topic, e);
+                        break;
+                    }
                 }
             }
+            _ = shutdown.wait() => break,
         }
-        sleep(rate).await;
     }
 }
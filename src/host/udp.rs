@@ -2,40 +2,82 @@
 use tokio::net::UdpSocket;
 use tokio::runtime::Handle;
 use tokio::sync::Mutex;
-use tokio::time::{sleep, Duration}; // as TokioMutex;
-                                    // Tracing for logging
+use tokio::time::Duration; // as TokioMutex;
+                           // Tracing for logging
 use tracing::*;
 // Postcard is the default de/serializer
-use crate::error::Error;
+use crate::error::{Error, HostError};
 use postcard::*;
 // Multi-threading primitives
 use std::sync::Arc;
 // Misc other imports
 use chrono::Utc;
 
+use crate::handshake::{self, Handshake};
+use crate::host::acl::AccessControl;
+use crate::host::registry::{Registry, Tracker, REAP_PERIOD};
+use crate::host::shutdown::{notice, Shutdown};
 use crate::host::GenericStore;
 use crate::prelude::*;
+use crate::psk::DatagramCipher;
+use std::collections::HashMap;
 use std::convert::TryInto;
+use std::net::SocketAddr;
 
 /// Host process for handling incoming connections from Nodes
-#[tracing::instrument(skip(db))]
+#[tracing::instrument(skip(db, tokens, shutdown, registry))]
 #[inline]
+#[allow(clippy::too_many_arguments)]
 pub async fn process_udp(
     rt_handle: Handle,
     socket: UdpSocket,
     mut db: sled::Db,
     max_buffer_size: usize,
+    max_name_size: usize,
+    cipher: Option<Arc<DatagramCipher>>,
+    acl: Option<Arc<AccessControl>>,
+    tokens: Arc<HashMap<String, String>>,
+    mut shutdown: Shutdown,
+    registry: Arc<Registry>,
 ) {
     let mut buf = vec![0u8; max_buffer_size];
     let s = Arc::new(socket);
+    // Nodes that have completed a handshake, by the address they send from
+    let mut peers: HashMap<SocketAddr, Tracker> = HashMap::new();
+    let mut prune = tokio::time::interval(REAP_PERIOD);
 
     loop {
         // dbg!(&count);
         let s = s.clone();
-        match s.recv_from(&mut buf).await {
+        let received = tokio::select! {
+            received = s.recv_from(&mut buf) => received,
+            _ = prune.tick() => {
+                // Forget the peers the registry has dropped for going quiet
+                peers.retain(|_, tracker| tracker.is_open());
+                continue;
+            }
+            _ = shutdown.wait() => {
+                // There are no connections to close, so every Node that shook hands is told
+                for (return_addr, tracker) in &peers {
+                    send_response(&s, &notice(), *return_addr, cipher.as_deref(), Some(tracker)).await;
+                }
+                break;
+            }
+        };
+        match received {
             Ok((0, _)) => break, // TO_DO: break or continue?
             Ok((n, return_addr)) => {
-                let bytes = &buf[..n];
+                let bytes = match &cipher {
+                    Some(cipher) => match cipher.open(&buf[..n]) {
+                        Ok(plaintext) => plaintext,
+                        Err(e) => {
+                            warn!("Dropping {}-byte datagram from {}: {}", n, return_addr, e);
+                            continue;
+                        }
+                    },
+                    None => buf[..n].to_vec(),
+                };
+                let bytes = &bytes[..];
                 let msg: GenericMsg = match from_bytes(bytes) {
                     Ok(msg) => msg,
                     Err(e) => {
@@ -44,39 +86,94 @@ pub async fn process_udp(
                     }
                 };
 
+                if msg.msg_type == MsgType::Handshake {
+                    let result = Handshake::from_generic(msg)
+                        .and_then(|handshake| handshake.validate(max_name_size, &tokens));
+                    match &result {
+                        Ok(handshake) => {
+                            debug!("Host received UDP handshake from {:?}", handshake);
+                            handshake.register_schema(&db);
+                            // A Node handshaking again from the same address has restarted
+                            if let Some(previous) = peers.remove(&return_addr) {
+                                previous.close();
+                            }
+                            match registry.register_peer(handshake.clone(), return_addr.to_string())
+                            {
+                                Ok(tracker) => {
+                                    peers.insert(return_addr, tracker);
+                                }
+                                Err(e) => error!("Unable to register UDP peer: {}", e),
+                            }
+                        }
+                        Err(e) => warn!("UDP handshake with {} failed: {}", return_addr, e),
+                    }
+                    match handshake::reply(&result) {
+                        Ok(response) => {
+                            let tracker = peers.get(&return_addr);
+                            send_response(&s, &response, return_addr, cipher.as_deref(), tracker)
+                                .await
+                        }
+                        Err(e) => error!("Unable to encode UDP handshake reply: {}", e),
+                    }
+                    continue;
+                }
+
+                // Peers that went quiet have to shake hands again
+                if let Some(tracker) = peers.get(&return_addr) {
+                    if !tracker.is_open() {
+                        peers.remove(&return_addr);
+                    }
+                }
+                let tracker = match peers.get(&return_addr) {
+                    Some(tracker) => tracker.clone(),
+                    None => {
+                        let e = Error::HostOperation(HostError::HandshakeRequired);
+                        warn!("Dropping {:?} from {}: {}", msg.msg_type, return_addr, e);
+                        // Even publishes hear back, so a Node the Host has forgotten, e.g. by
+                        // restarting, knows to shake hands again
+                        let response = GenericMsg::result(Err(e));
+                        send_response(&s, &response, return_addr, cipher.as_deref(), None).await;
+                        continue;
+                    }
+                };
+                tracker.received(n);
+
+                if let Some(acl) = &acl {
+                    if let Err(e) = acl.authorize(&tracker.name(), &msg) {
+                        // Publishing isn't acknowledged over UDP, so only requests hear back
+                        if msg.msg_type != MsgType::Set {
+                            let response = GenericMsg::result(Err(e));
+                            let tracker = Some(&tracker);
+                            send_response(&s, &response, return_addr, cipher.as_deref(), tracker)
+                                .await;
+                        }
+                        continue;
+                    }
+                }
+
                 match msg.msg_type {
                     MsgType::Set => {
-                        if let Err(e) = db.insert_generic(msg) {
+                        if let Err(e) = tracker.insert_generic(&mut db, msg) {
                             error!("{}", e);
                         }
                     }
                     MsgType::Get => {
-                        let response = match db.get_generic_nth(&msg.topic, 0) {
+                        let response = match tracker.get_generic_nth(&db, &msg.topic, 0) {
                             Ok(g) => g,
                             Err(e) => GenericMsg::result(Err(e)),
                         };
 
-                        if let Ok(return_bytes) = response.as_bytes() {
-                            if let Ok(()) = s.writable().await {
-                                if let Err(e) = s.try_send_to(&return_bytes, return_addr) {
-                                    error!("Error sending data back on UDP/GET: {}", e)
-                                };
-                            };
-                        }
+                        let tracker = Some(&tracker);
+                        send_response(&s, &response, return_addr, cipher.as_deref(), tracker).await;
                     }
                     MsgType::GetNth(n) => {
-                        let response = match db.get_generic_nth(&msg.topic, n) {
+                        let response = match tracker.get_generic_nth(&db, &msg.topic, n) {
                             Ok(g) => g,
                             Err(e) => GenericMsg::result(Err(e)),
                         };
 
-                        if let Ok(return_bytes) = response.as_bytes() {
-                            if let Ok(()) = s.writable().await {
-                                if let Err(e) = s.try_send_to(&return_bytes, return_addr) {
-                                    error!("Error sending data back on UDP/GET: {}", e)
-                                };
-                            };
-                        }
+                        let tracker = Some(&tracker);
+                        send_response(&s, &response, return_addr, cipher.as_deref(), tracker).await;
                     }
                     MsgType::Topics => {
                         let response = match db.topics() {
@@ -91,38 +188,56 @@ pub async fn process_udp(
                             Err(e) => GenericMsg::result(Err(e)),
                         };
 
-                        if let Ok(return_bytes) = response.as_bytes() {
-                            if let Ok(()) = s.writable().await {
-                                if let Err(e) = s.try_send_to(&return_bytes, return_addr) {
-                                    error!("Error sending data back on UDP/GET: {}", e)
-                                };
-                            };
-                        }
+                        let tracker = Some(&tracker);
+                        send_response(&s, &response, return_addr, cipher.as_deref(), tracker).await;
                     }
                     MsgType::Subscribe => {
-                        let specialized: Msg<Duration> = msg.clone().try_into().unwrap();
+                        let specialized: Msg<Duration> = match msg.clone().try_into() {
+                            Ok(specialized) => specialized,
+                            Err(e) => {
+                                warn!("Bad subscription from {}: {}", return_addr, e);
+                                let response = GenericMsg::result(Err(e));
+                                let tracker = Some(&tracker);
+                                send_response(
+                                    &s,
+                                    &response,
+                                    return_addr,
+                                    cipher.as_deref(),
+                                    tracker,
+                                )
+                                .await;
+                                continue;
+                            }
+                        };
                         let rate = specialized.data;
 
                         let db = db.clone();
+                        let cipher = cipher.clone();
+                        let mut shutdown = shutdown.clone();
+                        tracker.subscribed(&msg.topic);
                         rt_handle.spawn(async move {
-                            loop {
-                                let response = match db.get_generic_nth(&msg.topic, 0) {
+                            // Ends once the Node stops sending heartbeats
+                            while tracker.is_open() {
+                                let response = match tracker.get_generic_nth(&db, &msg.topic, 0) {
                                     Ok(g) => g,
                                     Err(e) => GenericMsg::result(Err(e)),
                                 };
 
-                                if let Ok(return_bytes) = response.as_bytes() {
-                                    if let Ok(()) = s.writable().await {
-                                        if let Err(e) = s.try_send_to(&return_bytes, return_addr) {
-                                            error!("Error sending data back on UDP/GET: {}", e)
-                                        };
-                                    };
-                                }
+                                let cipher = cipher.as_deref();
+                                send_response(&s, &response, return_addr, cipher, Some(&tracker))
+                                    .await;
 
-                                sleep(rate).await;
+                                if !shutdown.sleep(rate).await {
+                                    break;
+                                }
                             }
                         });
                     }
+                    MsgType::Heartbeat => {
+                        let response = GenericMsg::heartbeat();
+                        let tracker = Some(&tracker);
+                        send_response(&s, &response, return_addr, cipher.as_deref(), tracker).await;
+                    }
                     _ => {}
                 }
             }
@@ -137,3 +252,35 @@ pub async fn process_udp(
         }
     }
 }
+
+/// Send a response to the Node at `return_addr`, sealed with the pre-shared key if one is set
+async fn send_response(
+    socket: &UdpSocket,
+    response: &GenericMsg,
+    return_addr: SocketAddr,
+    cipher: Option<&DatagramCipher>,
+    tracker: Option<&Tracker>,
+) {
+    let return_bytes = response
+        .as_bytes()
+        .map_err(Error::from)
+        .and_then(|bytes| match cipher {
+            Some(cipher) => cipher.seal(&bytes),
+            None => Ok(bytes),
+        });
+    match return_bytes {
+        Ok(return_bytes) => {
+            if let Ok(()) = socket.writable().await {
+                match socket.try_send_to(&return_bytes, return_addr) {
+                    Ok(n) => {
+                        if let Some(tracker) = tracker {
+                            tracker.sent(n);
+                        }
+                    }
+                    Err(e) => error!("Error sending data back on UDP/GET: {}", e),
+                };
+            };
+        }
+        Err(e) => error!("Unable to encode UDP response: {}", e),
+    }
+}
@@ -0,0 +1,203 @@
+// Tokio for async
+use tokio::net::UnixStream;
+use tokio::time::Duration;
+// Tracing for logging
+use tracing::*;
+// Postcard is the default de/serializer
+use postcard::*;
+
+use crate::error::Error;
+use crate::handshake::{self, rejected, Handshake};
+use crate::host::acl::Authorizer;
+use crate::host::registry::Tracker;
+use crate::host::shutdown::{notice, Shutdown};
+use crate::host::GenericStore;
+use crate::prelude::*;
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::result::Result;
+
+/// Initiate a Unix domain socket connection with a Node, validating its handshake
+#[inline]
+#[tracing::instrument(skip(tokens))]
+pub async fn handshake(
+    stream: UnixStream,
+    max_buffer_size: usize,
+    max_name_size: usize,
+    tokens: &HashMap<String, String>,
+) -> Result<(UnixStream, Handshake), Error> {
+    let mut buf = vec![0u8; max_buffer_size];
+
+    // Readiness can be spurious, so reads and writes are retried until they don't block
+    let n = loop {
+        stream.readable().await?;
+        match stream.try_read(&mut buf) {
+            // The Node hung up without sending a handshake
+            Ok(0) => return Err(Error::AccessStream),
+            Ok(n) => break n,
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => continue,
+            Err(e) => return Err(e.into()),
+        }
+    };
+    let result = handshake::accept(&buf[..n], max_name_size, tokens);
+
+    let reply = handshake::reply(&result)?.as_bytes()?;
+    loop {
+        stream.writable().await?;
+        match stream.try_write(&reply) {
+            Ok(_) => break,
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok((stream, result?))
+}
+
+/// Host process for handling incoming Unix domain socket connections from Nodes
+#[tracing::instrument(skip_all)]
+#[inline]
+pub async fn process_uds(
+    stream: UnixStream,
+    mut db: sled::Db,
+    max_buffer_size: usize,
+    auth: Authorizer,
+    mut shutdown: Shutdown,
+    tracker: Tracker,
+) {
+    let mut buf = vec![0u8; max_buffer_size];
+    loop {
+        tokio::select! {
+            readable = stream.readable() => {
+                if let Err(e) = readable {
+                    error!("{}", e);
+                }
+            }
+            _ = shutdown.wait() => {
+                let _ = write_response(&stream, notice(), &tracker).await;
+                break;
+            }
+        }
+        match stream.try_read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => {
+                tracker.received(n);
+                let bytes = &buf[..n];
+                let msg: GenericMsg = match from_bytes(bytes) {
+                    Ok(msg) => msg,
+                    Err(e) => {
+                        error!("Had received Msg of {} bytes: {:?}, Error: {}", n, bytes, e);
+                        continue;
+                    }
+                };
+
+                if let Err(e) = auth.check(&msg) {
+                    if let Err(e) =
+                        write_response(&stream, GenericMsg::result(Err(e)), &tracker).await
+                    {
+                        error!("Error sending data back on UDS: {:?}", e);
+                    }
+                    continue;
+                }
+
+                let response = match &msg.msg_type {
+                    MsgType::Subscribe => {
+                        start_subscription(
+                            msg.clone(),
+                            db.clone(),
+                            &stream,
+                            &mut shutdown,
+                            &tracker,
+                        )
+                        .await;
+                        continue;
+                    }
+                    MsgType::Get => match tracker.get_generic_nth(&db, &msg.topic, 0) {
+                        Ok(g) => g,
+                        Err(e) => GenericMsg::result(Err(e)),
+                    },
+                    MsgType::GetNth(n) => match tracker.get_generic_nth(&db, &msg.topic, *n) {
+                        Ok(g) => g,
+                        Err(e) => GenericMsg::result(Err(e)),
+                    },
+                    MsgType::Set => GenericMsg::result(tracker.insert_generic(&mut db, msg)),
+                    MsgType::Topics => match db.topics() {
+                        Ok(topics) => {
+                            let msg = Msg::new(MsgType::Topics, "", topics);
+                            match msg.to_generic() {
+                                Ok(msg) => msg,
+                                Err(e) => GenericMsg::result(Err(e)),
+                            }
+                        }
+                        Err(e) => GenericMsg::result(Err(e)),
+                    },
+                    MsgType::Handshake => {
+                        GenericMsg::result(Err(rejected("the handshake was already completed")))
+                    }
+                    MsgType::Heartbeat => GenericMsg::heartbeat(),
+                    MsgType::Result(result) => {
+                        if let Err(e) = result {
+                            error!("{}", e);
+                        }
+                        continue;
+                    }
+                };
+
+                if let Err(e) = write_response(&stream, response, &tracker).await {
+                    error!("Error sending data back on UDS: {:?}", e);
+                }
+            }
+            Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                continue;
+            }
+            Err(e) => {
+                error!("Error: {:?}", e);
+                break;
+            }
+        }
+    }
+}
+
+async fn write_response(
+    stream: &UnixStream,
+    response: GenericMsg,
+    tracker: &Tracker,
+) -> Result<(), Error> {
+    let return_bytes = response.as_bytes()?;
+    stream.writable().await?;
+    let n = stream.try_write(&return_bytes)?;
+    tracker.sent(n);
+    Ok(())
+}
+
+async fn start_subscription(
+    msg: GenericMsg,
+    db: sled::Db,
+    stream: &UnixStream,
+    shutdown: &mut Shutdown,
+    tracker: &Tracker,
+) {
+    let specialized: Msg<Duration> = match msg.clone().try_into() {
+        Ok(specialized) => specialized,
+        Err(e) => {
+            error!("{}", e);
+            return;
+        }
+    };
+    let rate = specialized.data;
+    tracker.subscribed(&msg.topic);
+
+    loop {
+        let response = match tracker.get_generic_nth(&db, &msg.topic, 0) {
+            Ok(g) => g,
+            Err(e) => GenericMsg::result(Err(e)),
+        };
+        if let Err(e) = write_response(stream, response, tracker).await {
+            // The Node has hung up, so there's no one left to send to
+            debug!("Ending UDS subscription on {}: {:?}", &msg.topic, e);
+            break;
+        }
+        if !shutdown.sleep(rate).await {
+            break;
+        }
+    }
+}
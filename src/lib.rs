@@ -18,13 +18,26 @@
 //!
 //!| Protocol | Publish   | Request    | Subscribe | Encryption |
 //!|----------|-----------|------------|-----------|------------|
-//!| TCP      | **X**     | **X**      | **X**     |            |
-//!| UDP      | **X**     | **X**      | **X**     |            |
+//!| TCP      | **X**     | **X**      | **X**     | `tls`      |
+//!| UDP      | **X**     | **X**      | **X**     | PSK        |
 //!| QUIC     | **X**     | **X**      | **X**     | **X**      |
+//!| UDS      | **X**     | **X**      | **X**     |            |
+//!| SHM      | **X**     | **X**      | **X**     |            |
+//!| In-process | **X**   | **X**      | **X**     |            |
 //!
 
+/// Compression of message payloads
+pub mod compression;
+/// Multicast announcement and discovery of Hosts
+pub mod discovery;
+/// Serialization formats for message payloads
+pub mod encoding;
 /// Error types used by Meadow
 pub mod error;
+/// Identification of Nodes to the Host when they connect
+pub mod handshake;
+/// Detection of Nodes and Hosts that have stopped responding
+pub mod heartbeat;
 /// Central coordination process, which stores published data and responds to requests
 pub mod host;
 /// Message definitions for publish/request functions
@@ -33,6 +46,13 @@ pub mod msg;
 pub mod networks;
 /// Objects that publish and request strongly-typed data to named topics on the Host
 pub mod node;
+/// Pre-shared-key encryption of UDP datagrams
+pub mod psk;
+/// Zero-copy access to fixed-layout message data
+#[cfg(feature = "zerocopy")]
+pub mod raw;
+/// Stable identities for message types, independent of where they are defined
+pub mod schema;
 
 /// Re-export of Serde's `Serialize` and `Deserialize` traits
 pub use serde::{Deserialize, Serialize};
@@ -50,19 +70,44 @@ pub mod prelude {
     pub use crate::{Deserialize, Serialize};
     pub use chrono::*;
 
+    pub use crate::compression::Compression;
+    pub use crate::discovery::{Discovery, DiscoveryConfig, Transport};
+    pub use crate::encoding::Encoding;
     pub use crate::error::Error;
+    pub use crate::heartbeat::{HeartbeatConfig, Liveness};
     pub use crate::msg::{GenericMsg, Message, Msg, MsgType};
     pub use crate::networks::get_ip;
+    pub use crate::psk::PreSharedKey;
+    pub use crate::schema::{Schema, SchemaDescription};
 
-    pub use crate::host::{Host, HostConfig, SledConfig, Store, UdpConfig};
+    pub use crate::host::{Host, HostConfig, HostHandle, SledConfig, Store, UdpConfig};
     pub use crate::node::config::NodeConfig;
     pub use crate::node::config::RuntimeConfig;
-    pub use crate::node::network_config::{Blocking, NetworkConfig, Nonblocking, Tcp, Udp};
+    pub use crate::node::dynamic::{DynamicNode, DynamicNodeConfig};
+    pub use crate::node::network_config::{
+        Blocking, InProcess, NetworkConfig, Nonblocking, Tcp, Udp,
+    };
+    pub use crate::node::traits::{
+        AsyncPublisher, AsyncRequester, AsyncSubscriber, Publisher, Requester, Subscriber,
+    };
     pub use crate::node::{Active, Idle, Node, Subscription};
     pub use sled::Db;
 
+    #[cfg(feature = "zerocopy")]
+    pub use crate::raw::{MsgView, Raw};
+
     #[cfg(feature = "quic")]
     pub use crate::host::{generate_certs, QuicConfig};
     #[cfg(feature = "quic")]
     pub use crate::node::network_config::Quic;
+
+    #[cfg(unix)]
+    pub use crate::host::UdsConfig;
+    #[cfg(unix)]
+    pub use crate::node::network_config::Uds;
+
+    #[cfg(all(unix, feature = "shm"))]
+    pub use crate::host::ShmConfig;
+    #[cfg(all(unix, feature = "shm"))]
+    pub use crate::node::network_config::Shm;
 }
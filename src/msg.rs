@@ -1,3 +1,6 @@
+use crate::compression::Compression;
+use crate::encoding::Encoding;
+use crate::error::HostError;
 use crate::Error;
 use chrono::{DateTime, Utc};
 use postcard::to_allocvec;
@@ -6,6 +9,30 @@ use std::convert::{Into, TryInto};
 use std::time::Duration;
 
 use std::fmt::Debug;
+
+/// Version of the Host <=> Node protocol, advertised by Host discovery and negotiated in handshakes.
+///
+/// Any change to how `GenericMsg` or `MsgType` are encoded must bump this version; see
+/// `design/wire_format.md` for the encoding it pins.
+pub const PROTOCOL_VERSION: u16 = 1;
+/// Oldest protocol version this build can still speak
+pub const MIN_PROTOCOL_VERSION: u16 = 1;
+
+/// Newest protocol version spoken both by this build and a peer supporting `min..=max`
+pub fn negotiate_version(min: u16, max: u16) -> Option<u16> {
+    negotiate(MIN_PROTOCOL_VERSION, PROTOCOL_VERSION, min, max)
+}
+
+/// Newest version in both `our_min..=our_max` and `min..=max`
+fn negotiate(our_min: u16, our_max: u16, min: u16, max: u16) -> Option<u16> {
+    let version = max.min(our_max);
+    if version >= min.max(our_min) {
+        Some(version)
+    } else {
+        None
+    }
+}
+
 /// Trait for Meadow-compatible data, requiring serde De\Serialize, Debug, and Clone
 pub trait Message: Serialize + DeserializeOwned + Debug + Sync + Send + Clone {}
 impl<T> Message for T where T: Serialize + DeserializeOwned + Debug + Sync + Send + Clone {}
@@ -26,6 +53,25 @@ pub enum MsgType {
     Subscribe,
     /// Communicate success or failure of certain Host-side operations
     Result(Result<(), crate::Error>),
+    /// Introduce a newly-connected Node to the Host
+    Handshake,
+    /// Show the other side of a connection is still alive, which it answers in kind
+    Heartbeat,
+}
+
+impl MsgType {
+    /// Fixed `data_type` of control messages, whose payloads are defined by the protocol rather
+    /// than by a topic's type; `None` for the `Set`, `Get` and `GetNth` messages carrying topic data
+    pub fn protocol_data_type(&self) -> Option<&'static str> {
+        match self {
+            MsgType::Set | MsgType::Get | MsgType::GetNth(_) => None,
+            MsgType::Topics => Some("meadow/topics"),
+            MsgType::Subscribe => Some("meadow/subscribe"),
+            MsgType::Result(_) => Some("meadow/result"),
+            MsgType::Handshake => Some("meadow/handshake"),
+            MsgType::Heartbeat => Some("meadow/heartbeat"),
+        }
+    }
 }
 
 /// Message format containing a strongly-typed data payload and associated metadata
@@ -38,7 +84,8 @@ pub struct Msg<T> {
     pub timestamp: DateTime<Utc>,
     /// Topic name
     pub topic: String,
-    /// Name of message's data type (`String`-typed)
+    /// Identity of the message's data type: its schema identifier if registered with
+    /// `meadow::schema::register`, otherwise its `std::any::type_name`
     pub data_type: String,
     /// Strongly-typed data payload
     pub data: T,
@@ -47,11 +94,15 @@ pub struct Msg<T> {
 impl<T: Message> Msg<T> {
     /// Create a new strongly-typed message (default timestamp is from `SystemTime` in UTC)
     pub fn new(msg_type: MsgType, topic: impl Into<String>, data: T) -> Self {
+        let data_type = match msg_type.protocol_data_type() {
+            Some(data_type) => data_type.to_string(),
+            None => crate::schema::data_type::<T>(),
+        };
         Msg {
             msg_type,
             timestamp: Utc::now(),
             topic: topic.into(),
-            data_type: std::any::type_name::<T>().to_string(),
+            data_type,
             data,
         }
     }
@@ -75,6 +126,20 @@ impl<T: Message> Msg<T> {
     pub fn to_generic(self) -> Result<GenericMsg, crate::Error> {
         self.try_into()
     }
+
+    /// Attempt conversion to a `GenericMsg` whose payload is serialized in `encoding`
+    pub fn to_generic_encoded(self, encoding: Encoding) -> Result<GenericMsg, crate::Error> {
+        let data = encoding.encode(&self.data)?;
+        Ok(GenericMsg {
+            msg_type: self.msg_type,
+            timestamp: self.timestamp,
+            topic: self.topic,
+            data_type: self.data_type,
+            encoding,
+            compression: Compression::None,
+            data,
+        })
+    }
 }
 /// Message format containing a generic `Vec<u8>` data payload and associated metadata
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -86,21 +151,29 @@ pub struct GenericMsg {
     pub timestamp: DateTime<Utc>,
     /// Topic name
     pub topic: String,
-    /// Name of message's data type (`String`-typed)
+    /// Identity of the message's data type: its schema identifier if registered with
+    /// `meadow::schema::register`, otherwise its `std::any::type_name`
     pub data_type: String,
+    /// Format the data payload is serialized in
+    pub encoding: Encoding,
+    /// Compression applied to the serialized data payload
+    pub compression: Compression,
     /// Generic byte-represented data payload
     pub data: Vec<u8>,
 }
 
 impl GenericMsg {
-    /// Create a default `MsgType::Set` message for published messages
+    /// Create a default `MsgType::Set` message for published messages, whose `data` is
+    /// `postcard`-encoded
     #[inline]
     pub fn set<T: Message>(topic: impl Into<String>, data: Vec<u8>) -> Self {
         GenericMsg {
             msg_type: MsgType::Set,
             timestamp: Utc::now(),
             topic: topic.into(),
-            data_type: std::any::type_name::<T>().to_string(),
+            data_type: crate::schema::data_type::<T>(),
+            encoding: Encoding::Postcard,
+            compression: Compression::None,
             data,
         }
     }
@@ -112,7 +185,9 @@ impl GenericMsg {
             msg_type: MsgType::Get,
             timestamp: Utc::now(),
             topic: topic.into(),
-            data_type: std::any::type_name::<T>().to_string(),
+            data_type: crate::schema::data_type::<T>(),
+            encoding: Encoding::Postcard,
+            compression: Compression::None,
             data: Vec::new(),
         }
     }
@@ -131,7 +206,9 @@ impl GenericMsg {
             msg_type: MsgType::GetNth(n),
             timestamp: Utc::now(),
             topic: topic.into(),
-            data_type: std::any::type_name::<T>().to_string(),
+            data_type: crate::schema::data_type::<T>(),
+            encoding: Encoding::Postcard,
+            compression: Compression::None,
             data: Vec::new(),
         }
     }
@@ -143,7 +220,23 @@ impl GenericMsg {
             msg_type: MsgType::Topics,
             timestamp: Utc::now(),
             topic: String::new(),
-            data_type: std::any::type_name::<()>().to_string(),
+            data_type: "meadow/topics".into(),
+            encoding: Encoding::Postcard,
+            compression: Compression::None,
+            data: Vec::new(),
+        }
+    }
+
+    /// Create a `MsgType::Heartbeat` message
+    #[inline]
+    pub fn heartbeat() -> Self {
+        GenericMsg {
+            msg_type: MsgType::Heartbeat,
+            timestamp: Utc::now(),
+            topic: String::new(),
+            data_type: "meadow/heartbeat".into(),
+            encoding: Encoding::Postcard,
+            compression: Compression::None,
             data: Vec::new(),
         }
     }
@@ -154,7 +247,9 @@ impl GenericMsg {
             msg_type: MsgType::Result(r),
             timestamp: Utc::now(),
             topic: String::new(),
-            data_type: std::any::type_name::<()>().to_string(),
+            data_type: "meadow/result".into(),
+            encoding: Encoding::Postcard,
+            compression: Compression::None,
             data: Vec::new(),
         }
     }
@@ -164,6 +259,31 @@ impl GenericMsg {
         self.data = data;
     }
 
+    /// Set the format the data payload is serialized in
+    pub fn set_encoding(&mut self, encoding: Encoding) {
+        self.encoding = encoding;
+    }
+
+    /// Compress the data payload, unless it's already compressed or wouldn't get any smaller
+    pub fn compressed(mut self, compression: Compression) -> Self {
+        if self.compression == Compression::None && compression != Compression::None {
+            let data = compression.compress(&self.data);
+            if data.len() < self.data.len() {
+                self.data = data;
+                self.compression = compression;
+            }
+        }
+        self
+    }
+
+    /// Payload as it was serialized, decompressing it if necessary
+    pub fn decompressed_data(&self) -> Result<std::borrow::Cow<'_, [u8]>, Error> {
+        match self.compression {
+            Compression::None => Ok(std::borrow::Cow::Borrowed(&self.data)),
+            compression => Ok(std::borrow::Cow::Owned(compression.decompress(&self.data)?)),
+        }
+    }
+
     /// Convert `GenericMsg` into a `postcard`-encoded byte string
     pub fn as_bytes(&self) -> Result<Vec<u8>, postcard::Error> {
         postcard::to_allocvec(&self)
@@ -174,12 +294,27 @@ impl<T: Message> TryInto<Msg<T>> for GenericMsg {
     type Error = crate::Error;
 
     fn try_into(self) -> Result<Msg<T>, Error> {
-        let data = postcard::from_bytes::<T>(&self.data[..])?;
+        // The Host answered with an error in place of the data
+        if let MsgType::Result(Err(e)) = &self.msg_type {
+            return Err(e.clone());
+        }
+        // Catch topic data of another type before it's misread as `T`. Control messages' payloads
+        // are fixed by the protocol, whatever type the receiver names them with.
+        if self.msg_type.protocol_data_type().is_none() {
+            let requested = crate::schema::data_type::<T>();
+            if self.data_type != requested {
+                return Err(Error::HostOperation(HostError::TypeMismatch {
+                    expected: self.data_type,
+                    found: requested,
+                }));
+            }
+        }
+        let data = self.encoding.decode::<T>(&self.decompressed_data()?)?;
         Ok(Msg {
             msg_type: self.msg_type,
             timestamp: self.timestamp,
-            topic: self.topic.clone(),
-            data_type: self.data_type.clone(),
+            topic: self.topic,
+            data_type: self.data_type,
             data,
         })
     }
@@ -189,14 +324,7 @@ impl<T: Message> TryInto<GenericMsg> for Msg<T> {
     type Error = crate::Error;
 
     fn try_into(self) -> Result<GenericMsg, Error> {
-        let data = postcard::to_allocvec(&self.data)?;
-        Ok(GenericMsg {
-            msg_type: self.msg_type,
-            timestamp: self.timestamp,
-            topic: self.topic.clone(),
-            data_type: self.data_type.clone(),
-            data,
-        })
+        self.to_generic_encoded(Encoding::Postcard)
     }
 }
 
@@ -211,3 +339,132 @@ fn msg_conversions() {
     let msg_rc: Msg<i32> = generic_rc.try_into().unwrap();
     assert_eq!(msg, msg_rc);
 }
+
+#[test]
+fn data_type_checks() {
+    // Topic data is only decoded as the type it was published as
+    let set = Msg::new(MsgType::Set, "value", 0i32).to_generic().unwrap();
+    assert!(TryInto::<Msg<i64>>::try_into(set).is_err());
+
+    // Control messages carry the protocol's identifiers rather than their payloads' type names
+    let subscribe = GenericMsg::subscribe("value", Duration::from_millis(10)).unwrap();
+    assert_eq!(subscribe.data_type, "meadow/subscribe");
+    let rate: Msg<Duration> = subscribe.try_into().unwrap();
+    assert_eq!(rate.data, Duration::from_millis(10));
+    let topics = Msg::new(MsgType::Topics, "", vec!["value".to_string()]);
+    assert_eq!(topics.data_type, "meadow/topics");
+    let topics: Msg<Vec<String>> = topics.to_generic().unwrap().try_into().unwrap();
+    assert_eq!(topics.data, ["value"]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    // Golden bytes pinning the wire format of `PROTOCOL_VERSION` 1. If any of these change,
+    // mixed-version fleets break: bump `PROTOCOL_VERSION` and update `design/wire_format.md`.
+
+    #[test]
+    fn msg_type_encoding() {
+        let golden: Vec<(MsgType, &[u8])> = vec![
+            (MsgType::Set, &[0]),
+            (MsgType::Get, &[1]),
+            (MsgType::GetNth(3), &[2, 3]),
+            (MsgType::Topics, &[3]),
+            (MsgType::Subscribe, &[4]),
+            (MsgType::Result(Ok(())), &[5, 0]),
+            (
+                MsgType::Result(Err(Error::HostOperation(HostError::NonExistentTopic))),
+                &[5, 1, 0, 3],
+            ),
+            (MsgType::Handshake, &[6]),
+            (MsgType::Heartbeat, &[7]),
+        ];
+        for (msg_type, bytes) in golden {
+            assert_eq!(
+                postcard::to_allocvec(&msg_type).unwrap(),
+                bytes,
+                "{:?}",
+                msg_type
+            );
+            assert_eq!(postcard::from_bytes::<MsgType>(bytes).unwrap(), msg_type);
+        }
+    }
+
+    #[test]
+    fn error_encoding() {
+        // Indices must be the same with or without the `quic` feature
+        let golden: Vec<(Error, &[u8])> = vec![
+            (
+                Error::Io {
+                    error_kind: "x".into(),
+                    raw_os_error: None,
+                },
+                &[19, 1, b'x', 0],
+            ),
+            (Error::NonExistentTopic("a".into()), &[21, 1, b'a']),
+            (Error::Undefined, &[23]),
+            (Error::Decompression, &[26]),
+            (Error::NoTransport(Vec::new()), &[28, 0]),
+        ];
+        for (error, bytes) in golden {
+            assert_eq!(postcard::to_allocvec(&error).unwrap(), bytes, "{:?}", error);
+            assert_eq!(postcard::from_bytes::<Error>(bytes).unwrap(), error);
+        }
+    }
+
+    #[test]
+    fn generic_msg_encoding() {
+        let msg = GenericMsg {
+            msg_type: MsgType::Set,
+            timestamp: Utc.timestamp_opt(1_700_000_000, 0).unwrap(),
+            topic: "pose".into(),
+            data_type: "u8".into(),
+            encoding: Encoding::Postcard,
+            compression: Compression::None,
+            data: vec![7],
+        };
+        #[rustfmt::skip]
+        let golden: &[u8] = &[
+            // msg_type
+            0,
+            // timestamp, as an RFC 3339 string
+            20, b'2', b'0', b'2', b'3', b'-', b'1', b'1', b'-', b'1', b'4', b'T',
+            b'2', b'2', b':', b'1', b'3', b':', b'2', b'0', b'Z',
+            // topic
+            4, b'p', b'o', b's', b'e',
+            // data_type
+            2, b'u', b'8',
+            // encoding
+            0,
+            // compression
+            0,
+            // data
+            1, 7,
+        ];
+        assert_eq!(msg.as_bytes().unwrap(), golden);
+        assert_eq!(postcard::from_bytes::<GenericMsg>(golden).unwrap(), msg);
+    }
+
+    #[test]
+    fn version_negotiation() {
+        // A build speaking versions 3 to 5 picks the newest one its peer also speaks
+        assert_eq!(negotiate(3, 5, 1, 4), Some(4));
+        assert_eq!(negotiate(3, 5, 4, 9), Some(5));
+        assert_eq!(negotiate(3, 5, 3, 3), Some(3));
+        assert_eq!(negotiate(3, 5, 1, 2), None);
+        assert_eq!(negotiate(3, 5, 6, 9), None);
+
+        // Newer peers that can fall back to our version speak it
+        assert_eq!(
+            negotiate_version(MIN_PROTOCOL_VERSION, PROTOCOL_VERSION + 3),
+            Some(PROTOCOL_VERSION)
+        );
+        assert_eq!(
+            negotiate_version(PROTOCOL_VERSION + 1, PROTOCOL_VERSION + 3),
+            None
+        );
+        assert_eq!(negotiate_version(0, MIN_PROTOCOL_VERSION - 1), None);
+    }
+}
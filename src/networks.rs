@@ -1,27 +1,79 @@
 use crate::Error;
-use std::net::{IpAddr, Ipv4Addr};
+use socket2::{Domain, Protocol, Socket, Type};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 
 /// Get the IP address on a network interface for this computer
 pub fn get_ip(interface_name: &str) -> Result<Ipv4Addr, Error> {
-    let interface = match pnet_datalink::interfaces()
+    match get_ips(interface_name)?
+        .into_iter()
+        .find_map(|ip| match ip {
+            IpAddr::V4(ip) => Some(ip),
+            IpAddr::V6(_) => None,
+        }) {
+        Some(ip) => Ok(ip),
+        None => Err(Error::InvalidInterface),
+    }
+}
+
+/// Get the IPv6 address on a network interface for this computer
+pub fn get_ipv6(interface_name: &str) -> Result<Ipv6Addr, Error> {
+    match get_ips(interface_name)?
+        .into_iter()
+        .find_map(|ip| match ip {
+            IpAddr::V6(ip) => Some(ip),
+            IpAddr::V4(_) => None,
+        }) {
+        Some(ip) => Ok(ip),
+        None => Err(Error::InvalidInterface),
+    }
+}
+
+/// Get all IPv4 and IPv6 addresses on a network interface for this computer
+pub fn get_ips(interface_name: &str) -> Result<Vec<IpAddr>, Error> {
+    match pnet_datalink::interfaces()
         .into_iter()
         .find(|iface| iface.name == interface_name)
     {
-        Some(interfaces) => interfaces,
-        None => return Err(Error::InvalidInterface),
-    };
+        Some(interface) => Ok(interface.ips.iter().map(|ip| ip.ip()).collect()),
+        None => Err(Error::InvalidInterface),
+    }
+}
 
-    let source_ip = match interface
-        .ips
-        .iter()
-        .find(|ip| ip.is_ipv4())
-        .map(|ip| match ip.ip() {
-            IpAddr::V4(ip) => ip,
-            _ => unreachable!(),
-        }) {
-        Some(name) => name,
-        None => return Err(Error::InvalidInterface),
-    };
+/// Unspecified address of the same IP family as `remote`, for binding a local socket that talks to it
+pub(crate) fn unspecified_addr(remote: &SocketAddr) -> SocketAddr {
+    match remote {
+        SocketAddr::V4(_) => SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0),
+        SocketAddr::V6(_) => SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 0),
+    }
+}
+
+/// Bind `socket` to `addr` for use with an async runtime.
+///
+/// IPv6 sockets accept IPv4 traffic as well unless `ipv6_only` is set, rather than
+/// relying on platform defaults for dual-stack behavior.
+fn configure_and_bind(socket: &Socket, addr: SocketAddr, ipv6_only: bool) -> Result<(), Error> {
+    if addr.is_ipv6() {
+        socket.set_only_v6(ipv6_only)?;
+    }
+    socket.set_nonblocking(true)?;
+    socket.bind(&addr.into())?;
+    Ok(())
+}
+
+/// Bind a listening TCP socket, see [`configure_and_bind`]
+pub(crate) fn bind_tcp(addr: SocketAddr, ipv6_only: bool) -> Result<std::net::TcpListener, Error> {
+    let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+    // Match `tokio::net::TcpListener::bind`, so a restarted Host can reclaim its port right away
+    #[cfg(unix)]
+    socket.set_reuse_address(true)?;
+    configure_and_bind(&socket, addr, ipv6_only)?;
+    socket.listen(1024)?;
+    Ok(socket.into())
+}
 
-    Ok(source_ip)
+/// Bind a UDP socket, see [`configure_and_bind`]
+pub(crate) fn bind_udp(addr: SocketAddr, ipv6_only: bool) -> Result<std::net::UdpSocket, Error> {
+    let socket = Socket::new(Domain::for_address(addr), Type::DGRAM, Some(Protocol::UDP))?;
+    configure_and_bind(&socket, addr, ipv6_only)?;
+    Ok(socket.into())
 }
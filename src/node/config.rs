@@ -4,14 +4,16 @@ use std::sync::Arc;
 use tokio::runtime::Handle;
 use tokio::sync::Mutex as TokioMutex;
 
+use crate::heartbeat::Pulse;
 use crate::node::network_config::*;
 use crate::node::Node;
 use crate::node::{Active, Idle};
+use crate::psk::DatagramCipher;
 use std::default::Default;
 use std::marker::PhantomData;
 use std::sync::Mutex;
 
-/// Defines whether the Node should own it's async runtime or use a provided handle to an external one
+/// Defines whether a Node or Host should own its async runtime or use a provided handle to an external one
 #[derive(Debug, Clone)]
 pub struct RuntimeConfig {
     pub owned_runtime: bool,
@@ -46,6 +48,14 @@ impl RuntimeConfig {
 pub struct NodeConfig<B: Block, I: Interface + Default, T: Message> {
     pub __data_type: PhantomData<T>,
     pub topic: Option<String>,
+    /// Name the Node identifies itself to the Host with, which defaults to its topic
+    pub name: Option<String>,
+    /// Token proving the Node may use its name, when the Host requires one
+    pub token: Option<String>,
+    /// Format the Node serializes the data it publishes in
+    pub encoding: Encoding,
+    /// Compression the Node applies to the data it publishes
+    pub compression: Compression,
     pub network_cfg: NetworkConfig<B, I>,
     pub runtime_cfg: RuntimeConfig,
 }
@@ -59,6 +69,10 @@ where
         NodeConfig {
             __data_type: PhantomData,
             topic: Some(topic.into()),
+            name: None,
+            token: None,
+            encoding: Encoding::default(),
+            compression: Compression::default(),
             network_cfg: NetworkConfig::<B, I>::default(),
             runtime_cfg: RuntimeConfig::default(),
         }
@@ -70,6 +84,30 @@ where
         self
     }
 
+    /// Identify the Node to the Host by `name` rather than its topic
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Present `token` in the handshake, for Hosts that require one for this Node's name
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Serialize published data in `encoding`, e.g. `Encoding::Json` for web dashboards
+    pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Compress published data, e.g. with `Compression::Lz4` for maps or images sent over slow links
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
     pub fn with_runtime_config(mut self, runtime_cfg: RuntimeConfig) -> Self {
         self.runtime_cfg = runtime_cfg;
         self
@@ -103,6 +141,11 @@ impl<B: Block, I: Interface + Default + Clone, T: Message> NodeConfig<B, I, T> {
         };
 
         let max_buffer_size = self.network_cfg.max_buffer_size;
+        let udp_cipher = self
+            .network_cfg
+            .psk
+            .as_ref()
+            .map(|psk| Arc::new(DatagramCipher::new(psk)));
 
         Ok(Node::<B, I, Idle, T> {
             __state: PhantomData::<Idle>,
@@ -111,7 +154,13 @@ impl<B: Block, I: Interface + Default + Clone, T: Message> NodeConfig<B, I, T> {
             rt_handle,
             cfg: self,
             stream: None,
+            #[cfg(unix)]
+            uds_stream: None,
+            #[cfg(all(unix, feature = "shm"))]
+            shm: None,
             socket: None,
+            udp_cipher,
+            in_process: None,
             buffer: Arc::new(TokioMutex::new(vec![0u8; max_buffer_size])),
             //buffer: Arc::new(Vec::with_capacity(max_buffer_size)),
             #[cfg(feature = "quic")]
@@ -120,7 +169,9 @@ impl<B: Block, I: Interface + Default + Clone, T: Message> NodeConfig<B, I, T> {
             connection: None,
             topic,
             subscription_data: Arc::new(TokioMutex::new(None)),
+            host_pulse: Arc::new(Pulse::default()),
             task_subscribe: None,
+            task_heartbeat: None,
         })
     }
 }
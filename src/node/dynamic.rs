@@ -0,0 +1,633 @@
+//! Nodes whose transport is chosen at runtime.
+//!
+//! A `Node`'s transport is fixed by its `Interface` type parameter, which suits most
+//! deployments but means a binary must be rebuilt to run on a network that blocks it. A
+//! `DynamicNode` instead holds a `NetworkConfig` for each of TCP, UDP, and QUIC and an ordered
+//! list of the transports to try, e.g. read from a configuration file, and connects over the
+//! first one that reaches the Host when it's activated.
+//!
+//! ```no_run
+//! use meadow::prelude::*;
+//!
+//! let transports: Vec<Transport> = "quic,tcp,udp"
+//!     .split(',')
+//!     .map(|t| t.parse().unwrap())
+//!     .collect();
+//! let node = DynamicNodeConfig::<Blocking, f32>::new("battery")
+//!     .with_transports(transports)
+//!     .build()
+//!     .unwrap()
+//!     .activate()
+//!     .unwrap();
+//! println!("Connected over {}", node.transport());
+//! node.publish(0.87).unwrap();
+//! ```
+
+use crate::compression::Compression;
+use crate::discovery::Transport;
+use crate::encoding::Encoding;
+use crate::error::Error;
+use crate::heartbeat::Liveness;
+use crate::msg::{Message, Msg};
+use crate::node::config::{NodeConfig, RuntimeConfig};
+use crate::node::network_config::{Block, Blocking, NetworkConfig, Nonblocking, Tcp, Udp};
+use crate::node::traits::{
+    AsyncPublisher, AsyncRequester, AsyncSubscriber, Publisher, Requester, Subscriber,
+};
+use crate::node::{Active, Idle, Node, Subscription};
+
+#[cfg(feature = "quic")]
+use crate::node::network_config::Quic;
+
+use tokio::runtime::{Handle, Runtime};
+use tokio::time::Duration;
+use tracing::*;
+
+use std::marker::PhantomData;
+
+/// Configuration of a strongly-typed Node whose transport is chosen when it's activated
+#[derive(Debug, Clone)]
+pub struct DynamicNodeConfig<B: Block, T: Message> {
+    pub __data_type: PhantomData<T>,
+    pub topic: String,
+    /// Name the Node identifies itself to the Host with, which defaults to its topic
+    pub name: Option<String>,
+    /// Token proving the Node may use its name, when the Host requires one
+    pub token: Option<String>,
+    /// Format the Node serializes the data it publishes in
+    pub encoding: Encoding,
+    /// Compression the Node applies to the data it publishes
+    pub compression: Compression,
+    /// Transports to try, most preferred first
+    pub transports: Vec<Transport>,
+    pub tcp_cfg: NetworkConfig<B, Tcp>,
+    pub udp_cfg: NetworkConfig<B, Udp>,
+    #[cfg(feature = "quic")]
+    pub quic_cfg: NetworkConfig<B, Quic>,
+    pub runtime_cfg: RuntimeConfig,
+}
+
+impl<B: Block, T: Message> DynamicNodeConfig<B, T>
+where
+    NetworkConfig<B, Tcp>: Default,
+    NetworkConfig<B, Udp>: Default,
+{
+    /// Create a Node on `topic` that tries QUIC (when enabled), then TCP, then UDP
+    pub fn new(topic: impl Into<String>) -> Self {
+        DynamicNodeConfig {
+            __data_type: PhantomData,
+            topic: topic.into(),
+            name: None,
+            token: None,
+            encoding: Encoding::default(),
+            compression: Compression::default(),
+            #[cfg(feature = "quic")]
+            transports: vec![Transport::Quic, Transport::Tcp, Transport::Udp],
+            #[cfg(not(feature = "quic"))]
+            transports: vec![Transport::Tcp, Transport::Udp],
+            tcp_cfg: NetworkConfig::default(),
+            udp_cfg: NetworkConfig::default(),
+            #[cfg(feature = "quic")]
+            quic_cfg: NetworkConfig::default(),
+            runtime_cfg: RuntimeConfig::default(),
+        }
+    }
+}
+
+impl<B: Block, T: Message> DynamicNodeConfig<B, T> {
+    /// Try `transports` in order when activating, until one of them reaches the Host
+    ///
+    /// Only TCP, UDP, and QUIC are supported; any other transport fails with
+    /// `Error::UnsupportedTransport`.
+    pub fn with_transports(mut self, transports: impl IntoIterator<Item = Transport>) -> Self {
+        self.transports = transports.into_iter().collect();
+        self
+    }
+
+    /// Configure the TCP connection parameters
+    pub fn with_tcp_config(mut self, tcp_cfg: NetworkConfig<B, Tcp>) -> Self {
+        self.tcp_cfg = tcp_cfg;
+        self
+    }
+
+    /// Configure the UDP connection parameters
+    pub fn with_udp_config(mut self, udp_cfg: NetworkConfig<B, Udp>) -> Self {
+        self.udp_cfg = udp_cfg;
+        self
+    }
+
+    /// Configure the QUIC connection parameters
+    #[cfg(feature = "quic")]
+    pub fn with_quic_config(mut self, quic_cfg: NetworkConfig<B, Quic>) -> Self {
+        self.quic_cfg = quic_cfg;
+        self
+    }
+
+    /// Identify the Node to the Host by `name` rather than its topic
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Present `token` in the handshake, for Hosts that require one for this Node's name
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Serialize published data in `encoding`
+    pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Compress published data
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    pub fn with_runtime_config(mut self, runtime_cfg: RuntimeConfig) -> Self {
+        self.runtime_cfg = runtime_cfg;
+        self
+    }
+
+    /// Construct a Node from the specified configuration
+    pub fn build(self) -> Result<DynamicNode<B, Idle, T>, Error> {
+        // Every attempted transport shares one runtime, rather than each creating its own
+        let (runtime, rt_handle) = if self.runtime_cfg.owned_runtime {
+            let runtime = tokio::runtime::Builder::new_multi_thread()
+                .enable_all()
+                .build()
+                .map_err(|_| Error::RuntimeCreation)?;
+            let handle = runtime.handle().clone();
+            (Some(runtime), handle)
+        } else if let Some(rt_handle) = self.runtime_cfg.rt_handle.clone() {
+            (None, rt_handle)
+        } else {
+            return Err(Error::RuntimeCreation);
+        };
+
+        Ok(DynamicNode {
+            __state: PhantomData,
+            inner: None,
+            cfg: self,
+            rt_handle,
+            runtime,
+        })
+    }
+
+    /// Configuration of a Node on a single transport
+    fn node_config<I>(
+        &self,
+        network_cfg: NetworkConfig<B, I>,
+        rt_handle: &Handle,
+    ) -> NodeConfig<B, I, T>
+    where
+        I: crate::node::network_config::Interface + Default + Clone,
+    {
+        NodeConfig {
+            __data_type: PhantomData,
+            topic: Some(self.topic.clone()),
+            name: self.name.clone(),
+            token: self.token.clone(),
+            encoding: self.encoding,
+            compression: self.compression,
+            network_cfg,
+            runtime_cfg: RuntimeConfig::default()
+                .with_owned_runtime(false)
+                .with_rt_handle(Some(rt_handle.clone())),
+        }
+    }
+}
+
+/// Node connected over the transport that was selected
+#[derive(Debug)]
+enum Inner<B: Block, State, T: Message> {
+    Tcp(Node<B, Tcp, State, T>),
+    Udp(Node<B, Udp, State, T>),
+    #[cfg(feature = "quic")]
+    Quic(Node<B, Quic, State, T>),
+}
+
+impl<B: Block, State, T: Message> Inner<B, State, T> {
+    fn transport(&self) -> Transport {
+        match self {
+            Inner::Tcp(_) => Transport::Tcp,
+            Inner::Udp(_) => Transport::Udp,
+            #[cfg(feature = "quic")]
+            Inner::Quic(_) => Transport::Quic,
+        }
+    }
+
+    fn host_liveness(&self) -> Liveness {
+        match self {
+            Inner::Tcp(node) => node.host_liveness(),
+            Inner::Udp(node) => node.host_liveness(),
+            #[cfg(feature = "quic")]
+            Inner::Quic(node) => node.host_liveness(),
+        }
+    }
+}
+
+/// Strongly-typed Node whose transport is chosen at runtime from an ordered list
+#[derive(Debug)]
+pub struct DynamicNode<B: Block, State, T: Message> {
+    __state: PhantomData<State>,
+    /// Set once the Node is activated or subscribed
+    inner: Option<Inner<B, State, T>>,
+    cfg: DynamicNodeConfig<B, T>,
+    rt_handle: Handle,
+    // Declared last, so the runtime is dropped after the Node running on it
+    runtime: Option<Runtime>,
+}
+
+impl<B: Block, State, T: Message> DynamicNode<B, State, T> {
+    /// Get `Node`'s configuration
+    pub fn config(&self) -> &DynamicNodeConfig<B, T> {
+        &self.cfg
+    }
+
+    /// Get `Node`'s topic
+    pub fn topic(&self) -> String {
+        self.cfg.topic.clone()
+    }
+
+    /// Get reference to the `Node`'s Tokio runtime if one exists
+    pub fn runtime(&self) -> &Option<Runtime> {
+        &self.runtime
+    }
+
+    fn into_state<S>(self, inner: Inner<B, S, T>) -> DynamicNode<B, S, T> {
+        DynamicNode {
+            __state: PhantomData,
+            inner: Some(inner),
+            cfg: self.cfg,
+            rt_handle: self.rt_handle,
+            runtime: self.runtime,
+        }
+    }
+
+    fn selected(&self) -> Transport {
+        match &self.inner {
+            Some(inner) => inner.transport(),
+            None => unreachable!("only Idle DynamicNodes have no transport"),
+        }
+    }
+}
+
+impl<B: Block, T: Message> DynamicNode<B, Active, T> {
+    /// Transport the Node connected over
+    pub fn transport(&self) -> Transport {
+        self.selected()
+    }
+
+    /// How recently the Node heard from its Host, see [`Node::host_liveness`]
+    pub fn host_liveness(&self) -> Liveness {
+        match &self.inner {
+            Some(inner) => inner.host_liveness(),
+            None => unreachable!("only Idle DynamicNodes have no transport"),
+        }
+    }
+}
+
+impl<B: Block, T: Message> DynamicNode<B, Subscription, T> {
+    /// Transport the Node subscribed over
+    pub fn transport(&self) -> Transport {
+        self.selected()
+    }
+
+    /// How recently the Node heard from its Host, see [`Node::host_liveness`]
+    pub fn host_liveness(&self) -> Liveness {
+        match &self.inner {
+            Some(inner) => inner.host_liveness(),
+            None => unreachable!("only Idle DynamicNodes have no transport"),
+        }
+    }
+}
+
+/// Run `$call` on the Node of whichever transport was selected
+macro_rules! dispatch {
+    ($self:ident, $node:ident => $call:expr) => {
+        match &$self.inner {
+            Some(Inner::Tcp($node)) => $call,
+            Some(Inner::Udp($node)) => $call,
+            #[cfg(feature = "quic")]
+            Some(Inner::Quic($node)) => $call,
+            None => Err(Error::AccessStream),
+        }
+    };
+}
+
+impl<T: Message + 'static> DynamicNode<Blocking, Idle, T> {
+    /// Connect to the Host over the first of the configured transports that reaches it
+    #[tracing::instrument(skip_all)]
+    pub fn activate(self) -> Result<DynamicNode<Blocking, Active, T>, Error> {
+        let inner = self.first_reachable()?;
+        Ok(self.into_state(inner))
+    }
+
+    /// Subscribe to the Node's topic over the first of the configured transports that reaches the Host
+    #[tracing::instrument(skip_all)]
+    pub fn subscribe(
+        self,
+        rate: Duration,
+    ) -> Result<DynamicNode<Blocking, Subscription, T>, Error> {
+        // Subscriptions connect in the background, so check the transport reaches the Host first
+        let transport = self.first_reachable()?.transport();
+        let cfg = &self.cfg;
+        let inner = match transport {
+            Transport::Tcp => Inner::Tcp(
+                cfg.node_config(cfg.tcp_cfg.clone(), &self.rt_handle)
+                    .build()?
+                    .subscribe(rate)?,
+            ),
+            Transport::Udp => Inner::Udp(
+                cfg.node_config(cfg.udp_cfg.clone(), &self.rt_handle)
+                    .build()?
+                    .subscribe(rate)?,
+            ),
+            #[cfg(feature = "quic")]
+            Transport::Quic => Inner::Quic(
+                cfg.node_config(cfg.quic_cfg.clone(), &self.rt_handle)
+                    .build()?
+                    .subscribe(rate)?,
+            ),
+            // The local transports, and QUIC without its feature
+            _ => return Err(Error::UnsupportedTransport(transport)),
+        };
+        Ok(self.into_state(inner))
+    }
+
+    fn first_reachable(&self) -> Result<Inner<Blocking, Active, T>, Error> {
+        let mut failures = Vec::new();
+        for &transport in &self.cfg.transports {
+            match self.activate_over(transport) {
+                Ok(inner) => {
+                    info!("{}: Connected over {}", self.cfg.topic, transport);
+                    return Ok(inner);
+                }
+                Err(e) => {
+                    warn!(
+                        "{}: Couldn't connect over {}: {}",
+                        self.cfg.topic, transport, e
+                    );
+                    failures.push((transport, e));
+                }
+            }
+        }
+        Err(Error::NoTransport(failures))
+    }
+
+    fn activate_over(&self, transport: Transport) -> Result<Inner<Blocking, Active, T>, Error> {
+        let cfg = &self.cfg;
+        match transport {
+            Transport::Tcp => {
+                let node = cfg
+                    .node_config(cfg.tcp_cfg.clone(), &self.rt_handle)
+                    .build()?
+                    .activate()?;
+                // TCP Nodes activate even when the Host can't be reached
+                match node.stream {
+                    Some(_) => Ok(Inner::Tcp(node)),
+                    None => Err(Error::StreamConnection),
+                }
+            }
+            Transport::Udp => Ok(Inner::Udp(
+                cfg.node_config(cfg.udp_cfg.clone(), &self.rt_handle)
+                    .build()?
+                    .activate()?,
+            )),
+            #[cfg(feature = "quic")]
+            Transport::Quic => Ok(Inner::Quic(
+                cfg.node_config(cfg.quic_cfg.clone(), &self.rt_handle)
+                    .build()?
+                    .activate()?,
+            )),
+            // The local transports, and QUIC without its feature
+            _ => Err(Error::UnsupportedTransport(transport)),
+        }
+    }
+}
+
+impl<T: Message + 'static> DynamicNode<Nonblocking, Idle, T> {
+    /// Connect to the Host over the first of the configured transports that reaches it
+    #[tracing::instrument(skip_all)]
+    pub async fn activate(self) -> Result<DynamicNode<Nonblocking, Active, T>, Error> {
+        let inner = self.first_reachable().await?;
+        Ok(self.into_state(inner))
+    }
+
+    /// Subscribe to the Node's topic over the first of the configured transports that reaches the Host
+    #[tracing::instrument(skip_all)]
+    pub async fn subscribe(
+        self,
+        rate: Duration,
+    ) -> Result<DynamicNode<Nonblocking, Subscription, T>, Error> {
+        // Subscriptions connect in the background, so check the transport reaches the Host first
+        let transport = self.first_reachable().await?.transport();
+        let cfg = &self.cfg;
+        let inner = match transport {
+            Transport::Tcp => Inner::Tcp(
+                cfg.node_config(cfg.tcp_cfg.clone(), &self.rt_handle)
+                    .build()?
+                    .subscribe(rate)
+                    .await?,
+            ),
+            Transport::Udp => Inner::Udp(
+                cfg.node_config(cfg.udp_cfg.clone(), &self.rt_handle)
+                    .build()?
+                    .subscribe(rate)
+                    .await?,
+            ),
+            #[cfg(feature = "quic")]
+            Transport::Quic => Inner::Quic(
+                cfg.node_config(cfg.quic_cfg.clone(), &self.rt_handle)
+                    .build()?
+                    .subscribe(rate)
+                    .await?,
+            ),
+            // The local transports, and QUIC without its feature
+            _ => return Err(Error::UnsupportedTransport(transport)),
+        };
+        Ok(self.into_state(inner))
+    }
+
+    async fn first_reachable(&self) -> Result<Inner<Nonblocking, Active, T>, Error> {
+        let mut failures = Vec::new();
+        for &transport in &self.cfg.transports {
+            match self.activate_over(transport).await {
+                Ok(inner) => {
+                    info!("{}: Connected over {}", self.cfg.topic, transport);
+                    return Ok(inner);
+                }
+                Err(e) => {
+                    warn!(
+                        "{}: Couldn't connect over {}: {}",
+                        self.cfg.topic, transport, e
+                    );
+                    failures.push((transport, e));
+                }
+            }
+        }
+        Err(Error::NoTransport(failures))
+    }
+
+    async fn activate_over(
+        &self,
+        transport: Transport,
+    ) -> Result<Inner<Nonblocking, Active, T>, Error> {
+        let cfg = &self.cfg;
+        match transport {
+            Transport::Tcp => {
+                let node = cfg
+                    .node_config(cfg.tcp_cfg.clone(), &self.rt_handle)
+                    .build()?
+                    .activate()
+                    .await?;
+                // TCP Nodes activate even when the Host can't be reached
+                match node.stream {
+                    Some(_) => Ok(Inner::Tcp(node)),
+                    None => Err(Error::StreamConnection),
+                }
+            }
+            Transport::Udp => Ok(Inner::Udp(
+                cfg.node_config(cfg.udp_cfg.clone(), &self.rt_handle)
+                    .build()?
+                    .activate()
+                    .await?,
+            )),
+            #[cfg(feature = "quic")]
+            Transport::Quic => Ok(Inner::Quic(
+                cfg.node_config(cfg.quic_cfg.clone(), &self.rt_handle)
+                    .build()?
+                    .activate()
+                    .await?,
+            )),
+            // The local transports, and QUIC without its feature
+            _ => Err(Error::UnsupportedTransport(transport)),
+        }
+    }
+}
+
+impl<T: Message + 'static> DynamicNode<Blocking, Active, T> {
+    /// Send data to host on Node's assigned topic using `Msg<T>` packet
+    pub fn publish(&self, val: T) -> Result<(), Error> {
+        dispatch!(self, node => node.publish(val))
+    }
+
+    pub fn publish_msg(&self, msg: Msg<T>) -> Result<(), Error> {
+        dispatch!(self, node => node.publish_msg(msg))
+    }
+
+    /// Request data from host on Node's assigned topic
+    pub fn request(&self) -> Result<Msg<T>, Error> {
+        dispatch!(self, node => node.request())
+    }
+
+    /// Request n'th data from host on Node's assigned topic
+    pub fn request_nth_back(&self, n: usize) -> Result<Msg<T>, Error> {
+        dispatch!(self, node => node.request_nth_back(n))
+    }
+
+    pub fn topics(&self) -> Result<Msg<Vec<String>>, Error> {
+        dispatch!(self, node => node.topics())
+    }
+}
+
+impl<T: Message + 'static> DynamicNode<Nonblocking, Active, T> {
+    /// Send data to host on Node's assigned topic using `Msg<T>` packet
+    pub async fn publish(&self, val: T) -> Result<(), Error> {
+        dispatch!(self, node => node.publish(val).await)
+    }
+
+    pub async fn publish_msg(&self, msg: Msg<T>) -> Result<(), Error> {
+        dispatch!(self, node => node.publish_msg(msg).await)
+    }
+
+    /// Request data from host on Node's assigned topic
+    pub async fn request(&self) -> Result<Msg<T>, Error> {
+        dispatch!(self, node => node.request().await)
+    }
+
+    /// Request n'th data from host on Node's assigned topic
+    pub async fn request_nth_back(&self, n: usize) -> Result<Msg<T>, Error> {
+        dispatch!(self, node => node.request_nth_back(n).await)
+    }
+
+    pub async fn topics(&self) -> Result<Msg<Vec<String>>, Error> {
+        dispatch!(self, node => node.topics().await)
+    }
+}
+
+impl<T: Message + 'static> DynamicNode<Blocking, Subscription, T> {
+    pub fn get_subscribed_data(&self) -> Result<Msg<T>, Error> {
+        dispatch!(self, node => node.get_subscribed_data())
+    }
+}
+
+impl<T: Message + 'static> DynamicNode<Nonblocking, Subscription, T> {
+    pub async fn get_subscribed_data(&self) -> Result<Msg<T>, Error> {
+        dispatch!(self, node => node.get_subscribed_data().await)
+    }
+}
+
+impl<T: Message + 'static> Publisher<T> for DynamicNode<Blocking, Active, T> {
+    fn publish(&self, val: T) -> Result<(), Error> {
+        self.publish(val)
+    }
+
+    fn publish_msg(&self, msg: Msg<T>) -> Result<(), Error> {
+        self.publish_msg(msg)
+    }
+}
+
+impl<T: Message + 'static> Requester<T> for DynamicNode<Blocking, Active, T> {
+    fn request(&self) -> Result<Msg<T>, Error> {
+        self.request()
+    }
+
+    fn request_nth_back(&self, n: usize) -> Result<Msg<T>, Error> {
+        self.request_nth_back(n)
+    }
+
+    fn topics(&self) -> Result<Msg<Vec<String>>, Error> {
+        self.topics()
+    }
+}
+
+impl<T: Message + 'static> Subscriber<T> for DynamicNode<Blocking, Subscription, T> {
+    fn get_subscribed_data(&self) -> Result<Msg<T>, Error> {
+        self.get_subscribed_data()
+    }
+}
+
+impl<T: Message + 'static> AsyncPublisher<T> for DynamicNode<Nonblocking, Active, T> {
+    async fn publish(&self, val: T) -> Result<(), Error> {
+        self.publish(val).await
+    }
+
+    async fn publish_msg(&self, msg: Msg<T>) -> Result<(), Error> {
+        self.publish_msg(msg).await
+    }
+}
+
+impl<T: Message + 'static> AsyncRequester<T> for DynamicNode<Nonblocking, Active, T> {
+    async fn request(&self) -> Result<Msg<T>, Error> {
+        self.request().await
+    }
+
+    async fn request_nth_back(&self, n: usize) -> Result<Msg<T>, Error> {
+        self.request_nth_back(n).await
+    }
+
+    async fn topics(&self) -> Result<Msg<Vec<String>>, Error> {
+        self.topics().await
+    }
+}
+
+impl<T: Message + 'static> AsyncSubscriber<T> for DynamicNode<Nonblocking, Subscription, T> {
+    async fn get_subscribed_data(&self) -> Result<Msg<T>, Error> {
+        self.get_subscribed_data().await
+    }
+}
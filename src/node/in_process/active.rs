@@ -0,0 +1,238 @@
+use crate::node::in_process::InProcessChannel;
+use crate::node::network_config::{Blocking, InProcess, Nonblocking};
+use crate::node::{Active, Block, Node};
+use crate::prelude::*;
+
+use std::convert::TryInto;
+use std::fmt::Debug;
+
+#[cfg(feature = "zerocopy")]
+use crate::raw::{MsgView, Raw, RawMessage};
+use std::result::Result;
+
+use tracing::*;
+
+impl<T: Message + 'static, B: Block + Debug> Node<B, InProcess, Active, T> {
+    fn channel(&self) -> Result<&InProcessChannel, Error> {
+        match self.in_process.as_ref() {
+            Some(channel) => Ok(channel),
+            None => Err(Error::AccessStream),
+        }
+    }
+
+    #[tracing::instrument]
+    #[inline]
+    async fn publish_internal(&self, val: T) -> Result<(), Error> {
+        let msg = Msg::new(MsgType::Set, self.topic.clone(), val);
+        self.publish_msg_internal(msg).await
+    }
+
+    #[tracing::instrument]
+    #[inline]
+    async fn publish_msg_internal(&self, msg: Msg<T>) -> Result<(), Error> {
+        let channel = self.channel()?;
+
+        // Send the publish message and wait for the acknowledgement
+        let ack = channel
+            .exchange(
+                msg.to_generic_encoded(self.cfg.encoding)?
+                    .compressed(self.cfg.compression),
+            )
+            .await?;
+        if let MsgType::Result(result) = ack.msg_type {
+            result?;
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument]
+    #[inline]
+    async fn request_nth_back_internal(&self, n: usize) -> Result<Msg<T>, Error> {
+        let channel = self.channel()?;
+
+        let packet = GenericMsg::get_nth::<T>(self.topic.clone(), n);
+        let msg = channel.exchange(packet).await?.try_into()?;
+        Ok(msg)
+    }
+
+    #[tracing::instrument]
+    #[inline]
+    async fn topics_internal(&self) -> Result<Msg<Vec<String>>, Error> {
+        let channel = self.channel()?;
+
+        let msg = channel.exchange(GenericMsg::topics()).await?.try_into()?;
+        Ok(msg)
+    }
+}
+impl<T: Message + 'static> Node<Nonblocking, InProcess, Active, T> {
+    /// Send data to host on Node's assigned topic using `Msg<T>` packet
+    #[tracing::instrument]
+    #[inline]
+    pub async fn publish(&self, val: T) -> Result<(), Error> {
+        self.publish_internal(val).await?;
+        Ok(())
+    }
+
+    #[tracing::instrument]
+    #[inline]
+    pub async fn publish_msg(&self, msg: Msg<T>) -> Result<(), Error> {
+        self.publish_msg_internal(msg).await?;
+        Ok(())
+    }
+
+    /// Request data from host on Node's assigned topic
+    #[tracing::instrument]
+    #[inline]
+    pub async fn request(&self) -> Result<Msg<T>, Error> {
+        let msg = self.request_nth_back_internal(0).await?;
+        Ok(msg)
+    }
+
+    /// Request n'th data from host on Node's assigned topic
+    #[tracing::instrument]
+    #[inline]
+    pub async fn request_nth_back(&self, n: usize) -> Result<Msg<T>, Error> {
+        let msg = self.request_nth_back_internal(n).await?;
+        Ok(msg)
+    }
+
+    #[tracing::instrument]
+    #[inline]
+    pub async fn topics(&self) -> Result<Msg<Vec<String>>, Error> {
+        let msg = self.topics_internal().await?;
+        Ok(msg)
+    }
+}
+
+impl<T: Message + 'static> Node<Blocking, InProcess, Active, T> {
+    /// Send data to host on Node's assigned topic using `Msg<T>` packet
+    #[tracing::instrument(skip_all)]
+    #[inline]
+    pub fn publish(&self, val: T) -> Result<(), Error> {
+        match &self.rt_handle {
+            Some(handle) => handle.block_on(async {
+                self.publish_internal(val).await?;
+                Ok(())
+            }),
+            None => Err(Error::HandleAccess),
+        }
+    }
+
+    #[tracing::instrument]
+    #[inline]
+    pub fn publish_msg(&self, msg: Msg<T>) -> Result<(), Error> {
+        match &self.rt_handle {
+            Some(handle) => handle.block_on(async {
+                self.publish_msg_internal(msg).await?;
+                Ok(())
+            }),
+            None => Err(Error::HandleAccess),
+        }
+    }
+
+    /// Request data from host on Node's assigned topic
+    #[tracing::instrument]
+    #[inline]
+    pub fn request(&self) -> Result<Msg<T>, Error> {
+        match &self.rt_handle {
+            Some(handle) => handle.block_on(async {
+                let msg = self.request_nth_back_internal(0).await?;
+                Ok(msg)
+            }),
+            None => Err(Error::HandleAccess),
+        }
+    }
+
+    /// Request n'th data from host on Node's assigned topic
+    #[tracing::instrument]
+    #[inline]
+    pub fn request_nth_back(&self, n: usize) -> Result<Msg<T>, Error> {
+        match &self.rt_handle {
+            Some(handle) => handle.block_on(async {
+                let msg = self.request_nth_back_internal(n).await?;
+                Ok(msg)
+            }),
+            None => Err(Error::HandleAccess),
+        }
+    }
+
+    #[tracing::instrument]
+    #[inline]
+    pub fn topics(&self) -> Result<Msg<Vec<String>>, Error> {
+        match &self.rt_handle {
+            Some(handle) => handle.block_on(async {
+                let msg = self.topics_internal().await?;
+                Ok(msg)
+            }),
+            None => Err(Error::HandleAccess),
+        }
+    }
+}
+
+#[cfg(feature = "zerocopy")]
+impl<T: RawMessage, B: Block + Debug> Node<B, InProcess, Active, Raw<T>> {
+    #[tracing::instrument(skip(f))]
+    #[inline]
+    async fn request_view_internal<R>(
+        &self,
+        n: usize,
+        f: impl FnOnce(MsgView<'_, T>) -> R,
+    ) -> Result<R, Error> {
+        let channel = self.channel()?;
+
+        // Messages arrive without being serialized, so the view borrows the data directly
+        let packet = GenericMsg::get_nth::<Raw<T>>(self.topic.clone(), n);
+        let msg = channel.exchange(packet).await?;
+        Ok(f(MsgView::from_generic(&msg)?))
+    }
+}
+
+#[cfg(feature = "zerocopy")]
+impl<T: RawMessage> Node<Nonblocking, InProcess, Active, Raw<T>> {
+    /// Request data from host on Node's assigned topic, passing `f` a view of it read in place
+    /// from the received bytes rather than deserialized
+    #[tracing::instrument(skip(f))]
+    #[inline]
+    pub async fn request_view<R>(&self, f: impl FnOnce(MsgView<'_, T>) -> R) -> Result<R, Error> {
+        self.request_view_internal(0, f).await
+    }
+
+    /// Request n'th data from host on Node's assigned topic, passing `f` a view of it
+    #[tracing::instrument(skip(f))]
+    #[inline]
+    pub async fn request_nth_back_view<R>(
+        &self,
+        n: usize,
+        f: impl FnOnce(MsgView<'_, T>) -> R,
+    ) -> Result<R, Error> {
+        self.request_view_internal(n, f).await
+    }
+}
+
+#[cfg(feature = "zerocopy")]
+impl<T: RawMessage> Node<Blocking, InProcess, Active, Raw<T>> {
+    /// Request data from host on Node's assigned topic, passing `f` a view of it read in place
+    /// from the received bytes rather than deserialized
+    #[tracing::instrument(skip(f))]
+    #[inline]
+    pub fn request_view<R>(&self, f: impl FnOnce(MsgView<'_, T>) -> R) -> Result<R, Error> {
+        match &self.rt_handle {
+            Some(handle) => handle.block_on(self.request_view_internal(0, f)),
+            None => Err(Error::HandleAccess),
+        }
+    }
+
+    /// Request n'th data from host on Node's assigned topic, passing `f` a view of it
+    #[tracing::instrument(skip(f))]
+    #[inline]
+    pub fn request_nth_back_view<R>(
+        &self,
+        n: usize,
+        f: impl FnOnce(MsgView<'_, T>) -> R,
+    ) -> Result<R, Error> {
+        match &self.rt_handle {
+            Some(handle) => handle.block_on(self.request_view_internal(n, f)),
+            None => Err(Error::HandleAccess),
+        }
+    }
+}
@@ -0,0 +1,270 @@
+use crate::prelude::*;
+
+use crate::host::HostHandle;
+use crate::node::in_process::InProcessChannel;
+use crate::node::network_config::{Blocking, InProcess, Nonblocking};
+use crate::node::*;
+
+use tokio::sync::Mutex as TokioMutex;
+use tokio::time::Duration;
+
+use tracing::*;
+
+use std::convert::TryInto;
+use std::ops::DerefMut;
+use std::result::Result;
+use std::sync::Arc;
+
+use std::marker::PhantomData;
+
+impl<T: Message> From<Node<Nonblocking, InProcess, Idle, T>>
+    for Node<Nonblocking, InProcess, Active, T>
+{
+    fn from(node: Node<Nonblocking, InProcess, Idle, T>) -> Self {
+        Self {
+            __state: PhantomData,
+            __data_type: PhantomData,
+            cfg: node.cfg,
+            runtime: node.runtime,
+            rt_handle: node.rt_handle,
+            stream: node.stream,
+            #[cfg(unix)]
+            uds_stream: node.uds_stream,
+            #[cfg(all(unix, feature = "shm"))]
+            shm: node.shm,
+            topic: node.topic,
+            socket: node.socket,
+            udp_cipher: node.udp_cipher,
+            in_process: node.in_process,
+            buffer: node.buffer,
+            #[cfg(feature = "quic")]
+            endpoint: node.endpoint,
+            #[cfg(feature = "quic")]
+            connection: node.connection,
+            subscription_data: node.subscription_data,
+            host_pulse: node.host_pulse,
+            task_subscribe: None,
+            task_heartbeat: None,
+        }
+    }
+}
+
+impl<T: Message> From<Node<Nonblocking, InProcess, Idle, T>>
+    for Node<Nonblocking, InProcess, Subscription, T>
+{
+    fn from(node: Node<Nonblocking, InProcess, Idle, T>) -> Self {
+        Self {
+            __state: PhantomData,
+            __data_type: PhantomData,
+            cfg: node.cfg,
+            runtime: node.runtime,
+            rt_handle: node.rt_handle,
+            stream: node.stream,
+            #[cfg(unix)]
+            uds_stream: node.uds_stream,
+            #[cfg(all(unix, feature = "shm"))]
+            shm: node.shm,
+            topic: node.topic,
+            socket: node.socket,
+            udp_cipher: node.udp_cipher,
+            in_process: node.in_process,
+            buffer: node.buffer,
+            #[cfg(feature = "quic")]
+            endpoint: node.endpoint,
+            #[cfg(feature = "quic")]
+            connection: node.connection,
+            subscription_data: node.subscription_data,
+            host_pulse: node.host_pulse,
+            task_subscribe: None,
+            task_heartbeat: None,
+        }
+    }
+}
+
+/// Look up the Host handle from the Node's network configuration
+fn host_handle<B: Block>(cfg: &NetworkConfig<B, InProcess>) -> Result<HostHandle, Error> {
+    match &cfg.host_handle {
+        Some(host_handle) => Ok(host_handle.clone()),
+        None => Err(Error::NoHostHandle),
+    }
+}
+
+impl<T: Message + 'static> Node<Nonblocking, InProcess, Idle, T> {
+    /// Connect the Node to the Host behind the configured handle
+    #[tracing::instrument(skip_all)]
+    pub async fn activate(mut self) -> Result<Node<Nonblocking, InProcess, Active, T>, Error> {
+        let host_handle = host_handle(&self.cfg.network_cfg)?;
+        self.in_process = Some(host_handle.connect(self.handshake())?);
+        debug!("Established Node<=>Host in-process channel");
+
+        Ok(Node::<Nonblocking, InProcess, Active, T>::from(self))
+    }
+
+    #[tracing::instrument]
+    pub async fn subscribe(
+        mut self,
+        rate: Duration,
+    ) -> Result<Node<Nonblocking, InProcess, Subscription, T>, Error> {
+        let host_handle = host_handle(&self.cfg.network_cfg)?;
+        let topic = self.topic.clone();
+        let channel = host_handle.connect(self.handshake())?;
+
+        let subscription_data: Arc<TokioMutex<Option<Msg<T>>>> = Arc::new(TokioMutex::new(None));
+        let data = Arc::clone(&subscription_data);
+
+        let packet = GenericMsg::subscribe(&topic, rate)?;
+
+        let task_subscribe = tokio::spawn(async move {
+            if let Err(e) = run_subscription::<T>(packet, &channel, data.clone()).await {
+                error!("{:?}", e);
+            }
+        });
+        self.task_subscribe = Some(task_subscribe);
+
+        let mut subscription_node = Node::<Nonblocking, InProcess, Subscription, T>::from(self);
+        subscription_node.subscription_data = subscription_data;
+
+        Ok(subscription_node)
+    }
+}
+
+async fn run_subscription<T: Message>(
+    packet: GenericMsg,
+    channel: &InProcessChannel,
+    data: Arc<TokioMutex<Option<Msg<T>>>>,
+) -> Result<(), Error> {
+    channel.send_msg(packet)?;
+
+    loop {
+        // The channel closing means the Host has gone away
+        let msg = channel.await_response().await?;
+        match TryInto::<Msg<T>>::try_into(msg) {
+            Ok(msg) => {
+                let mut data = data.lock().await;
+                match data.deref_mut() {
+                    Some(existing) => {
+                        let delta = msg.timestamp - existing.timestamp;
+                        if delta <= chrono::Duration::zero() {
+                            continue;
+                        }
+                        *data = Some(msg);
+                    }
+                    None => {
+                        *data = Some(msg);
+                    }
+                }
+            }
+            Err(e) => {
+                error!("{}", e);
+            }
+        }
+    }
+}
+
+//------
+
+impl<T: Message> From<Node<Blocking, InProcess, Idle, T>> for Node<Blocking, InProcess, Active, T> {
+    fn from(node: Node<Blocking, InProcess, Idle, T>) -> Self {
+        Self {
+            __state: PhantomData,
+            __data_type: PhantomData,
+            cfg: node.cfg,
+            runtime: node.runtime,
+            rt_handle: node.rt_handle,
+            stream: node.stream,
+            #[cfg(unix)]
+            uds_stream: node.uds_stream,
+            #[cfg(all(unix, feature = "shm"))]
+            shm: node.shm,
+            topic: node.topic,
+            socket: node.socket,
+            udp_cipher: node.udp_cipher,
+            in_process: node.in_process,
+            buffer: node.buffer,
+            #[cfg(feature = "quic")]
+            endpoint: node.endpoint,
+            #[cfg(feature = "quic")]
+            connection: node.connection,
+            subscription_data: node.subscription_data,
+            host_pulse: node.host_pulse,
+            task_subscribe: None,
+            task_heartbeat: None,
+        }
+    }
+}
+
+impl<T: Message> From<Node<Blocking, InProcess, Idle, T>>
+    for Node<Blocking, InProcess, Subscription, T>
+{
+    fn from(node: Node<Blocking, InProcess, Idle, T>) -> Self {
+        Self {
+            __state: PhantomData,
+            __data_type: PhantomData,
+            cfg: node.cfg,
+            runtime: node.runtime,
+            rt_handle: node.rt_handle,
+            stream: node.stream,
+            #[cfg(unix)]
+            uds_stream: node.uds_stream,
+            #[cfg(all(unix, feature = "shm"))]
+            shm: node.shm,
+            topic: node.topic,
+            socket: node.socket,
+            udp_cipher: node.udp_cipher,
+            in_process: node.in_process,
+            buffer: node.buffer,
+            #[cfg(feature = "quic")]
+            endpoint: node.endpoint,
+            #[cfg(feature = "quic")]
+            connection: node.connection,
+            subscription_data: node.subscription_data,
+            host_pulse: node.host_pulse,
+            task_subscribe: None,
+            task_heartbeat: None,
+        }
+    }
+}
+
+impl<T: Message + 'static> Node<Blocking, InProcess, Idle, T> {
+    /// Connect the Node to the Host behind the configured handle
+    #[tracing::instrument(skip_all)]
+    pub fn activate(mut self) -> Result<Node<Blocking, InProcess, Active, T>, Error> {
+        let host_handle = host_handle(&self.cfg.network_cfg)?;
+        self.in_process = Some(host_handle.connect(self.handshake())?);
+        debug!("Established Node<=>Host in-process channel");
+
+        Ok(Node::<Blocking, InProcess, Active, T>::from(self))
+    }
+
+    #[tracing::instrument]
+    pub fn subscribe(
+        mut self,
+        rate: Duration,
+    ) -> Result<Node<Blocking, InProcess, Subscription, T>, Error> {
+        let host_handle = host_handle(&self.cfg.network_cfg)?;
+        let topic = self.topic.clone();
+        let channel = host_handle.connect(self.handshake())?;
+
+        let subscription_data: Arc<TokioMutex<Option<Msg<T>>>> = Arc::new(TokioMutex::new(None));
+        let data = Arc::clone(&subscription_data);
+
+        let packet = GenericMsg::subscribe(&topic, rate)?;
+
+        let handle = match &self.rt_handle {
+            Some(handle) => handle,
+            None => return Err(Error::HandleAccess),
+        };
+
+        let task_subscribe = handle.spawn(async move {
+            if let Err(e) = run_subscription::<T>(packet, &channel, data.clone()).await {
+                error!("{:?}", e);
+            }
+        });
+        self.task_subscribe = Some(task_subscribe);
+
+        let mut subscription_node = Node::<Blocking, InProcess, Subscription, T>::from(self);
+        subscription_node.subscription_data = subscription_data;
+
+        Ok(subscription_node)
+    }
+}
@@ -0,0 +1,53 @@
+mod active;
+mod idle;
+mod subscription;
+
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+use tokio::sync::Mutex as TokioMutex;
+
+use std::result::Result;
+
+use crate::msg::GenericMsg;
+use crate::Error;
+
+/// Node-side half of an in-process connection to a `Host`
+#[derive(Debug)]
+pub struct InProcessChannel {
+    pub(crate) tx: UnboundedSender<GenericMsg>,
+    pub(crate) rx: TokioMutex<UnboundedReceiver<GenericMsg>>,
+}
+
+impl InProcessChannel {
+    /// Hand a `GenericMsg` to the Host
+    #[inline]
+    pub fn send_msg(&self, msg: GenericMsg) -> Result<(), Error> {
+        match self.tx.send(msg) {
+            Ok(()) => Ok(()),
+            // The Host has stopped, dropping its end of the channel
+            Err(_e) => Err(Error::AccessStream),
+        }
+    }
+
+    /// Wait for the next `GenericMsg` from the Host
+    #[inline]
+    pub async fn await_response(&self) -> Result<GenericMsg, Error> {
+        match self.rx.lock().await.recv().await {
+            Some(msg) => Ok(msg),
+            None => Err(Error::AccessStream),
+        }
+    }
+
+    /// Hand a `GenericMsg` to the Host and wait for its reply
+    ///
+    /// The response channel stays locked in between, so concurrent requests
+    /// on the same Node can't receive each other's replies.
+    #[inline]
+    pub async fn exchange(&self, msg: GenericMsg) -> Result<GenericMsg, Error> {
+        let mut rx = self.rx.lock().await;
+        self.send_msg(msg)?;
+        match rx.recv().await {
+            Some(msg) => Ok(msg),
+            None => Err(Error::AccessStream),
+        }
+    }
+}
@@ -1,7 +1,14 @@
 pub mod config;
+pub mod dynamic;
+pub mod in_process;
 pub mod network_config;
+#[cfg(all(unix, feature = "shm"))]
+pub mod shm;
 pub mod tcp;
+pub mod traits;
 pub mod udp;
+#[cfg(unix)]
+pub mod uds;
 
 #[cfg(feature = "quic")]
 pub mod quic;
@@ -19,11 +26,16 @@ pub struct Subscription;
 mod private {
     pub trait Sealed {}
 
-    use crate::node::network_config::{Tcp, Udp};
+    use crate::node::network_config::{InProcess, Tcp, Udp};
     impl Sealed for Udp {}
+    impl Sealed for InProcess {}
     impl Sealed for Tcp {}
     #[cfg(feature = "quic")]
     impl Sealed for crate::node::network_config::Quic {}
+    #[cfg(unix)]
+    impl Sealed for crate::node::network_config::Uds {}
+    #[cfg(all(unix, feature = "shm"))]
+    impl Sealed for crate::node::network_config::Shm {}
 
     use crate::node::{Active, Idle};
     impl Sealed for Idle {}
@@ -35,6 +47,8 @@ mod private {
 }
 
 use tokio::io::AsyncWriteExt;
+#[cfg(unix)]
+use tokio::net::UnixStream;
 use tokio::net::{TcpStream, UdpSocket};
 use tokio::runtime::{Handle, Runtime};
 use tokio::sync::Mutex as TokioMutex;
@@ -53,7 +67,11 @@ extern crate alloc;
 use alloc::vec::Vec;
 use postcard::*;
 
+use crate::discovery::{discover, Transport};
+use crate::handshake::{Feature, Handshake};
+use crate::heartbeat::{HeartbeatTask, Liveness, Pulse};
 use crate::msg::*;
+use crate::node::network_config::Blocking;
 use crate::node::network_config::{Block, Interface};
 use crate::Error;
 use chrono::{DateTime, Utc};
@@ -82,15 +100,24 @@ pub struct Node<B: Block, I: Interface + Default, State, T: Message> {
     pub(crate) runtime: Option<Runtime>,
     pub(crate) rt_handle: Option<Handle>,
     pub(crate) topic: String,
-    pub(crate) stream: Option<TcpStream>,
-    pub(crate) socket: Option<UdpSocket>,
+    pub(crate) stream: Option<Arc<crate::node::tcp::TcpConnection>>,
+    #[cfg(unix)]
+    pub(crate) uds_stream: Option<UnixStream>,
+    #[cfg(all(unix, feature = "shm"))]
+    pub(crate) shm: Option<crate::node::shm::ShmChannel>,
+    pub(crate) socket: Option<Arc<UdpSocket>>,
+    pub(crate) udp_cipher: Option<Arc<crate::psk::DatagramCipher>>,
+    pub(crate) in_process: Option<crate::node::in_process::InProcessChannel>,
     pub(crate) buffer: Arc<TokioMutex<Vec<u8>>>,
     #[cfg(feature = "quic")]
     pub(crate) endpoint: Option<Endpoint>,
     #[cfg(feature = "quic")]
     pub(crate) connection: Option<QuicConnection>,
     pub(crate) subscription_data: Arc<TokioMutex<Option<Msg<T>>>>,
+    /// When the Node last heard from its Host
+    pub(crate) host_pulse: Arc<Pulse>,
     pub(crate) task_subscribe: Option<JoinHandle<()>>,
+    pub(crate) task_heartbeat: Option<HeartbeatTask>,
 }
 
 impl<B: Block, I: Interface + Default, State, T: Message> Node<B, I, State, T> {
@@ -113,4 +140,62 @@ impl<B: Block, I: Interface + Default, State, T: Message> Node<B, I, State, T> {
     pub fn topic(&self) -> String {
         self.topic.clone()
     }
+
+    /// How recently the Node heard from its Host.
+    ///
+    /// Nodes only track this over TCP and UDP with heartbeats enabled, where the Host is `Late`
+    /// once it misses answering a heartbeat and `Dead` after the timeout. QUIC Nodes report the
+    /// Host `Dead` once QUIC's idle timeout closes the connection, and otherwise the Host is
+    /// assumed `Alive`.
+    pub fn host_liveness(&self) -> Liveness {
+        self.host_pulse.liveness()
+    }
+
+    /// Handshake identifying the Node to the Host, listing the features its configuration enables
+    pub(crate) fn handshake(&self) -> Handshake {
+        let network_cfg = &self.cfg.network_cfg;
+        let mut features = Vec::new();
+        if network_cfg.cert_path.is_some() || network_cfg.psk.is_some() {
+            features.push(Feature::Encryption);
+        }
+        if network_cfg.client_cert_path.is_some() {
+            features.push(Feature::ClientCertificate);
+        }
+        if network_cfg.psk.is_some() {
+            features.push(Feature::PreSharedKey);
+        }
+        Handshake {
+            features,
+            token: self.cfg.token.clone(),
+            schema: crate::schema::registered::<T>(),
+            ..Handshake::new(self.cfg.name.as_ref().unwrap_or(&self.topic))
+        }
+    }
+}
+
+impl<B: Block, I: Interface + Default, T: Message> Node<B, I, Idle, T> {
+    /// Replace the configured Host address with one found through discovery, if enabled
+    pub(crate) async fn discover_host_addr(&mut self, transport: Transport) -> Result<(), Error> {
+        if let Some(discovery) = &self.cfg.network_cfg.discovery {
+            self.cfg.network_cfg.host_addr = discover(discovery, transport).await?;
+        }
+        Ok(())
+    }
+}
+
+impl<I: Interface + Default, T: Message> Node<Blocking, I, Idle, T> {
+    /// Blocking equivalent of `discover_host_addr`
+    pub(crate) fn discover_host_addr_blocking(
+        &mut self,
+        transport: Transport,
+    ) -> Result<(), Error> {
+        if self.cfg.network_cfg.discovery.is_none() {
+            return Ok(());
+        }
+        let handle = match &self.rt_handle {
+            Some(handle) => handle.clone(),
+            None => return Err(Error::HandleAccess),
+        };
+        handle.block_on(self.discover_host_addr(transport))
+    }
 }
@@ -3,7 +3,11 @@ use std::marker::PhantomData;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::path::{Path, PathBuf};
 
+use crate::discovery::Discovery;
+use crate::heartbeat::HeartbeatConfig;
+use crate::host::HostHandle;
 use crate::node::private;
+use crate::psk::PreSharedKey;
 pub trait Interface: private::Sealed + Default {}
 pub trait Block: private::Sealed + Default + Sized {}
 
@@ -19,6 +23,23 @@ pub struct Quic {}
 #[cfg(feature = "quic")]
 impl Interface for Quic {}
 
+/// Unix domain socket transport, for Nodes running on the same machine as the Host
+#[derive(Debug, Clone, Default)]
+pub struct Uds {}
+#[cfg(unix)]
+impl Interface for Uds {}
+
+/// Shared-memory transport, exchanging messages with a Host on the same machine through ring buffers
+#[derive(Debug, Clone, Default)]
+pub struct Shm {}
+#[cfg(all(unix, feature = "shm"))]
+impl Interface for Shm {}
+
+/// In-process transport, connecting a Node directly to a Host in the same binary over channels
+#[derive(Debug, Clone, Default)]
+pub struct InProcess {}
+impl Interface for InProcess {}
+
 #[derive(Debug, Clone, Default)]
 pub struct Blocking;
 impl Block for Blocking {}
@@ -39,9 +60,25 @@ where
     pub host_addr: SocketAddr,
     /// Max buffer size that the Node will allocate for Host responses
     pub max_buffer_size: usize,
+    /// Certificate(s) trusted to identify the Host
     pub cert_path: Option<PathBuf>,
+    /// Certificate the Node presents to a Host requiring client authentication
+    pub client_cert_path: Option<PathBuf>,
+    /// Private key matching `client_cert_path`
     pub key_path: Option<PathBuf>,
+    /// DNS name the Host's certificate is verified against
+    pub server_name: Option<String>,
+    /// Filesystem path of the Host's Unix domain socket
+    pub socket_path: Option<PathBuf>,
+    /// Handle to a Host running in the same process
+    pub host_handle: Option<HostHandle>,
+    /// Locate the Host by name through multicast discovery, replacing `host_addr`
+    pub discovery: Option<Discovery>,
+    /// Key shared with the Host for encrypting and authenticating UDP datagrams
+    pub psk: Option<PreSharedKey>,
     pub send_tries: usize,
+    /// Heartbeats sent to the Host so each side can tell the other is still alive
+    pub heartbeat: Option<HeartbeatConfig>,
 }
 
 impl<B: Block> Default for NetworkConfig<B, Tcp> {
@@ -52,7 +89,14 @@ impl<B: Block> Default for NetworkConfig<B, Tcp> {
             host_addr: SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 25_000),
             max_buffer_size: 1024,
             cert_path: None,
+            client_cert_path: None,
             key_path: None,
+            server_name: None,
+            socket_path: None,
+            host_handle: None,
+            discovery: None,
+            psk: None,
+            heartbeat: Some(HeartbeatConfig::default()),
             send_tries: 10,
         }
     }
@@ -65,6 +109,35 @@ impl<B: Block> NetworkConfig<B, Tcp> {
         self
     }
 
+    /// Encrypt the connection with TLS, trusting the certificate(s) in the PEM file to identify the Host
+    #[cfg(feature = "tls")]
+    pub fn set_tls(mut self, cert_path: impl Into<PathBuf>) -> Self {
+        self.cert_path = Some(cert_path.into());
+        if self.server_name.is_none() {
+            self.server_name = Some("localhost".into());
+        }
+        self
+    }
+
+    /// Set the DNS name the Host's TLS certificate must be valid for, which needn't match `host_addr`
+    #[cfg(feature = "tls")]
+    pub fn set_server_name(mut self, server_name: impl Into<String>) -> Self {
+        self.server_name = Some(server_name.into());
+        self
+    }
+
+    /// Authenticate the Node to the Host over TLS with a client certificate and its private key
+    #[cfg(feature = "tls")]
+    pub fn set_client_cert(
+        mut self,
+        cert_path: impl Into<PathBuf>,
+        key_path: impl Into<PathBuf>,
+    ) -> Self {
+        self.client_cert_path = Some(cert_path.into());
+        self.key_path = Some(key_path.into());
+        self
+    }
+
     /// Set a max buffer size for Host responses
     pub fn set_max_buffer_size(mut self, max_buffer_size: impl Into<usize>) -> Self {
         self.max_buffer_size = max_buffer_size.into();
@@ -80,7 +153,14 @@ impl<B: Block> Default for NetworkConfig<B, Udp> {
             host_addr: SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 25_000),
             max_buffer_size: 2048,
             cert_path: None,
+            client_cert_path: None,
             key_path: None,
+            server_name: None,
+            socket_path: None,
+            host_handle: None,
+            discovery: None,
+            psk: None,
+            heartbeat: Some(HeartbeatConfig::default()),
             send_tries: 10,
         }
     }
@@ -93,6 +173,12 @@ impl<B: Block> NetworkConfig<B, Udp> {
         self
     }
 
+    /// Encrypt and authenticate datagrams with the key the Host's `UdpConfig` was given
+    pub fn set_psk(mut self, psk: PreSharedKey) -> Self {
+        self.psk = Some(psk);
+        self
+    }
+
     /// Set a max buffer size for Host responses
     pub fn set_max_buffer_size(mut self, max_buffer_size: impl Into<usize>) -> Self {
         self.max_buffer_size = max_buffer_size.into();
@@ -109,7 +195,14 @@ impl<B: Block> Default for NetworkConfig<B, Quic> {
             max_buffer_size: 4096,
             send_tries: 10,
             cert_path: Some(Path::new("target").join("cert.pem")),
-            key_path: Some(Path::new("target").join("priv_key.pem")),
+            client_cert_path: None,
+            key_path: None,
+            server_name: Some("localhost".into()),
+            socket_path: None,
+            host_handle: None,
+            discovery: None,
+            psk: None,
+            heartbeat: Some(HeartbeatConfig::default()),
         }
     }
 }
@@ -121,9 +214,144 @@ impl<B: Block> NetworkConfig<B, Quic> {
         self
     }
 
+    /// Set the PEM file of certificate(s) trusted to identify the Host
+    pub fn set_cert_path(mut self, cert_path: impl Into<PathBuf>) -> Self {
+        self.cert_path = Some(cert_path.into());
+        self
+    }
+
+    /// Set the DNS name the Host's certificate must be valid for, which needn't match `host_addr`
+    pub fn set_server_name(mut self, server_name: impl Into<String>) -> Self {
+        self.server_name = Some(server_name.into());
+        self
+    }
+
+    /// Authenticate the Node to the Host with a client certificate and its private key
+    pub fn set_client_cert(
+        mut self,
+        cert_path: impl Into<PathBuf>,
+        key_path: impl Into<PathBuf>,
+    ) -> Self {
+        self.client_cert_path = Some(cert_path.into());
+        self.key_path = Some(key_path.into());
+        self
+    }
+
     /// Set a max buffer size for Host responses
     pub fn set_max_buffer_size(mut self, max_buffer_size: impl Into<usize>) -> Self {
         self.max_buffer_size = max_buffer_size.into();
         self
     }
 }
+
+impl<B: Block> Default for NetworkConfig<B, Uds> {
+    fn default() -> NetworkConfig<B, Uds> {
+        Self {
+            __interface: PhantomData::<Uds>,
+            __block: PhantomData::<B>,
+            host_addr: SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 25_000),
+            max_buffer_size: 1024,
+            cert_path: None,
+            client_cert_path: None,
+            key_path: None,
+            server_name: None,
+            socket_path: Some(Path::new("/tmp").join("meadow.sock")),
+            host_handle: None,
+            discovery: None,
+            psk: None,
+            heartbeat: None,
+            send_tries: 10,
+        }
+    }
+}
+
+impl<B: Block> NetworkConfig<B, Uds> {
+    /// Define the path of the Unix domain socket the Host is listening on
+    pub fn set_socket_path(mut self, socket_path: impl Into<PathBuf>) -> Self {
+        self.socket_path = Some(socket_path.into());
+        self
+    }
+
+    /// Set a max buffer size for Host responses
+    pub fn set_max_buffer_size(mut self, max_buffer_size: impl Into<usize>) -> Self {
+        self.max_buffer_size = max_buffer_size.into();
+        self
+    }
+}
+
+impl<B: Block> Default for NetworkConfig<B, Shm> {
+    fn default() -> NetworkConfig<B, Shm> {
+        Self {
+            __interface: PhantomData::<Shm>,
+            __block: PhantomData::<B>,
+            host_addr: SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 25_000),
+            max_buffer_size: 1024,
+            cert_path: None,
+            client_cert_path: None,
+            key_path: None,
+            server_name: None,
+            socket_path: Some(Path::new("/tmp").join("meadow_shm.sock")),
+            host_handle: None,
+            discovery: None,
+            psk: None,
+            heartbeat: None,
+            send_tries: 10,
+        }
+    }
+}
+
+impl<B: Block> NetworkConfig<B, Shm> {
+    /// Define the path of the control socket the Host's shared-memory listener is bound to
+    pub fn set_socket_path(mut self, socket_path: impl Into<PathBuf>) -> Self {
+        self.socket_path = Some(socket_path.into());
+        self
+    }
+}
+
+impl<B: Block> Default for NetworkConfig<B, InProcess> {
+    fn default() -> NetworkConfig<B, InProcess> {
+        Self {
+            __interface: PhantomData::<InProcess>,
+            __block: PhantomData::<B>,
+            host_addr: SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 25_000),
+            max_buffer_size: 1024,
+            cert_path: None,
+            client_cert_path: None,
+            key_path: None,
+            server_name: None,
+            socket_path: None,
+            host_handle: None,
+            discovery: None,
+            psk: None,
+            heartbeat: None,
+            send_tries: 10,
+        }
+    }
+}
+
+impl<B: Block> NetworkConfig<B, InProcess> {
+    /// Connect to the Host behind the provided handle, obtained with `Host::handle()`
+    pub fn set_host_handle(mut self, host_handle: HostHandle) -> Self {
+        self.host_handle = Some(host_handle);
+        self
+    }
+}
+
+impl<B: Block, I: Interface> NetworkConfig<B, I> {
+    /// Discover the Host by name when the Node is activated, instead of using `host_addr`
+    ///
+    /// Only applies to network transports (TCP, UDP, and QUIC)
+    pub fn set_discovery(mut self, discovery: Discovery) -> Self {
+        self.discovery = Some(discovery);
+        self
+    }
+
+    /// Set how often heartbeats are sent to the Host and how long it may go without answering,
+    /// or with `None`, send none; the Host should then be configured not to expect them either
+    ///
+    /// Only applies to network transports (TCP, UDP, and QUIC)
+    pub fn set_heartbeat(mut self, heartbeat: Option<HeartbeatConfig>) -> Self {
+        self.heartbeat = heartbeat;
+        self
+    }
+}
@@ -21,7 +21,8 @@ impl<T: Message + 'static, B: Block + Debug> Node<B, Quic, Active, T> {
     #[inline]
     async fn publish_internal(&self, val: T) -> Result<(), Error> {
         let packet = Msg::new(MsgType::Set, self.topic.clone(), val)
-            .to_generic()?
+            .to_generic_encoded(self.cfg.encoding)?
+            .compressed(self.cfg.compression)
             .as_bytes()?;
 
         if let Some(connection) = &self.connection {
@@ -51,7 +52,10 @@ impl<T: Message + 'static, B: Block + Debug> Node<B, Quic, Active, T> {
     #[tracing::instrument(skip_all)]
     #[inline]
     async fn publish_msg_internal(&self, msg: Msg<T>) -> Result<(), Error> {
-        let packet = msg.to_generic()?.as_bytes()?;
+        let packet = msg
+            .to_generic_encoded(self.cfg.encoding)?
+            .compressed(self.cfg.compression)
+            .as_bytes()?;
 
         if let Some(connection) = &self.connection {
             match connection.open_bi().await {
@@ -152,6 +156,14 @@ impl<T: Message + 'static> Node<Nonblocking, Quic, Active, T> {
         Ok(msg)
     }
 
+    /// Request n'th data from host on Node's assigned topic
+    #[tracing::instrument(skip_all)]
+    #[inline]
+    pub async fn request_nth_back(&self, n: usize) -> Result<Msg<T>, Error> {
+        let msg = self.request_nth_back_internal(n).await?;
+        Ok(msg)
+    }
+
     #[tracing::instrument(skip_all)]
     #[inline]
     pub async fn topics(&self) -> Result<Msg<Vec<String>>, Error> {
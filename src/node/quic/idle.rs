@@ -2,18 +2,21 @@ extern crate alloc;
 use crate::error::{Error, Quic::*};
 use crate::*;
 
+use crate::discovery::Transport;
 use crate::node::network_config::{Nonblocking, Quic};
 use crate::node::*;
 
 use std::path::PathBuf;
 
 use tokio::net::UdpSocket;
+use tokio::runtime::Handle;
 use tokio::sync::Mutex as TokioMutex;
 use tokio::time::{sleep, Duration};
 
 use tracing::*;
 
-use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use crate::networks::unspecified_addr;
+use std::net::SocketAddr;
 use std::result::Result;
 use std::sync::Arc;
 
@@ -26,7 +29,7 @@ use std::marker::PhantomData;
 use quinn::Endpoint;
 
 use crate::msg::*;
-use crate::node::quic::generate_client_config_from_certs;
+use crate::node::quic::{generate_client_config_from_certs, handshake, watch_connection};
 use chrono::Utc;
 
 impl<T: Message> From<Node<Nonblocking, Quic, Idle, T>> for Node<Nonblocking, Quic, Active, T> {
@@ -38,13 +41,21 @@ impl<T: Message> From<Node<Nonblocking, Quic, Idle, T>> for Node<Nonblocking, Qu
             runtime: node.runtime,
             rt_handle: node.rt_handle,
             stream: node.stream,
+            #[cfg(unix)]
+            uds_stream: node.uds_stream,
+            #[cfg(all(unix, feature = "shm"))]
+            shm: node.shm,
             topic: node.topic,
             socket: node.socket,
+            udp_cipher: node.udp_cipher,
+            in_process: node.in_process,
             buffer: node.buffer,
             endpoint: node.endpoint,
             connection: node.connection,
             subscription_data: node.subscription_data,
+            host_pulse: node.host_pulse,
             task_subscribe: None,
+            task_heartbeat: node.task_heartbeat,
         }
     }
 }
@@ -60,13 +71,21 @@ impl<T: Message> From<Node<Nonblocking, Quic, Idle, T>>
             runtime: node.runtime,
             rt_handle: node.rt_handle,
             stream: node.stream,
+            #[cfg(unix)]
+            uds_stream: node.uds_stream,
+            #[cfg(all(unix, feature = "shm"))]
+            shm: node.shm,
             topic: node.topic,
             socket: node.socket,
+            udp_cipher: node.udp_cipher,
+            in_process: node.in_process,
             buffer: node.buffer,
             endpoint: node.endpoint,
             connection: node.connection,
             subscription_data: node.subscription_data,
+            host_pulse: node.host_pulse,
             task_subscribe: node.task_subscribe,
+            task_heartbeat: node.task_heartbeat,
         }
     }
 }
@@ -75,6 +94,7 @@ impl<T: Message + 'static> Node<Nonblocking, Quic, Idle, T> {
     /// Attempt connection from the Node to the Host located at the specified address
     //#[tracing::instrument(skip_all)]
     pub async fn activate(mut self) -> Result<Node<Nonblocking, Quic, Active, T>, Error> {
+        self.discover_host_addr(Transport::Quic).await?;
         debug!("Attempting QUIC connection");
 
         self.create_connection().await?;
@@ -84,23 +104,30 @@ impl<T: Message + 'static> Node<Nonblocking, Quic, Idle, T> {
 
     async fn create_connection(&mut self) -> Result<(), Error> {
         let host_addr = self.cfg.network_cfg.host_addr;
-        let cert_path = self.cfg.network_cfg.cert_path.clone();
+        let client_cfg = generate_client_config_from_certs(&self.cfg.network_cfg)?;
+        let server_name = match &self.cfg.network_cfg.server_name {
+            Some(server_name) => server_name.clone(),
+            None => return Err(Error::Quic(NoProvidedServerName)),
+        };
+        let node_handshake = self.handshake();
 
         let (endpoint, connection) = {
             // QUIC, needs to be done inside of a tokio context
-            let client_cfg = generate_client_config_from_certs(cert_path)?;
-            let client_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)), 0);
+            let client_addr = unspecified_addr(&host_addr);
 
             let mut endpoint = Endpoint::client(client_addr)?;
             endpoint.set_default_client_config(client_cfg);
 
-            // TO_DO: This shouldn't just be "localhost"
-            let connection = endpoint.connect(host_addr, "localhost")?.await?;
+            let connection = endpoint.connect(host_addr, &server_name)?.await?;
+            handshake(&connection, &node_handshake).await?;
 
             debug!("{:?}", &endpoint.local_addr());
 
             Ok::<(Endpoint, quinn::Connection), Error>((endpoint, connection))
         }?;
+        let (pulse, task) = watch_connection(connection.clone(), &Handle::current());
+        self.host_pulse = pulse;
+        self.task_heartbeat = Some(task);
         self.endpoint = Some(endpoint);
         self.connection = Some(connection);
         Ok(())
@@ -111,6 +138,7 @@ impl<T: Message + 'static> Node<Nonblocking, Quic, Idle, T> {
         mut self,
         rate: Duration,
     ) -> Result<Node<Nonblocking, Quic, Subscription, T>, Error> {
+        self.discover_host_addr(Transport::Quic).await?;
         self.create_connection().await?;
         let connection = self.connection.clone();
         let topic = self.topic.clone();
@@ -202,13 +230,21 @@ impl<T: Message> From<Node<Blocking, Quic, Idle, T>> for Node<Blocking, Quic, Ac
             runtime: node.runtime,
             rt_handle: node.rt_handle,
             stream: node.stream,
+            #[cfg(unix)]
+            uds_stream: node.uds_stream,
+            #[cfg(all(unix, feature = "shm"))]
+            shm: node.shm,
             topic: node.topic,
             socket: node.socket,
+            udp_cipher: node.udp_cipher,
+            in_process: node.in_process,
             buffer: node.buffer,
             endpoint: node.endpoint,
             connection: node.connection,
             subscription_data: node.subscription_data,
+            host_pulse: node.host_pulse,
             task_subscribe: None,
+            task_heartbeat: node.task_heartbeat,
         }
     }
 }
@@ -222,13 +258,21 @@ impl<T: Message> From<Node<Blocking, Quic, Idle, T>> for Node<Blocking, Quic, Su
             runtime: node.runtime,
             rt_handle: node.rt_handle,
             stream: node.stream,
+            #[cfg(unix)]
+            uds_stream: node.uds_stream,
+            #[cfg(all(unix, feature = "shm"))]
+            shm: node.shm,
             topic: node.topic,
             socket: node.socket,
+            udp_cipher: node.udp_cipher,
+            in_process: node.in_process,
             buffer: node.buffer,
             endpoint: node.endpoint,
             connection: node.connection,
             subscription_data: node.subscription_data,
+            host_pulse: node.host_pulse,
             task_subscribe: node.task_subscribe,
+            task_heartbeat: node.task_heartbeat,
         }
     }
 }
@@ -237,6 +281,7 @@ impl<T: Message + 'static> Node<Blocking, Quic, Idle, T> {
     /// Attempt connection from the Node to the Host located at the specified address
     //#[tracing::instrument(skip_all)]
     pub fn activate(mut self) -> Result<Node<Blocking, Quic, Active, T>, Error> {
+        self.discover_host_addr_blocking(Transport::Quic)?;
         debug!("Attempting QUIC connection");
 
         self.create_connection()?;
@@ -246,7 +291,12 @@ impl<T: Message + 'static> Node<Blocking, Quic, Idle, T> {
 
     fn create_connection(&mut self) -> Result<(), Error> {
         let host_addr = self.cfg.network_cfg.host_addr;
-        let cert_path = self.cfg.network_cfg.cert_path.clone();
+        let client_cfg = generate_client_config_from_certs(&self.cfg.network_cfg)?;
+        let server_name = match &self.cfg.network_cfg.server_name {
+            Some(server_name) => server_name.clone(),
+            None => return Err(Error::Quic(NoProvidedServerName)),
+        };
+        let node_handshake = self.handshake();
 
         let handle = match &self.rt_handle {
             Some(handle) => handle,
@@ -255,19 +305,21 @@ impl<T: Message + 'static> Node<Blocking, Quic, Idle, T> {
 
         let (endpoint, connection) = handle.block_on(async move {
             // QUIC, needs to be done inside of a tokio context
-            let client_cfg = generate_client_config_from_certs(cert_path)?;
-            let client_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)), 0);
+            let client_addr = unspecified_addr(&host_addr);
 
             let mut endpoint = Endpoint::client(client_addr)?;
             endpoint.set_default_client_config(client_cfg);
 
-            // TO_DO: This shouldn't just be "localhost"
-            let connection = endpoint.connect(host_addr, "localhost")?.await?;
+            let connection = endpoint.connect(host_addr, &server_name)?.await?;
+            handshake(&connection, &node_handshake).await?;
 
             debug!("{:?}", &endpoint.local_addr());
 
             Ok::<(Endpoint, quinn::Connection), Error>((endpoint, connection))
         })?;
+        let (pulse, task) = watch_connection(connection.clone(), handle);
+        self.host_pulse = pulse;
+        self.task_heartbeat = Some(task);
         self.endpoint = Some(endpoint);
         self.connection = Some(connection);
         Ok(())
@@ -278,6 +330,7 @@ impl<T: Message + 'static> Node<Blocking, Quic, Idle, T> {
         mut self,
         rate: Duration,
     ) -> Result<Node<Blocking, Quic, Subscription, T>, Error> {
+        self.discover_host_addr_blocking(Transport::Quic)?;
         self.create_connection()?;
         let connection = self.connection.clone();
         let topic = self.topic.clone();
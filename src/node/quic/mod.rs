@@ -2,42 +2,89 @@ mod active;
 mod idle;
 mod subscription;
 
-use std::fs::File;
-use std::io::BufReader;
-use std::net::SocketAddr;
-use std::path::PathBuf;
+use std::sync::Arc;
 
 use crate::error::Quic::*;
+use crate::handshake::Handshake;
+use crate::heartbeat::{HeartbeatTask, Pulse};
+use crate::host::quic::{read_certs_from_file, read_root_store};
+use crate::node::network_config::{Block, Interface};
 use crate::prelude::*;
 
 use quinn::ClientConfig;
-use rustls::Certificate;
-
+use tokio::runtime::Handle;
 use tracing::*;
 
-pub fn generate_client_config_from_certs(
-    cert_path: Option<PathBuf>,
-) -> Result<ClientConfig, Error> {
-    let mut certs = rustls::RootCertStore::empty();
-
-    let path = match cert_path {
+/// Build the Node's TLS configuration, trusting the Host certificate(s) at `cert_path`
+/// and presenting a client certificate if one is configured
+pub(crate) fn client_crypto_config<B: Block, I: Interface>(
+    cfg: &NetworkConfig<B, I>,
+) -> Result<rustls::ClientConfig, Error> {
+    let path = match &cfg.cert_path {
         Some(path) => path,
         None => return Err(Error::Quic(NoProvidedCertPath)),
     };
+    let builder = rustls::ClientConfig::builder()
+        .with_safe_default_cipher_suites()
+        .with_safe_default_kx_groups()
+        .with_protocol_versions(&[&rustls::version::TLS13])?
+        .with_root_certificates(read_root_store(path)?);
 
-    let f = File::open(path)?;
-    let mut cert_chain_reader = BufReader::new(f);
-    let server_certs: Vec<Certificate> = rustls_pemfile::certs(&mut cert_chain_reader)
-        .unwrap()
-        .into_iter()
-        .map(rustls::Certificate)
-        .collect();
-    for cert in server_certs {
-        if let Err(e) = certs.add(&cert) {
-            error!("Error adding certificate: {:?}", e);
-            return Err(Error::Quic(Webpki));
+    let crypto = match (&cfg.client_cert_path, &cfg.key_path) {
+        (Some(client_cert_path), Some(key_path)) => {
+            let (certs, key) = read_certs_from_file(client_cert_path, key_path)?;
+            builder.with_single_cert(certs, key)?
         }
+        _ => builder.with_no_client_auth(),
+    };
+    Ok(crypto)
+}
+
+/// Run the initial Node <=> Host handshake on the first stream of a new connection
+pub(crate) async fn handshake(
+    connection: &quinn::Connection,
+    handshake: &Handshake,
+) -> Result<(), Error> {
+    let (mut send, mut recv) = connection.open_bi().await?;
+    send.write_all(&handshake.to_generic()?.as_bytes()?).await?;
+    send.finish().await?;
+
+    let mut buf = vec![0u8; 1024];
+    let n = recv.read(&mut buf).await?.ok_or(Error::Quic(Connection))?;
+    let version = crate::handshake::confirm(postcard::from_bytes(&buf[..n])?)?;
+    debug!(
+        "{}: Successfully connected to host with protocol version {}",
+        handshake.name, version
+    );
+    Ok(())
+}
+
+/// Build the Node's QUIC client configuration, trusting the Host certificate(s) at `cert_path`
+/// and presenting a client certificate if one is configured
+pub fn generate_client_config_from_certs<B: Block>(
+    cfg: &NetworkConfig<B, Quic>,
+) -> Result<ClientConfig, Error> {
+    let mut crypto = client_crypto_config(cfg)?;
+    // Mirrors quinn's `ClientConfig::with_root_certificates`
+    crypto.enable_early_data = true;
+    let mut client_cfg = ClientConfig::new(Arc::new(crypto));
+    if let Some(heartbeat) = cfg.heartbeat {
+        client_cfg.transport_config(Arc::new(heartbeat.quic_transport()));
     }
+    Ok(client_cfg)
+}
 
-    Ok(ClientConfig::with_root_certificates(certs))
+/// Mark the Host as lost once QUIC closes the connection, including after its idle timeout
+pub(crate) fn watch_connection(
+    connection: quinn::Connection,
+    handle: &Handle,
+) -> (Arc<Pulse>, HeartbeatTask) {
+    let pulse = Arc::new(Pulse::new(None));
+    let lost = Arc::clone(&pulse);
+    let task = handle.spawn(async move {
+        let reason = connection.closed().await;
+        debug!("Connection to the Host closed: {}", reason);
+        lost.lose();
+    });
+    (pulse, HeartbeatTask::from(task))
 }
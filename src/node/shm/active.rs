@@ -0,0 +1,172 @@
+use crate::node::network_config::{Blocking, Nonblocking, Shm};
+use crate::node::shm::ShmChannel;
+use crate::node::{Active, Block, Node};
+use crate::prelude::*;
+
+use std::convert::TryInto;
+use std::fmt::Debug;
+use std::result::Result;
+
+use tracing::*;
+
+impl<T: Message + 'static, B: Block + Debug> Node<B, Shm, Active, T> {
+    fn channel(&self) -> Result<&ShmChannel, Error> {
+        match self.shm.as_ref() {
+            Some(channel) => Ok(channel),
+            None => Err(Error::AccessStream),
+        }
+    }
+
+    #[tracing::instrument]
+    #[inline]
+    async fn publish_internal(&self, val: T) -> Result<(), Error> {
+        let msg = Msg::new(MsgType::Set, self.topic.clone(), val);
+        self.publish_msg_internal(msg).await
+    }
+
+    #[tracing::instrument]
+    #[inline]
+    async fn publish_msg_internal(&self, msg: Msg<T>) -> Result<(), Error> {
+        let channel = self.channel()?;
+
+        // Send the publish message
+        channel
+            .send_msg(
+                &msg.to_generic_encoded(self.cfg.encoding)?
+                    .compressed(self.cfg.compression),
+            )
+            .await?;
+
+        // Wait for the publish acknowledgement
+        let ack = channel.await_response().await?;
+        if let MsgType::Result(result) = ack.msg_type {
+            result?;
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument]
+    #[inline]
+    async fn request_nth_back_internal(&self, n: usize) -> Result<Msg<T>, Error> {
+        let channel = self.channel()?;
+
+        let packet = GenericMsg::get_nth::<T>(self.topic.clone(), n);
+        channel.send_msg(&packet).await?;
+        let msg = channel.await_response().await?.try_into()?;
+        Ok(msg)
+    }
+
+    #[tracing::instrument]
+    #[inline]
+    async fn topics_internal(&self) -> Result<Msg<Vec<String>>, Error> {
+        let channel = self.channel()?;
+
+        channel.send_msg(&GenericMsg::topics()).await?;
+        let msg = channel.await_response().await?.try_into()?;
+        Ok(msg)
+    }
+}
+impl<T: Message + 'static> Node<Nonblocking, Shm, Active, T> {
+    /// Send data to host on Node's assigned topic using `Msg<T>` packet
+    #[tracing::instrument]
+    #[inline]
+    pub async fn publish(&self, val: T) -> Result<(), Error> {
+        self.publish_internal(val).await?;
+        Ok(())
+    }
+
+    #[tracing::instrument]
+    #[inline]
+    pub async fn publish_msg(&self, msg: Msg<T>) -> Result<(), Error> {
+        self.publish_msg_internal(msg).await?;
+        Ok(())
+    }
+
+    /// Request data from host on Node's assigned topic
+    #[tracing::instrument]
+    #[inline]
+    pub async fn request(&self) -> Result<Msg<T>, Error> {
+        let msg = self.request_nth_back_internal(0).await?;
+        Ok(msg)
+    }
+
+    /// Request n'th data from host on Node's assigned topic
+    #[tracing::instrument]
+    #[inline]
+    pub async fn request_nth_back(&self, n: usize) -> Result<Msg<T>, Error> {
+        let msg = self.request_nth_back_internal(n).await?;
+        Ok(msg)
+    }
+
+    #[tracing::instrument]
+    #[inline]
+    pub async fn topics(&self) -> Result<Msg<Vec<String>>, Error> {
+        let msg = self.topics_internal().await?;
+        Ok(msg)
+    }
+}
+
+impl<T: Message + 'static> Node<Blocking, Shm, Active, T> {
+    /// Send data to host on Node's assigned topic using `Msg<T>` packet
+    #[tracing::instrument(skip_all)]
+    #[inline]
+    pub fn publish(&self, val: T) -> Result<(), Error> {
+        match &self.rt_handle {
+            Some(handle) => handle.block_on(async {
+                self.publish_internal(val).await?;
+                Ok(())
+            }),
+            None => Err(Error::HandleAccess),
+        }
+    }
+
+    #[tracing::instrument]
+    #[inline]
+    pub fn publish_msg(&self, msg: Msg<T>) -> Result<(), Error> {
+        match &self.rt_handle {
+            Some(handle) => handle.block_on(async {
+                self.publish_msg_internal(msg).await?;
+                Ok(())
+            }),
+            None => Err(Error::HandleAccess),
+        }
+    }
+
+    /// Request data from host on Node's assigned topic
+    #[tracing::instrument]
+    #[inline]
+    pub fn request(&self) -> Result<Msg<T>, Error> {
+        match &self.rt_handle {
+            Some(handle) => handle.block_on(async {
+                let msg = self.request_nth_back_internal(0).await?;
+                Ok(msg)
+            }),
+            None => Err(Error::HandleAccess),
+        }
+    }
+
+    /// Request n'th data from host on Node's assigned topic
+    #[tracing::instrument]
+    #[inline]
+    pub fn request_nth_back(&self, n: usize) -> Result<Msg<T>, Error> {
+        match &self.rt_handle {
+            Some(handle) => handle.block_on(async {
+                let msg = self.request_nth_back_internal(n).await?;
+                Ok(msg)
+            }),
+            None => Err(Error::HandleAccess),
+        }
+    }
+
+    #[tracing::instrument]
+    #[inline]
+    pub fn topics(&self) -> Result<Msg<Vec<String>>, Error> {
+        match &self.rt_handle {
+            Some(handle) => handle.block_on(async {
+                let msg = self.topics_internal().await?;
+                Ok(msg)
+            }),
+            None => Err(Error::HandleAccess),
+        }
+    }
+}
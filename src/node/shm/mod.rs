@@ -0,0 +1,125 @@
+mod active;
+mod idle;
+pub(crate) mod ring;
+mod subscription;
+
+use crate::handshake::Handshake;
+use crate::msg::GenericMsg;
+use crate::node::shm::ring::ShmRing;
+use crate::Error;
+
+use serde::{Deserialize, Serialize};
+use tokio::net::UnixStream;
+use tokio::sync::Mutex as TokioMutex;
+use tracing::*;
+
+use std::path::PathBuf;
+use std::result::Result;
+
+/// Locations of the ring buffers the Host created for a shared-memory connection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct ShmRings {
+    /// Ring carrying messages from the Node to the Host
+    pub(crate) request: PathBuf,
+    /// Ring carrying messages from the Host to the Node
+    pub(crate) response: PathBuf,
+}
+
+/// Node-side half of a shared-memory connection.
+///
+/// Payloads travel through the ring buffers, while the control socket only carries
+/// single-byte notifications that new data is available.
+#[derive(Debug)]
+pub struct ShmChannel {
+    control: UnixStream,
+    tx: TokioMutex<ShmRing>,
+    rx: TokioMutex<ShmRing>,
+}
+
+/// Run the initial Node <=> Host handshake, mapping the rings the Host creates for this connection
+pub async fn handshake(stream: UnixStream, handshake: &Handshake) -> Result<ShmChannel, Error> {
+    write_all(&stream, &handshake.to_generic()?.as_bytes()?).await?;
+
+    let mut buf = vec![0u8; 1024];
+    let n = read_some(&stream, &mut buf).await?;
+    // The Host replies with the protocol version it chose and the rings it created, or why
+    // it rejected the handshake
+    let reply: Result<(u16, ShmRings), Error> = postcard::from_bytes(&buf[..n])?;
+    let (version, rings) = reply?;
+    debug!(
+        "{}: Mapping shared-memory rings {:?} for protocol version {}",
+        handshake.name, &rings, version
+    );
+
+    let tx = ShmRing::open(&rings.request)?;
+    let rx = ShmRing::open(&rings.response)?;
+
+    // Let the Host know both rings are mapped, so it can unlink the backing files
+    write_all(&stream, &[0u8]).await?;
+
+    Ok(ShmChannel {
+        control: stream,
+        tx: TokioMutex::new(tx),
+        rx: TokioMutex::new(rx),
+    })
+}
+
+impl ShmChannel {
+    /// Place a `GenericMsg` in the request ring and notify the Host
+    #[inline]
+    pub async fn send_msg(&self, msg: &GenericMsg) -> Result<(), Error> {
+        self.tx.lock().await.push(msg)?;
+        notify(&self.control).await
+    }
+
+    /// Wait for the next `GenericMsg` from the Host to arrive in the response ring
+    #[inline]
+    pub async fn await_response(&self) -> Result<GenericMsg, Error> {
+        let mut rx = self.rx.lock().await;
+        loop {
+            if let Some(msg) = rx.pop::<GenericMsg>() {
+                return msg;
+            }
+            wait_for_notification(&self.control).await?;
+        }
+    }
+}
+
+/// Signal the other side of the control socket that a ring has new data
+#[inline]
+pub(crate) async fn notify(stream: &UnixStream) -> Result<(), Error> {
+    write_all(stream, &[1u8]).await
+}
+
+/// Wait until the other side of the control socket signals new data, consuming any pending signals
+#[inline]
+pub(crate) async fn wait_for_notification(stream: &UnixStream) -> Result<(), Error> {
+    let mut buf = [0u8; 64];
+    read_some(stream, &mut buf).await?;
+    Ok(())
+}
+
+pub(crate) async fn write_all(stream: &UnixStream, mut bytes: &[u8]) -> Result<(), Error> {
+    while !bytes.is_empty() {
+        stream.writable().await?;
+        match stream.try_write(bytes) {
+            Ok(n) => bytes = &bytes[n..],
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(())
+}
+
+pub(crate) async fn read_some(stream: &UnixStream, buf: &mut [u8]) -> Result<usize, Error> {
+    loop {
+        stream.readable().await?;
+        match stream.try_read(buf) {
+            // The other side hung up
+            Ok(0) => return Err(Error::AccessStream),
+            Ok(n) => return Ok(n),
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
@@ -0,0 +1,231 @@
+use crate::Error;
+
+use memmap2::MmapMut;
+use serde::{de::DeserializeOwned, Serialize};
+
+use std::fs::{File, OpenOptions};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Bytes reserved at the start of each mapping for the read/write cursors.
+/// The cursors sit on separate cache lines so producer and consumer don't contend.
+const HEADER_SIZE: usize = 128;
+const WRITE_POS_OFFSET: usize = 0;
+const READ_POS_OFFSET: usize = 64;
+/// Each record in the ring is prefixed by its length as a little-endian `u32`
+const LEN_PREFIX: usize = 4;
+/// Length value marking the remainder of the ring as unused, so the next record starts at zero
+const WRAP_MARKER: u32 = u32::MAX;
+
+/// Single-producer, single-consumer ring buffer backed by a memory-mapped file.
+///
+/// Records are always stored contiguously, so they can be serialized into and
+/// deserialized out of the mapping directly without an intermediate buffer.
+#[derive(Debug)]
+pub(crate) struct ShmRing {
+    map: MmapMut,
+    capacity: usize,
+}
+
+impl ShmRing {
+    /// Create a new ring file of `capacity` data bytes at `path`, readable only by its owner.
+    ///
+    /// Fails if anything already exists at `path`, so another user can't plant a file or
+    /// symlink there for the Host to map.
+    pub(crate) fn create(path: impl AsRef<Path>, capacity: usize) -> Result<Self, Error> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(path)?;
+        file.set_len((HEADER_SIZE + capacity) as u64)?;
+        ShmRing::map(&file)
+    }
+
+    /// Map an existing ring file created by [`ShmRing::create`]
+    pub(crate) fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        ShmRing::map(&file)
+    }
+
+    fn map(file: &File) -> Result<Self, Error> {
+        // SAFETY: the file is only ever shared between one producer and one consumer,
+        // which coordinate through the atomic cursors in the header
+        let map = unsafe { MmapMut::map_mut(file)? };
+        if map.len() <= HEADER_SIZE {
+            return Err(Error::ShmMapping);
+        }
+        let capacity = map.len() - HEADER_SIZE;
+        Ok(ShmRing { map, capacity })
+    }
+
+    fn cursor(&self, offset: usize) -> &AtomicU64 {
+        // SAFETY: mappings are page-aligned and the header is always present,
+        // so both cursor offsets are valid, 8-byte aligned `u64` locations
+        unsafe { &*(self.map.as_ptr().add(offset) as *const AtomicU64) }
+    }
+
+    /// Bytes between the cursors, which the other side of the mapping could have corrupted
+    fn used(&self, read: u64, write: u64) -> Result<usize, Error> {
+        match write.checked_sub(read) {
+            Some(used) if used <= self.capacity as u64 => Ok(used as usize),
+            _ => Err(Error::ShmCorrupted),
+        }
+    }
+
+    /// Serialize `value` directly into the ring, returning the number of payload bytes written
+    pub(crate) fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<usize, Error> {
+        let write = self.cursor(WRITE_POS_OFFSET).load(Ordering::Relaxed);
+        let read = self.cursor(READ_POS_OFFSET).load(Ordering::Acquire);
+        let free = self.capacity - self.used(read, write)?;
+        let index = (write % self.capacity as u64) as usize;
+
+        // First try to fit the record between the write cursor and the end of the ring
+        let contiguous = usize::min(self.capacity - index, free);
+        if let Some(n) = self.try_write_at(index, contiguous, value) {
+            self.advance_write(write, LEN_PREFIX + n);
+            return Ok(n);
+        }
+
+        // Otherwise skip the tail of the ring and start again from the beginning
+        let skip = self.capacity - index;
+        if free <= skip {
+            return Err(Error::ShmFull);
+        }
+        match self.try_write_at(0, free - skip, value) {
+            Some(n) => {
+                if skip >= LEN_PREFIX {
+                    self.data_mut()[index..index + LEN_PREFIX]
+                        .copy_from_slice(&WRAP_MARKER.to_le_bytes());
+                }
+                self.advance_write(write, skip + LEN_PREFIX + n);
+                Ok(n)
+            }
+            None => Err(Error::ShmFull),
+        }
+    }
+
+    fn try_write_at<T: Serialize + ?Sized>(
+        &mut self,
+        index: usize,
+        space: usize,
+        value: &T,
+    ) -> Option<usize> {
+        if space <= LEN_PREFIX {
+            return None;
+        }
+        let data = &mut self.data_mut()[index..index + space];
+        let (prefix, body) = data.split_at_mut(LEN_PREFIX);
+        let n = postcard::to_slice(value, body).ok()?.len();
+        prefix.copy_from_slice(&(n as u32).to_le_bytes());
+        Some(n)
+    }
+
+    fn advance_write(&self, write: u64, n: usize) {
+        self.cursor(WRITE_POS_OFFSET)
+            .store(write + n as u64, Ordering::Release);
+    }
+
+    /// Deserialize the oldest record in the ring, if one exists
+    pub(crate) fn pop<T: DeserializeOwned>(&mut self) -> Option<Result<T, Error>> {
+        self.pop_sized()
+            .map(|result| result.map(|(value, _)| value))
+    }
+
+    /// Deserialize the oldest record in the ring along with its number of payload bytes.
+    ///
+    /// Returns `Error::ShmCorrupted`, and keeps returning it, once the cursors or a record's
+    /// length point outside the data the producer has written.
+    pub(crate) fn pop_sized<T: DeserializeOwned>(&mut self) -> Option<Result<(T, usize), Error>> {
+        loop {
+            let read = self.cursor(READ_POS_OFFSET).load(Ordering::Relaxed);
+            let write = self.cursor(WRITE_POS_OFFSET).load(Ordering::Acquire);
+            let used = match self.used(read, write) {
+                Ok(0) => return None,
+                Ok(used) => used,
+                Err(e) => return Some(Err(e)),
+            };
+            let index = (read % self.capacity as u64) as usize;
+            let remaining = self.capacity - index;
+
+            // Records never straddle the end of the ring; a short tail or an explicit
+            // marker means the producer wrapped around to the start
+            let len = if remaining < LEN_PREFIX {
+                WRAP_MARKER
+            } else {
+                let mut prefix = [0u8; LEN_PREFIX];
+                prefix.copy_from_slice(&self.data()[index..index + LEN_PREFIX]);
+                u32::from_le_bytes(prefix)
+            };
+            if len == WRAP_MARKER {
+                if remaining > used {
+                    return Some(Err(Error::ShmCorrupted));
+                }
+                self.cursor(READ_POS_OFFSET)
+                    .store(read + remaining as u64, Ordering::Release);
+                continue;
+            }
+            if LEN_PREFIX + len as usize > used.min(remaining) {
+                return Some(Err(Error::ShmCorrupted));
+            }
+
+            let start = index + LEN_PREFIX;
+            let bytes = &self.data()[start..start + len as usize];
+            let result = postcard::from_bytes::<T>(bytes)
+                .map(|value| (value, len as usize))
+                .map_err(Error::from);
+            self.cursor(READ_POS_OFFSET)
+                .store(read + (LEN_PREFIX + len as usize) as u64, Ordering::Release);
+            return Some(result);
+        }
+    }
+
+    fn data(&self) -> &[u8] {
+        &self.map[HEADER_SIZE..]
+    }
+
+    fn data_mut(&mut self) -> &mut [u8] {
+        &mut self.map[HEADER_SIZE..]
+    }
+}
+
+#[test]
+fn ring_wraps_around() {
+    let path = std::env::temp_dir().join("meadow_ring_wraps_around");
+    let _ = std::fs::remove_file(&path);
+    let mut tx = ShmRing::create(&path, 64).unwrap();
+    let mut rx = ShmRing::open(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(rx.pop::<Vec<u8>>().is_none());
+    for i in 0..100u8 {
+        let value = vec![i; (i % 20) as usize];
+        tx.push(&value).unwrap();
+        assert_eq!(rx.pop::<Vec<u8>>().unwrap().unwrap(), value);
+    }
+    // A record larger than the ring can never fit
+    assert_eq!(tx.push(&vec![0u8; 128]), Err(Error::ShmFull));
+}
+
+#[test]
+fn corrupt_ring() {
+    let path = std::env::temp_dir().join("meadow_corrupt_ring");
+    let _ = std::fs::remove_file(&path);
+    let mut tx = ShmRing::create(&path, 64).unwrap();
+    let mut rx = ShmRing::open(&path).unwrap();
+    // Rings are never created over an existing file
+    assert!(ShmRing::create(&path, 64).is_err());
+    std::fs::remove_file(&path).unwrap();
+
+    // A record claiming more bytes than were written
+    tx.push(&vec![1u8; 8]).unwrap();
+    tx.data_mut()[..LEN_PREFIX].copy_from_slice(&60u32.to_le_bytes());
+    assert_eq!(rx.pop::<Vec<u8>>(), Some(Err(Error::ShmCorrupted)));
+
+    // A read cursor ahead of the write cursor
+    rx.cursor(READ_POS_OFFSET).store(100, Ordering::Release);
+    assert_eq!(tx.push(&1u8), Err(Error::ShmCorrupted));
+    assert_eq!(rx.pop::<u8>(), Some(Err(Error::ShmCorrupted)));
+}
@@ -1,4 +1,5 @@
 use crate::error::HostOperation;
+use crate::heartbeat::Pulse;
 use crate::node::network_config::Nonblocking;
 use crate::node::tcp::*;
 use crate::node::{Active, Node};
@@ -7,8 +8,10 @@ use crate::*;
 
 use std::convert::TryInto;
 use std::ops::DerefMut;
+use std::sync::Arc;
 
 use chrono::Utc;
+use tokio::runtime::Handle;
 
 use postcard::{from_bytes, to_allocvec};
 #[cfg(feature = "quic")]
@@ -20,12 +23,32 @@ use crate::node::network_config::{Interface, Tcp};
 use crate::node::Block;
 use std::fmt::Debug;
 
+#[cfg(feature = "zerocopy")]
+use crate::raw::{MsgView, Raw, RawMessage};
+
 impl<T: Message + 'static, B: Block + Debug> Node<B, Tcp, Active, T> {
+    /// Exchange heartbeats with the Host on `handle`, if the Node is connected and configured to
+    pub(crate) fn start_heartbeat(&mut self, handle: &Handle) {
+        let (stream, heartbeat) = match (&self.stream, self.cfg.network_cfg.heartbeat) {
+            (Some(stream), Some(heartbeat)) => (Arc::clone(stream), heartbeat),
+            _ => return,
+        };
+        self.host_pulse = Arc::new(Pulse::new(Some(heartbeat)));
+        let task = handle.spawn(crate::node::tcp::heartbeat(
+            stream,
+            Arc::clone(&self.buffer),
+            heartbeat,
+            Arc::clone(&self.host_pulse),
+        ));
+        self.task_heartbeat = Some(task.into());
+    }
+
     #[tracing::instrument]
     #[inline]
     async fn publish_internal(&self, val: T) -> Result<(), Error> {
         let packet = Msg::new(MsgType::Set, self.topic.clone(), val)
-            .to_generic()?
+            .to_generic_encoded(self.cfg.encoding)?
+            .compressed(self.cfg.compression)
             .as_bytes()?;
 
         let stream = match self.stream.as_ref() {
@@ -34,39 +57,19 @@ impl<T: Message + 'static, B: Block + Debug> Node<B, Tcp, Active, T> {
         };
 
         // Send the publish message
-        send_msg(stream, packet).await?;
+        let mut buf = self.buffer.lock().await;
+        stream.send_msg(packet).await?;
 
         // Wait for the publish acknowledgement
-        let mut buf = self.buffer.lock().await;
-        loop {
-            if let Ok(()) = stream.readable().await {
-                match stream.try_read(&mut buf) {
-                    Ok(0) => continue,
-                    Ok(n) => {
-                        let bytes = &buf[..n];
-                        match from_bytes::<GenericMsg>(bytes) {
-                            Ok(g) => match g.msg_type {
-                                MsgType::Result(result) => {
-                                    if let Err(e) = result {
-                                        error!("{}", e);
-                                    }
-                                }
-                                _ => {
-                                    info!("{:?}", &g);
-                                }
-                            },
-                            Err(e) => {
-                                error!("{}", e);
-                            }
-                        }
-
-                        break;
-                    }
-                    Err(_e) => {
-                        // if e.kind() == std::io::ErrorKind::WouldBlock {}
-                        continue;
-                    }
+        match stream.await_response(&mut buf).await {
+            Ok(g) => match g.msg_type {
+                MsgType::Result(result) => result?,
+                _ => {
+                    info!("{:?}", &g);
                 }
+            },
+            Err(e) => {
+                error!("{}", e);
             }
         }
         Ok(())
@@ -75,48 +78,29 @@ impl<T: Message + 'static, B: Block + Debug> Node<B, Tcp, Active, T> {
     #[tracing::instrument]
     #[inline]
     async fn publish_msg_internal(&self, msg: Msg<T>) -> Result<(), Error> {
-        let packet = msg.to_generic()?.as_bytes()?;
+        let packet = msg
+            .to_generic_encoded(self.cfg.encoding)?
+            .compressed(self.cfg.compression)
+            .as_bytes()?;
         let stream = match self.stream.as_ref() {
             Some(stream) => stream,
             None => return Err(Error::AccessStream),
         };
 
         // Send the publish message
-        send_msg(stream, packet).await?;
+        let mut buf = self.buffer.lock().await;
+        stream.send_msg(packet).await?;
 
         // Wait for the publish acknowledgement
-        let mut buf = self.buffer.lock().await;
-        loop {
-            if let Ok(()) = stream.readable().await {
-                match stream.try_read(&mut buf) {
-                    Ok(0) => continue,
-                    Ok(n) => {
-                        let bytes = &buf[..n];
-
-                        match from_bytes::<GenericMsg>(bytes) {
-                            Ok(g) => match g.msg_type {
-                                MsgType::Result(result) => {
-                                    if let Err(e) = result {
-                                        error!("{}", e)
-                                    } else {
-                                        info!("{:?}", result);
-                                    }
-                                }
-                                _ => (),
-                            },
-                            Err(e) => {
-                                error!("{}", e);
-                            }
-                        }
-
-                        break;
-                    }
-                    Err(_e) => {
-                        // if e.kind() == std::io::ErrorKind::WouldBlock {}
-                        continue;
-                    }
+        match stream.await_response(&mut buf).await {
+            Ok(g) => {
+                if let MsgType::Result(result) = g.msg_type {
+                    result?;
                 }
             }
+            Err(e) => {
+                error!("{}", e);
+            }
         }
         Ok(())
     }
@@ -133,8 +117,8 @@ impl<T: Message + 'static, B: Block + Debug> Node<B, Tcp, Active, T> {
         let packet = GenericMsg::get_nth::<T>(self.topic.clone(), n).as_bytes()?;
 
         let mut buffer = self.buffer.lock().await;
-        send_msg(stream, packet).await?;
-        let msg = await_response(stream, &mut buffer).await?.try_into()?;
+        stream.send_msg(packet).await?;
+        let msg = stream.await_response(&mut buffer).await?.try_into()?;
         Ok(msg)
     }
 
@@ -149,8 +133,8 @@ impl<T: Message + 'static, B: Block + Debug> Node<B, Tcp, Active, T> {
         let packet = GenericMsg::topics().as_bytes()?;
 
         let mut buffer = self.buffer.lock().await;
-        send_msg(stream, packet).await?;
-        let msg = await_response(stream, &mut buffer).await?.try_into()?;
+        stream.send_msg(packet).await?;
+        let msg = stream.await_response(&mut buffer).await?.try_into()?;
         Ok(msg)
     }
 }
@@ -263,3 +247,76 @@ impl<T: Message + 'static> Node<Blocking, Tcp, Active, T> {
         }
     }
 }
+
+#[cfg(feature = "zerocopy")]
+impl<T: RawMessage, B: Block + Debug> Node<B, Tcp, Active, Raw<T>> {
+    #[tracing::instrument(skip(f))]
+    #[inline]
+    async fn request_view_internal<R>(
+        &self,
+        n: usize,
+        f: impl FnOnce(MsgView<'_, T>) -> R,
+    ) -> Result<R, Error> {
+        let stream = match self.stream.as_ref() {
+            Some(stream) => stream,
+            None => return Err(Error::AccessStream),
+        };
+
+        let packet = GenericMsg::get_nth::<Raw<T>>(self.topic.clone(), n).as_bytes()?;
+
+        let mut buffer = self.buffer.lock().await;
+        stream.send_msg(packet).await?;
+        let len = stream.read_reply(&mut buffer).await?;
+        Ok(f(MsgView::from_bytes(&buffer[..len])?))
+    }
+}
+
+#[cfg(feature = "zerocopy")]
+impl<T: RawMessage> Node<Nonblocking, Tcp, Active, Raw<T>> {
+    /// Request data from host on Node's assigned topic, passing `f` a view of it read in place
+    /// from the received bytes rather than deserialized
+    #[tracing::instrument(skip(f))]
+    #[inline]
+    pub async fn request_view<R>(&self, f: impl FnOnce(MsgView<'_, T>) -> R) -> Result<R, Error> {
+        self.request_view_internal(0, f).await
+    }
+
+    /// Request n'th data from host on Node's assigned topic, passing `f` a view of it
+    #[tracing::instrument(skip(f))]
+    #[inline]
+    pub async fn request_nth_back_view<R>(
+        &self,
+        n: usize,
+        f: impl FnOnce(MsgView<'_, T>) -> R,
+    ) -> Result<R, Error> {
+        self.request_view_internal(n, f).await
+    }
+}
+
+#[cfg(feature = "zerocopy")]
+impl<T: RawMessage> Node<Blocking, Tcp, Active, Raw<T>> {
+    /// Request data from host on Node's assigned topic, passing `f` a view of it read in place
+    /// from the received bytes rather than deserialized
+    #[tracing::instrument(skip(f))]
+    #[inline]
+    pub fn request_view<R>(&self, f: impl FnOnce(MsgView<'_, T>) -> R) -> Result<R, Error> {
+        match &self.rt_handle {
+            Some(handle) => handle.block_on(self.request_view_internal(0, f)),
+            None => Err(Error::HandleAccess),
+        }
+    }
+
+    /// Request n'th data from host on Node's assigned topic, passing `f` a view of it
+    #[tracing::instrument(skip(f))]
+    #[inline]
+    pub fn request_nth_back_view<R>(
+        &self,
+        n: usize,
+        f: impl FnOnce(MsgView<'_, T>) -> R,
+    ) -> Result<R, Error> {
+        match &self.rt_handle {
+            Some(handle) => handle.block_on(self.request_view_internal(n, f)),
+            None => Err(Error::HandleAccess),
+        }
+    }
+}
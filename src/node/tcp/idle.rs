@@ -1,11 +1,15 @@
 extern crate alloc;
 use crate::prelude::*;
 
+use crate::discovery::Transport;
+use crate::error::HostError;
 use crate::node::network_config::{Nonblocking, Tcp};
 use crate::node::*;
 
-use tcp::try_connection;
+use crate::heartbeat::{HeartbeatConfig, Pulse, Ticker};
+use tcp::TcpConnection;
 use tokio::net::UdpSocket;
+use tokio::runtime::Handle;
 use tokio::sync::Mutex as TokioMutex;
 use tokio::time::{sleep, Duration};
 
@@ -35,15 +39,23 @@ impl<T: Message> From<Node<Nonblocking, Tcp, Idle, T>> for Node<Nonblocking, Tcp
             runtime: node.runtime,
             rt_handle: node.rt_handle,
             stream: node.stream,
+            #[cfg(unix)]
+            uds_stream: node.uds_stream,
+            #[cfg(all(unix, feature = "shm"))]
+            shm: node.shm,
             topic: node.topic,
             socket: node.socket,
+            udp_cipher: node.udp_cipher,
+            in_process: node.in_process,
             buffer: node.buffer,
             #[cfg(feature = "quic")]
             endpoint: node.endpoint,
             #[cfg(feature = "quic")]
             connection: node.connection,
             subscription_data: node.subscription_data,
+            host_pulse: node.host_pulse,
             task_subscribe: None,
+            task_heartbeat: None,
         }
     }
 }
@@ -57,43 +69,51 @@ impl<T: Message> From<Node<Nonblocking, Tcp, Idle, T>> for Node<Nonblocking, Tcp
             runtime: node.runtime,
             rt_handle: node.rt_handle,
             stream: node.stream,
+            #[cfg(unix)]
+            uds_stream: node.uds_stream,
+            #[cfg(all(unix, feature = "shm"))]
+            shm: node.shm,
             topic: node.topic,
             socket: node.socket,
+            udp_cipher: node.udp_cipher,
+            in_process: node.in_process,
             buffer: node.buffer,
             #[cfg(feature = "quic")]
             endpoint: node.endpoint,
             #[cfg(feature = "quic")]
             connection: node.connection,
             subscription_data: node.subscription_data,
+            host_pulse: node.host_pulse,
             task_subscribe: None,
+            task_heartbeat: None,
         }
     }
 }
 
-use crate::node::tcp::handshake;
-use tokio::net::TcpStream;
-
 impl<T: Message + 'static> Node<Nonblocking, Tcp, Idle, T> {
     /// Attempt connection from the Node to the Host located at the specified address
     #[tracing::instrument(skip_all)]
     pub async fn activate(mut self) -> Result<Node<Nonblocking, Tcp, Active, T>, Error> {
-        let addr = self.cfg.network_cfg.host_addr;
-        let topic = self.topic.clone();
-
-        let stream: Result<TcpStream, Error> = {
-            let stream = try_connection(addr).await?;
-            let stream = handshake(stream, topic).await?;
-            Ok(stream)
-        };
-        if let Ok(stream) = stream {
-            debug!(
-                "Established Node<=>Host TCP stream: {:?}",
-                stream.local_addr()
-            );
-            self.stream = Some(stream);
+        self.discover_host_addr(Transport::Tcp).await?;
+        let node_handshake = self.handshake();
+
+        let stream = TcpConnection::connect(&self.cfg.network_cfg, &node_handshake).await;
+        match stream {
+            Ok(stream) => {
+                debug!(
+                    "Established Node<=>Host TCP stream: {:?}",
+                    stream.local_addr()
+                );
+                self.stream = Some(Arc::new(stream));
+            }
+            // The Host was reached, but refused to accept the Node
+            Err(e @ Error::HostOperation(HostError::HandshakeRejected(_))) => return Err(e),
+            Err(_) => (),
         }
 
-        Ok(Node::<Nonblocking, Tcp, Active, T>::from(self))
+        let mut node = Node::<Nonblocking, Tcp, Active, T>::from(self);
+        node.start_heartbeat(&Handle::current());
+        Ok(node)
     }
 
     #[tracing::instrument]
@@ -101,32 +121,40 @@ impl<T: Message + 'static> Node<Nonblocking, Tcp, Idle, T> {
         mut self,
         rate: Duration,
     ) -> Result<Node<Nonblocking, Tcp, Subscription, T>, Error> {
-        let addr = self.cfg.network_cfg.host_addr;
+        self.discover_host_addr(Transport::Tcp).await?;
+        let network_cfg = self.cfg.network_cfg.clone();
         let topic = self.topic.clone();
+        let node_handshake = self.handshake();
 
         let subscription_data: Arc<TokioMutex<Option<Msg<T>>>> = Arc::new(TokioMutex::new(None));
         let data = Arc::clone(&subscription_data);
 
         let buffer = self.buffer.clone();
         let packet = GenericMsg::subscribe(&topic, rate)?;
+        let pulse = Arc::new(Pulse::new(network_cfg.heartbeat));
+        self.host_pulse = Arc::clone(&pulse);
 
         let task_subscribe = tokio::spawn(async move {
-            if let Ok(stream) = try_connection(addr).await {
-                if let Ok(stream) = handshake(stream, topic.clone()).await {
-                    loop {
-                        if let Err(e) = run_subscription::<T>(
-                            packet.clone(),
-                            buffer.clone(),
-                            &stream,
-                            data.clone(),
-                        )
-                        .await
-                        {
-                            error!("{:?}", e);
-                        }
+            if let Ok(stream) = TcpConnection::connect(&network_cfg, &node_handshake).await {
+                loop {
+                    match run_subscription::<T>(
+                        packet.clone(),
+                        buffer.clone(),
+                        &stream,
+                        data.clone(),
+                        network_cfg.heartbeat,
+                        &pulse,
+                    )
+                    .await
+                    {
+                        // The Host has hung up
+                        Err(Error::AccessStream) => break,
+                        Err(e) => error!("{:?}", e),
+                        Ok(()) => (),
                     }
                 }
             }
+            pulse.lose();
         });
         self.task_subscribe = Some(task_subscribe);
 
@@ -137,19 +165,35 @@ impl<T: Message + 'static> Node<Nonblocking, Tcp, Idle, T> {
     }
 }
 
-use crate::node::tcp::{await_response, send_msg};
 async fn run_subscription<T: Message>(
     packet: GenericMsg,
     buffer: Arc<TokioMutex<Vec<u8>>>,
-    stream: &TcpStream,
+    stream: &TcpConnection,
     data: Arc<TokioMutex<Option<Msg<T>>>>,
+    heartbeat: Option<HeartbeatConfig>,
+    pulse: &Pulse,
 ) -> Result<(), Error> {
-    send_msg(stream, packet.as_bytes()?).await?;
+    stream.send_msg(packet.as_bytes()?).await?;
 
+    let mut ticker = Ticker::new(heartbeat);
     let mut buffer = buffer.lock().await;
     loop {
-        match await_response(stream, &mut buffer).await {
+        let received = tokio::select! {
+            received = stream.recv_msg(&mut buffer) => received,
+            _ = ticker.tick() => {
+                if let Err(e) = stream.send_msg(GenericMsg::heartbeat().as_bytes()?).await {
+                    debug!("Unable to send a heartbeat to the Host: {:?}", e);
+                    return Err(Error::AccessStream);
+                }
+                continue;
+            }
+        };
+        match received {
             Ok(msg) => {
+                pulse.beat();
+                if msg.msg_type == MsgType::Heartbeat {
+                    continue;
+                }
                 match TryInto::<Msg<T>>::try_into(msg) {
                     Ok(msg) => {
                         let mut data = data.lock().await;
@@ -175,6 +219,8 @@ async fn run_subscription<T: Message>(
                     }
                 }
             }
+            // Let the caller stop, rather than spin on a closed connection
+            Err(Error::AccessStream) => return Err(Error::AccessStream),
             Err(e) => {
                 error!("Subscription Error: {:?}", e);
                 continue;
@@ -194,15 +240,23 @@ impl<T: Message> From<Node<Blocking, Tcp, Idle, T>> for Node<Blocking, Tcp, Acti
             runtime: node.runtime,
             rt_handle: node.rt_handle,
             stream: node.stream,
+            #[cfg(unix)]
+            uds_stream: node.uds_stream,
+            #[cfg(all(unix, feature = "shm"))]
+            shm: node.shm,
             topic: node.topic,
             socket: node.socket,
+            udp_cipher: node.udp_cipher,
+            in_process: node.in_process,
             buffer: node.buffer,
             #[cfg(feature = "quic")]
             endpoint: node.endpoint,
             #[cfg(feature = "quic")]
             connection: node.connection,
             subscription_data: node.subscription_data,
+            host_pulse: node.host_pulse,
             task_subscribe: None,
+            task_heartbeat: None,
         }
     }
 }
@@ -216,15 +270,23 @@ impl<T: Message> From<Node<Blocking, Tcp, Idle, T>> for Node<Blocking, Tcp, Subs
             runtime: node.runtime,
             rt_handle: node.rt_handle,
             stream: node.stream,
+            #[cfg(unix)]
+            uds_stream: node.uds_stream,
+            #[cfg(all(unix, feature = "shm"))]
+            shm: node.shm,
             topic: node.topic,
             socket: node.socket,
+            udp_cipher: node.udp_cipher,
+            in_process: node.in_process,
             buffer: node.buffer,
             #[cfg(feature = "quic")]
             endpoint: node.endpoint,
             #[cfg(feature = "quic")]
             connection: node.connection,
             subscription_data: node.subscription_data,
+            host_pulse: node.host_pulse,
             task_subscribe: None,
+            task_heartbeat: None,
         }
     }
 }
@@ -234,28 +296,36 @@ impl<T: Message + 'static> Node<Blocking, Tcp, Idle, T> {
     /// Attempt connection from the Node to the Host located at the specified address
     #[tracing::instrument(skip_all)]
     pub fn activate(mut self) -> Result<Node<Blocking, Tcp, Active, T>, Error> {
-        let addr = self.cfg.network_cfg.host_addr;
-        let topic = self.topic.clone();
+        self.discover_host_addr_blocking(Transport::Tcp)?;
+        let node_handshake = self.handshake();
 
         let handle = match &self.rt_handle {
             Some(handle) => handle,
             None => return Err(Error::HandleAccess),
         };
 
-        let stream: Result<TcpStream, Error> = handle.block_on(async move {
-            let stream = try_connection(addr).await?;
-            let stream = handshake(stream, topic).await?;
-            Ok(stream)
-        });
-        if let Ok(stream) = stream {
-            debug!(
-                "Established Node<=>Host TCP stream: {:?}",
-                stream.local_addr()
-            );
-            self.stream = Some(stream);
+        let stream = handle.block_on(TcpConnection::connect(
+            &self.cfg.network_cfg,
+            &node_handshake,
+        ));
+        match stream {
+            Ok(stream) => {
+                debug!(
+                    "Established Node<=>Host TCP stream: {:?}",
+                    stream.local_addr()
+                );
+                self.stream = Some(Arc::new(stream));
+            }
+            // The Host was reached, but refused to accept the Node
+            Err(e @ Error::HostOperation(HostError::HandshakeRejected(_))) => return Err(e),
+            Err(_) => (),
         }
 
-        Ok(Node::<Blocking, Tcp, Active, T>::from(self))
+        let mut node = Node::<Blocking, Tcp, Active, T>::from(self);
+        if let Some(handle) = node.rt_handle.clone() {
+            node.start_heartbeat(&handle);
+        }
+        Ok(node)
     }
 
     #[tracing::instrument]
@@ -263,14 +333,18 @@ impl<T: Message + 'static> Node<Blocking, Tcp, Idle, T> {
         mut self,
         rate: Duration,
     ) -> Result<Node<Blocking, Tcp, Subscription, T>, Error> {
-        let addr = self.cfg.network_cfg.host_addr;
+        self.discover_host_addr_blocking(Transport::Tcp)?;
+        let network_cfg = self.cfg.network_cfg.clone();
         let topic = self.topic.clone();
+        let node_handshake = self.handshake();
 
         let subscription_data: Arc<TokioMutex<Option<Msg<T>>>> = Arc::new(TokioMutex::new(None));
         let data = Arc::clone(&subscription_data);
 
         let buffer = self.buffer.clone();
         let packet = GenericMsg::subscribe(&topic, rate)?;
+        let pulse = Arc::new(Pulse::new(network_cfg.heartbeat));
+        self.host_pulse = Arc::clone(&pulse);
 
         let handle = match &self.rt_handle {
             Some(handle) => handle,
@@ -278,22 +352,26 @@ impl<T: Message + 'static> Node<Blocking, Tcp, Idle, T> {
         };
 
         let task_subscribe = handle.spawn(async move {
-            if let Ok(stream) = try_connection(addr).await {
-                if let Ok(stream) = handshake(stream, topic.clone()).await {
-                    loop {
-                        if let Err(e) = run_subscription::<T>(
-                            packet.clone(),
-                            buffer.clone(),
-                            &stream,
-                            data.clone(),
-                        )
-                        .await
-                        {
-                            error!("{:?}", e);
-                        }
+            if let Ok(stream) = TcpConnection::connect(&network_cfg, &node_handshake).await {
+                loop {
+                    match run_subscription::<T>(
+                        packet.clone(),
+                        buffer.clone(),
+                        &stream,
+                        data.clone(),
+                        network_cfg.heartbeat,
+                        &pulse,
+                    )
+                    .await
+                    {
+                        // The Host has hung up
+                        Err(Error::AccessStream) => break,
+                        Err(e) => error!("{:?}", e),
+                        Ok(()) => (),
                     }
                 }
             }
+            pulse.lose();
         });
         self.task_subscribe = Some(task_subscribe);
 
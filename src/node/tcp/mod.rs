@@ -12,7 +12,7 @@ use tokio::time::{sleep, Duration};
 
 use tracing::*;
 
-use std::convert::TryInto;
+use std::convert::{TryFrom, TryInto};
 use std::net::SocketAddr;
 
 use std::marker::{PhantomData, Sync};
@@ -20,14 +20,24 @@ use std::result::Result;
 use std::sync::Arc;
 
 use alloc::vec::Vec;
-use postcard::from_bytes;
+use postcard::{from_bytes, take_from_bytes};
 use serde::{de::DeserializeOwned, Serialize};
 
+use crate::handshake::Handshake;
+use crate::heartbeat::{HeartbeatConfig, Pulse};
 use crate::msg::{GenericMsg, Message, Msg, MsgType};
-use crate::node::network_config::Interface;
+use crate::node::network_config::{Block, Interface, NetworkConfig, Tcp};
 use crate::Error;
 use chrono::{DateTime, Utc};
 
+// TLS stuff
+#[cfg(feature = "tls")]
+use crate::node::quic::client_crypto_config;
+#[cfg(feature = "tls")]
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+#[cfg(feature = "tls")]
+use tokio_rustls::{client::TlsStream, TlsConnector};
+
 // Quic stuff
 #[cfg(feature = "quic")]
 use quinn::{ClientConfig, Connection as QuicConnection, Endpoint};
@@ -62,55 +72,210 @@ pub async fn try_connection(host_addr: SocketAddr) -> Result<TcpStream, Error> {
     }
 }
 
-/// Run the initial Node <=> Host connection handshake
-pub async fn handshake(stream: TcpStream, topic: String) -> Result<TcpStream, Error> {
-    loop {
-        if let Ok(()) = stream.writable().await {
-            match stream.try_write(topic.as_bytes()) {
-                Ok(_n) => {
-                    debug!("{}: Wrote {} bytes to host", topic, _n);
-                    debug!("{}: Successfully connected to host", topic);
-                    break;
-                }
-                Err(e) => {
-                    if e.kind() == std::io::ErrorKind::WouldBlock {
-                    } else {
-                        error!("NODE handshake error: {:?}", e);
-                    }
+/// Run the initial Node <=> Host connection handshake, waiting for the Host to accept it
+pub async fn handshake(stream: TcpStream, handshake: &Handshake) -> Result<TcpStream, Error> {
+    send_msg(&stream, handshake.to_generic()?.as_bytes()?).await?;
+
+    let mut buf = vec![0u8; 1024];
+    let version = crate::handshake::confirm(await_response(&stream, &mut buf).await?)?;
+    debug!(
+        "{}: Successfully connected to host with protocol version {}",
+        handshake.name, version
+    );
+
+    Ok(stream)
+}
+
+/// Node's stream to the Host, encrypted with TLS when the Node is configured with a certificate
+#[derive(Debug)]
+pub enum TcpConnection {
+    Plain(TcpStream),
+    #[cfg(feature = "tls")]
+    Tls(Box<TokioMutex<TlsStream<TcpStream>>>),
+}
+
+impl TcpConnection {
+    /// Connect to the Host and run the handshake, over TLS if `cert_path` is set
+    pub(crate) async fn connect<B: Block>(
+        cfg: &NetworkConfig<B, Tcp>,
+        handshake: &Handshake,
+    ) -> Result<TcpConnection, Error> {
+        let stream = try_connection(cfg.host_addr).await?;
+        #[cfg(feature = "tls")]
+        if cfg.cert_path.is_some() {
+            return tls_handshake(cfg, stream, handshake).await;
+        }
+        Ok(TcpConnection::Plain(
+            self::handshake(stream, handshake).await?,
+        ))
+    }
+
+    /// Local address of the underlying `TcpStream`
+    pub fn local_addr(&self) -> Result<SocketAddr, Error> {
+        match self {
+            TcpConnection::Plain(stream) => Ok(stream.local_addr()?),
+            #[cfg(feature = "tls")]
+            TcpConnection::Tls(stream) => match stream.try_lock() {
+                Ok(stream) => Ok(stream.get_ref().0.local_addr()?),
+                Err(_) => Err(Error::AccessStream),
+            },
+        }
+    }
+
+    /// Send a serialized `GenericMsg` to the Host
+    #[inline]
+    pub async fn send_msg(&self, packet: Vec<u8>) -> Result<(), Error> {
+        match self {
+            TcpConnection::Plain(stream) => send_msg(stream, packet).await,
+            #[cfg(feature = "tls")]
+            TcpConnection::Tls(stream) => {
+                let mut stream = stream.lock().await;
+                stream.write_all(&packet).await?;
+                stream.flush().await?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Wait for the Host's response to a request, skipping any heartbeat answered too late to
+    /// have been counted
+    #[inline]
+    pub async fn await_response(&self, buf: &mut [u8]) -> Result<GenericMsg, Error> {
+        let n = self.read_reply(buf).await?;
+        Ok(from_bytes::<GenericMsg>(&buf[..n])?)
+    }
+
+    /// Wait for the next `GenericMsg` from the Host
+    #[inline]
+    pub async fn recv_msg(&self, buf: &mut [u8]) -> Result<GenericMsg, Error> {
+        let n = self.read_response(buf).await?;
+        Ok(from_bytes::<GenericMsg>(&buf[..n])?)
+    }
+
+    /// Read the Host's reply into `buf` without decoding it, returning its length.
+    ///
+    /// Heartbeats answered after their exchange timed out are skipped, decoding only their
+    /// `MsgType` to tell them apart. A late heartbeat can arrive in the same read as the reply,
+    /// which is then moved to the start of `buf`.
+    #[inline]
+    pub async fn read_reply(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        let mut n = 0;
+        loop {
+            if n == 0 {
+                n = self.read_response(buf).await?;
+            }
+            let (msg_type, _) = take_from_bytes::<MsgType>(&buf[..n])?;
+            if msg_type != MsgType::Heartbeat {
+                return Ok(n);
+            }
+            let (_, rest) = take_from_bytes::<GenericMsg>(&buf[..n])?;
+            let consumed = n - rest.len();
+            buf.copy_within(consumed..n, 0);
+            n -= consumed;
+        }
+    }
+
+    /// Read the next message from the Host into `buf` without decoding it, returning its length
+    #[inline]
+    pub async fn read_response(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        match self {
+            TcpConnection::Plain(stream) => read_response(stream, buf).await,
+            #[cfg(feature = "tls")]
+            TcpConnection::Tls(stream) => {
+                let n = stream.lock().await.read(buf).await?;
+                if n == 0 {
+                    // The Host has closed the connection
+                    return Err(Error::AccessStream);
                 }
+                Ok(n)
             }
-        };
+        }
     }
-    // TO_DO: Is there a better way to do this?
-    // Pause after connection to avoid accidentally including published data in initial handshake
-    sleep(Duration::from_millis(20)).await;
+}
 
-    Ok(stream)
+/// Run the initial Node <=> Host handshake over a TLS session
+#[cfg(feature = "tls")]
+async fn tls_handshake<B: Block>(
+    cfg: &NetworkConfig<B, Tcp>,
+    stream: TcpStream,
+    handshake: &Handshake,
+) -> Result<TcpConnection, Error> {
+    let crypto = client_crypto_config(cfg)?;
+    let server_name = match &cfg.server_name {
+        Some(server_name) => server_name.as_str(),
+        None => return Err(Error::Quic(crate::error::Quic::NoProvidedServerName)),
+    };
+    let server_name = rustls::ServerName::try_from(server_name)
+        .map_err(|_| Error::Quic(crate::error::Quic::InvalidServerName))?;
+
+    let mut stream = TlsConnector::from(Arc::new(crypto))
+        .connect(server_name, stream)
+        .await?;
+    stream
+        .write_all(&handshake.to_generic()?.as_bytes()?)
+        .await?;
+    stream.flush().await?;
+
+    let mut buf = vec![0u8; 1024];
+    let n = stream.read(&mut buf).await?;
+    if n == 0 {
+        return Err(Error::AccessStream);
+    }
+    crate::handshake::confirm(from_bytes(&buf[..n])?)?;
+    debug!(
+        "{}: Successfully connected to host over TLS",
+        handshake.name
+    );
+
+    Ok(TcpConnection::Tls(Box::new(TokioMutex::new(stream))))
 }
 
 /// Send a `GenericMsg` of `MsgType` from the Node to the Host
 #[inline]
 pub async fn send_msg(stream: &TcpStream, packet: Vec<u8>) -> Result<(), Error> {
-    stream.writable().await?;
-
     // Write the request
     // TO_DO: This should be a loop with a maximum number of attempts
     loop {
+        stream.writable().await?;
         match stream.try_write(&packet) {
             Ok(_n) => {
                 // debug!("Node successfully wrote {}-byte request to host",n);
                 break;
             }
-            Err(_e) => {
-                // if e.kind() == std::io::ErrorKind::WouldBlock {}
-                continue;
-            }
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => continue,
+            // e.g. the Host has closed the connection
+            Err(e) => return Err(e.into()),
         }
     }
     Ok(())
 }
 
-/// Set Node to wait for response from Host, with data to be deserialized into `Msg<T>`-type
+/// Exchange heartbeats with the Host every interval over an Active Node's stream, until the
+/// Host hangs up
+pub(crate) async fn heartbeat(
+    stream: Arc<TcpConnection>,
+    buffer: Arc<TokioMutex<Vec<u8>>>,
+    heartbeat: HeartbeatConfig,
+    pulse: Arc<Pulse>,
+) {
+    crate::heartbeat::run(heartbeat, pulse, || {
+        let stream = Arc::clone(&stream);
+        let buffer = Arc::clone(&buffer);
+        async move {
+            // Holding the buffer keeps the exchange from interleaving with the Node's requests
+            let mut buf = buffer.lock().await;
+            stream.send_msg(GenericMsg::heartbeat().as_bytes()?).await?;
+            loop {
+                if stream.recv_msg(&mut buf).await?.msg_type == MsgType::Heartbeat {
+                    return Ok(());
+                }
+            }
+        }
+    })
+    .await
+}
+
+// Set Node to wait for response from Host, with data to be deserialized into `Msg<T>`-type
 // #[tracing::instrument]
 /* #[inline]
 pub async fn await_response<T: Message>(
@@ -153,18 +318,22 @@ pub async fn await_response(
     stream: &TcpStream,
     buf: &mut [u8], //max_buffer_size: usize,
 ) -> Result<GenericMsg, Error> {
+    let n = read_response(stream, buf).await?;
+    Ok(from_bytes::<GenericMsg>(&buf[..n])?)
+}
+
+/// Read the next message from the Host into `buf` without decoding it, returning its length
+#[inline]
+pub async fn read_response(stream: &TcpStream, buf: &mut [u8]) -> Result<usize, Error> {
     // TO_DO: This can be made cleaner
     loop {
         if let Err(e) = stream.readable().await {
             error!("{}", e);
         }
         match stream.try_read(buf) {
-            Ok(0) => continue,
-            Ok(n) => {
-                let bytes = &buf[..n];
-                let msg = from_bytes::<GenericMsg>(bytes)?;
-                return Ok(msg);
-            }
+            // The Host has closed the connection, e.g. after a failed TLS handshake
+            Ok(0) => return Err(Error::AccessStream),
+            Ok(n) => return Ok(n),
             Err(_e) => {
                 // if e.kind() == std::io::ErrorKind::WouldBlock {}
                 debug!("Would block");
@@ -0,0 +1,158 @@
+//! Transport-agnostic interfaces to Nodes.
+//!
+//! Every `Node` flavor implements these traits, so applications can be written once and run
+//! over whichever transport a deployment uses. `Blocking` Nodes implement [`Publisher`],
+//! [`Requester`], and [`Subscriber`], while `Nonblocking` Nodes implement their `Async*`
+//! counterparts.
+//!
+//! ```no_run
+//! use meadow::prelude::*;
+//!
+//! fn report<N: Publisher<f32> + Requester<f32>>(node: &N, battery: f32) -> Result<f32, Error> {
+//!     node.publish(battery)?;
+//!     Ok(node.request()?.data)
+//! }
+//! ```
+
+use crate::error::Error;
+use crate::msg::{Message, Msg};
+use crate::node::network_config::{Blocking, InProcess, Nonblocking, Tcp, Udp};
+use crate::node::{Active, Node, Subscription};
+
+use std::future::Future;
+
+/// Publishes data to a Host on a Node's topic
+pub trait Publisher<T: Message> {
+    /// Send data to the Host on the Node's topic
+    fn publish(&self, val: T) -> Result<(), Error>;
+    /// Send a complete message to the Host on the Node's topic
+    fn publish_msg(&self, msg: Msg<T>) -> Result<(), Error>;
+}
+
+/// Requests data from a Host on a Node's topic
+pub trait Requester<T: Message> {
+    /// Request the latest data on the Node's topic
+    fn request(&self) -> Result<Msg<T>, Error>;
+    /// Request the data published `n` messages before the latest on the Node's topic
+    fn request_nth_back(&self, n: usize) -> Result<Msg<T>, Error>;
+    /// Request the names of all topics on the Host
+    fn topics(&self) -> Result<Msg<Vec<String>>, Error>;
+}
+
+/// Reads the latest data received by a Node's topic subscription
+pub trait Subscriber<T: Message> {
+    /// Get the latest data received on the subscribed topic
+    fn get_subscribed_data(&self) -> Result<Msg<T>, Error>;
+}
+
+/// Publishes data to a Host on a Node's topic, asynchronously
+pub trait AsyncPublisher<T: Message> {
+    /// Send data to the Host on the Node's topic
+    fn publish(&self, val: T) -> impl Future<Output = Result<(), Error>> + Send;
+    /// Send a complete message to the Host on the Node's topic
+    fn publish_msg(&self, msg: Msg<T>) -> impl Future<Output = Result<(), Error>> + Send;
+}
+
+/// Requests data from a Host on a Node's topic, asynchronously
+pub trait AsyncRequester<T: Message> {
+    /// Request the latest data on the Node's topic
+    fn request(&self) -> impl Future<Output = Result<Msg<T>, Error>> + Send;
+    /// Request the data published `n` messages before the latest on the Node's topic
+    fn request_nth_back(&self, n: usize) -> impl Future<Output = Result<Msg<T>, Error>> + Send;
+    /// Request the names of all topics on the Host
+    fn topics(&self) -> impl Future<Output = Result<Msg<Vec<String>>, Error>> + Send;
+}
+
+/// Reads the latest data received by a Node's topic subscription, asynchronously
+pub trait AsyncSubscriber<T: Message> {
+    /// Get the latest data received on the subscribed topic
+    fn get_subscribed_data(&self) -> impl Future<Output = Result<Msg<T>, Error>> + Send;
+}
+
+// Each implementation forwards to the Node's inherent method of the same name, which method
+// resolution prefers over the trait's
+macro_rules! impl_node_traits {
+    ($($(#[$attr:meta])* $interface:ty),* $(,)?) => {
+        $(
+            $(#[$attr])*
+            impl<T: Message + 'static> Publisher<T> for Node<Blocking, $interface, Active, T> {
+                fn publish(&self, val: T) -> Result<(), Error> {
+                    self.publish(val)
+                }
+
+                fn publish_msg(&self, msg: Msg<T>) -> Result<(), Error> {
+                    self.publish_msg(msg)
+                }
+            }
+
+            $(#[$attr])*
+            impl<T: Message + 'static> Requester<T> for Node<Blocking, $interface, Active, T> {
+                fn request(&self) -> Result<Msg<T>, Error> {
+                    self.request()
+                }
+
+                fn request_nth_back(&self, n: usize) -> Result<Msg<T>, Error> {
+                    self.request_nth_back(n)
+                }
+
+                fn topics(&self) -> Result<Msg<Vec<String>>, Error> {
+                    self.topics()
+                }
+            }
+
+            $(#[$attr])*
+            impl<T: Message + 'static> Subscriber<T> for Node<Blocking, $interface, Subscription, T> {
+                fn get_subscribed_data(&self) -> Result<Msg<T>, Error> {
+                    self.get_subscribed_data()
+                }
+            }
+
+            $(#[$attr])*
+            impl<T: Message + 'static> AsyncPublisher<T> for Node<Nonblocking, $interface, Active, T> {
+                async fn publish(&self, val: T) -> Result<(), Error> {
+                    self.publish(val).await
+                }
+
+                async fn publish_msg(&self, msg: Msg<T>) -> Result<(), Error> {
+                    self.publish_msg(msg).await
+                }
+            }
+
+            $(#[$attr])*
+            impl<T: Message + 'static> AsyncRequester<T> for Node<Nonblocking, $interface, Active, T> {
+                async fn request(&self) -> Result<Msg<T>, Error> {
+                    self.request().await
+                }
+
+                async fn request_nth_back(&self, n: usize) -> Result<Msg<T>, Error> {
+                    self.request_nth_back(n).await
+                }
+
+                async fn topics(&self) -> Result<Msg<Vec<String>>, Error> {
+                    self.topics().await
+                }
+            }
+
+            $(#[$attr])*
+            impl<T: Message + 'static> AsyncSubscriber<T>
+                for Node<Nonblocking, $interface, Subscription, T>
+            {
+                async fn get_subscribed_data(&self) -> Result<Msg<T>, Error> {
+                    self.get_subscribed_data().await
+                }
+            }
+        )*
+    };
+}
+
+impl_node_traits!(
+    Tcp,
+    Udp,
+    InProcess,
+    #[cfg(unix)]
+    crate::node::network_config::Uds,
+    #[cfg(all(unix, feature = "shm"))]
+    crate::node::network_config::Shm,
+    #[cfg(feature = "quic")]
+    crate::node::network_config::Quic,
+);
@@ -7,8 +7,11 @@ use std::marker::PhantomData;
 use std::net::SocketAddr;
 use std::ops::DerefMut;
 use std::sync::Arc;
+use tokio::net::UdpSocket;
+use tokio::runtime::Handle;
 use tokio::sync::Mutex as TokioMutex;
 
+use crate::heartbeat::Pulse;
 use crate::node::udp::*;
 
 use chrono::Utc;
@@ -28,15 +31,23 @@ impl<T: Message> From<Node<Nonblocking, Udp, Idle, T>> for Node<Nonblocking, Udp
             runtime: node.runtime,
             rt_handle: node.rt_handle,
             stream: node.stream,
+            #[cfg(unix)]
+            uds_stream: node.uds_stream,
+            #[cfg(all(unix, feature = "shm"))]
+            shm: node.shm,
             topic: node.topic,
             socket: node.socket,
+            udp_cipher: node.udp_cipher,
+            in_process: node.in_process,
             buffer: node.buffer,
             #[cfg(feature = "quic")]
             endpoint: node.endpoint,
             #[cfg(feature = "quic")]
             connection: node.connection,
             subscription_data: node.subscription_data,
+            host_pulse: node.host_pulse,
             task_subscribe: None,
+            task_heartbeat: None,
         }
     }
 }
@@ -45,67 +56,115 @@ use crate::node::Block;
 use std::fmt::Debug;
 
 impl<T: Message + 'static, B: Block + Debug> Node<B, Udp, Active, T> {
-    #[tracing::instrument]
-    #[inline]
-    async fn publish_internal(&self, val: T) -> Result<(), Error> {
-        let packet = Msg::new(MsgType::Set, self.topic.clone(), val)
-            .to_generic()?
-            .as_bytes()?;
+    /// Exchange heartbeats with the Host on `handle`, if the Node is configured to
+    pub(crate) fn start_heartbeat(&mut self, handle: &Handle) {
+        let (socket, heartbeat) = match (&self.socket, self.cfg.network_cfg.heartbeat) {
+            (Some(socket), Some(heartbeat)) => (Arc::clone(socket), heartbeat),
+            _ => return,
+        };
+        self.host_pulse = Arc::new(Pulse::new(Some(heartbeat)));
+        let task = handle.spawn(crate::node::udp::heartbeat(
+            socket,
+            self.cfg.network_cfg.host_addr,
+            self.udp_cipher.clone(),
+            Arc::clone(&self.buffer),
+            self.handshake(),
+            self.cfg.network_cfg.send_tries,
+            heartbeat,
+            Arc::clone(&self.host_pulse),
+        ));
+        self.task_heartbeat = Some(task.into());
+    }
+
+    /// Shake hands with the Host again after it has forgotten the Node, e.g. by restarting
+    async fn rehandshake(&self, socket: &UdpSocket, buf: &mut [u8]) -> Result<(), Error> {
+        info!("{}: The Host asked for a new handshake", &self.topic);
+        shake_hands(
+            socket,
+            &self.handshake(),
+            self.cfg.network_cfg.host_addr,
+            buf,
+            self.udp_cipher.as_deref(),
+            self.cfg.network_cfg.send_tries,
+        )
+        .await
+    }
 
+    /// Send a publish, first shaking hands again if the Host has said it no longer knows the Node
+    async fn send_publish(&self, packet: Vec<u8>) -> Result<(), Error> {
         let socket = match self.socket.as_ref() {
             Some(socket) => socket,
             None => return Err(Error::AccessSocket),
         };
+        let cipher = self.udp_cipher.as_deref();
 
-        socket
-            .send_to(&packet, self.cfg.network_cfg.host_addr)
-            .await?;
+        let mut buffer = self.buffer.lock().await;
+        if forgotten(socket, &mut buffer, cipher) {
+            self.rehandshake(socket, &mut buffer).await?;
+        }
+        send_msg(socket, packet, self.cfg.network_cfg.host_addr, cipher).await?;
         Ok(())
     }
 
+    #[tracing::instrument]
+    #[inline]
+    async fn publish_internal(&self, val: T) -> Result<(), Error> {
+        let packet = Msg::new(MsgType::Set, self.topic.clone(), val)
+            .to_generic_encoded(self.cfg.encoding)?
+            .compressed(self.cfg.compression)
+            .as_bytes()?;
+        self.send_publish(packet).await
+    }
+
     #[tracing::instrument]
     #[inline]
     async fn publish_msg_internal(&self, msg: Msg<T>) -> Result<(), Error> {
-        let packet = msg.to_generic()?.as_bytes()?;
+        let packet = msg
+            .to_generic_encoded(self.cfg.encoding)?
+            .compressed(self.cfg.compression)
+            .as_bytes()?;
+        self.send_publish(packet).await
+    }
+
+    /// Send a request and wait for the Host's answer, shaking hands again and resending the
+    /// request if the Host has forgotten the Node
+    async fn send_request(&self, packet: Vec<u8>) -> Result<GenericMsg, Error> {
         let socket = match self.socket.as_ref() {
             Some(socket) => socket,
             None => return Err(Error::AccessSocket),
         };
+        let cipher = self.udp_cipher.as_deref();
+        let host_addr = self.cfg.network_cfg.host_addr;
 
-        socket
-            .send_to(&packet, self.cfg.network_cfg.host_addr)
-            .await?;
-        Ok(())
+        let mut buffer = self.buffer.lock().await;
+        // Anything left over, e.g. a shutdown notice, would otherwise be taken for the answer
+        if forgotten(socket, &mut buffer, cipher) {
+            self.rehandshake(socket, &mut buffer).await?;
+        }
+        send_msg(socket, packet.clone(), host_addr, cipher).await?;
+        let response = await_response(socket, &mut buffer, cipher).await?;
+        if !handshake_required(&response) {
+            return Ok(response);
+        }
+        self.rehandshake(socket, &mut buffer).await?;
+        send_msg(socket, packet, host_addr, cipher).await?;
+        await_response(socket, &mut buffer, cipher).await
     }
 
     #[tracing::instrument]
     #[inline]
     async fn request_nth_back_internal(&self, n: usize) -> Result<Msg<T>, Error> {
         let packet = GenericMsg::get_nth::<T>(self.topic.clone(), n).as_bytes()?;
-        let buffer = self.buffer.clone();
-
-        if let Some(socket) = &self.socket {
-            send_msg(socket, packet, self.cfg.network_cfg.host_addr).await?;
-            let msg = await_response(socket, buffer).await?.try_into()?;
-            Ok(msg)
-        } else {
-            Err(Error::AccessSocket)
-        }
+        let msg = self.send_request(packet).await?.try_into()?;
+        Ok(msg)
     }
 
     #[tracing::instrument]
     #[inline]
     async fn topics_internal(&self) -> Result<Msg<Vec<String>>, Error> {
         let packet = GenericMsg::topics().as_bytes()?;
-        let buffer = self.buffer.clone();
-
-        if let Some(socket) = &self.socket {
-            send_msg(socket, packet, self.cfg.network_cfg.host_addr).await?;
-            let msg = await_response(socket, buffer).await?.try_into()?;
-            Ok(msg)
-        } else {
-            Err(Error::AccessSocket)
-        }
+        let msg = self.send_request(packet).await?.try_into()?;
+        Ok(msg)
     }
 }
 
@@ -129,6 +188,14 @@ impl<T: Message + 'static> Node<Nonblocking, Udp, Active, T> {
         Ok(msg)
     }
 
+    /// Request n'th data from host on Node's assigned topic
+    #[tracing::instrument]
+    #[inline]
+    pub async fn request_nth_back(&self, n: usize) -> Result<Msg<T>, Error> {
+        let msg = self.request_nth_back_internal(n).await?;
+        Ok(msg)
+    }
+
     #[tracing::instrument]
     #[inline]
     pub async fn topics(&self) -> Result<Msg<Vec<String>>, Error> {
@@ -150,15 +217,23 @@ impl<T: Message> From<Node<Blocking, Udp, Idle, T>> for Node<Blocking, Udp, Acti
             runtime: node.runtime,
             rt_handle: node.rt_handle,
             stream: node.stream,
+            #[cfg(unix)]
+            uds_stream: node.uds_stream,
+            #[cfg(all(unix, feature = "shm"))]
+            shm: node.shm,
             topic: node.topic,
             socket: node.socket,
+            udp_cipher: node.udp_cipher,
+            in_process: node.in_process,
             buffer: node.buffer,
             #[cfg(feature = "quic")]
             endpoint: node.endpoint,
             #[cfg(feature = "quic")]
             connection: node.connection,
             subscription_data: node.subscription_data,
+            host_pulse: node.host_pulse,
             task_subscribe: None,
+            task_heartbeat: None,
         }
     }
 }
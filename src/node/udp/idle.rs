@@ -2,10 +2,15 @@ extern crate alloc;
 use crate::node::network_config::{Nonblocking, Udp};
 use crate::Error;
 
+use crate::discovery::Transport;
+use crate::heartbeat::{HeartbeatConfig, Pulse, Ticker};
+use crate::networks::unspecified_addr;
 use crate::node::udp::send_msg;
 use crate::node::*;
+use crate::psk::DatagramCipher;
 
 use tokio::net::UdpSocket;
+use tokio::runtime::Handle;
 use tokio::sync::Mutex as TokioMutex;
 use tokio::time::{sleep, Duration};
 
@@ -31,15 +36,23 @@ impl<T: Message> From<Node<Nonblocking, Udp, Idle, T>> for Node<Nonblocking, Udp
             runtime: node.runtime,
             rt_handle: node.rt_handle,
             stream: node.stream,
+            #[cfg(unix)]
+            uds_stream: node.uds_stream,
+            #[cfg(all(unix, feature = "shm"))]
+            shm: node.shm,
             topic: node.topic,
             socket: node.socket,
+            udp_cipher: node.udp_cipher,
+            in_process: node.in_process,
             buffer: node.buffer,
             #[cfg(feature = "quic")]
             endpoint: node.endpoint,
             #[cfg(feature = "quic")]
             connection: node.connection,
             subscription_data: node.subscription_data,
+            host_pulse: node.host_pulse,
             task_subscribe: None,
+            task_heartbeat: None,
         }
     }
 }
@@ -47,20 +60,29 @@ impl<T: Message> From<Node<Nonblocking, Udp, Idle, T>> for Node<Nonblocking, Udp
 impl<T: Message + 'static> Node<Nonblocking, Udp, Idle, T> {
     #[tracing::instrument(skip(self))]
     pub async fn activate(mut self) -> Result<Node<Nonblocking, Udp, Active, T>, Error> {
-        match {
-            match UdpSocket::bind("[::]:0").await {
-                Ok(socket) => {
-                    info!("Bound to socket: {:?}", &socket);
-                    Ok(socket)
-                }
-                Err(_e) => Err(Error::AccessSocket),
+        self.discover_host_addr(Transport::Udp).await?;
+        let host_addr = self.cfg.network_cfg.host_addr;
+        let socket = match UdpSocket::bind(unspecified_addr(&host_addr)).await {
+            Ok(socket) => {
+                info!("Bound to socket: {:?}", &socket);
+                socket
             }
-        } {
-            Ok(socket) => self.socket = Some(socket),
-            Err(e) => return Err(e),
+            Err(_e) => return Err(Error::AccessSocket),
         };
+        udp::handshake(
+            &socket,
+            &self.handshake(),
+            host_addr,
+            self.buffer.clone(),
+            self.udp_cipher.as_deref(),
+            self.cfg.network_cfg.send_tries,
+        )
+        .await?;
+        self.socket = Some(Arc::new(socket));
 
-        Ok(Node::<Nonblocking, Udp, Active, T>::from(self))
+        let mut node = Node::<Nonblocking, Udp, Active, T>::from(self);
+        node.start_heartbeat(&Handle::current());
+        Ok(node)
     }
 
     #[tracing::instrument(skip(self))]
@@ -68,17 +90,38 @@ impl<T: Message + 'static> Node<Nonblocking, Udp, Idle, T> {
         mut self,
         rate: Duration,
     ) -> Result<Node<Nonblocking, Udp, Subscription, T>, Error> {
+        self.discover_host_addr(Transport::Udp).await?;
         let topic = self.topic.clone();
         let subscription_data: Arc<TokioMutex<Option<Msg<T>>>> = Arc::new(TokioMutex::new(None));
         let data = Arc::clone(&subscription_data);
         let addr = self.cfg.network_cfg.host_addr;
         let buffer = self.buffer.clone();
+        let cipher = self.udp_cipher.clone();
+        let node_handshake = self.handshake();
+        let tries = self.cfg.network_cfg.send_tries;
+        let heartbeat = self.cfg.network_cfg.heartbeat;
+        let pulse = Arc::new(Pulse::new(heartbeat));
+        self.host_pulse = Arc::clone(&pulse);
 
         let packet = GenericMsg::subscribe(topic, rate)?;
 
         let task_subscribe = tokio::spawn(async move {
-            if let Ok(socket) = UdpSocket::bind("[::]:0").await {
+            if let Ok(socket) = UdpSocket::bind(unspecified_addr(&addr)).await {
                 info!("Bound to socket: {:?}", &socket);
+                if let Err(e) = udp::handshake(
+                    &socket,
+                    &node_handshake,
+                    addr,
+                    buffer.clone(),
+                    cipher.as_deref(),
+                    tries,
+                )
+                .await
+                {
+                    error!("{:?}", e);
+                    pulse.lose();
+                    return;
+                }
                 loop {
                     if let Err(e) = run_subscription::<T>(
                         packet.clone(),
@@ -86,6 +129,11 @@ impl<T: Message + 'static> Node<Nonblocking, Udp, Idle, T> {
                         &socket,
                         data.clone(),
                         addr,
+                        cipher.as_deref(),
+                        &node_handshake,
+                        tries,
+                        heartbeat,
+                        &pulse,
                     )
                     .await
                     {
@@ -106,19 +154,44 @@ impl<T: Message + 'static> Node<Nonblocking, Udp, Idle, T> {
 }
 
 #[tracing::instrument(skip_all)]
+#[allow(clippy::too_many_arguments)]
 async fn run_subscription<T: Message>(
     packet: GenericMsg,
     buffer: Arc<TokioMutex<Vec<u8>>>,
     socket: &UdpSocket,
     data: Arc<TokioMutex<Option<Msg<T>>>>,
     addr: SocketAddr,
+    cipher: Option<&DatagramCipher>,
+    node_handshake: &Handshake,
+    tries: usize,
+    heartbeat: Option<HeartbeatConfig>,
+    pulse: &Pulse,
 ) -> Result<(), Error> {
-    udp::send_msg(socket, packet.as_bytes()?, addr).await?;
+    let packet = packet.as_bytes()?;
+    udp::send_msg(socket, packet.clone(), addr, cipher).await?;
 
+    // Heartbeats keep the Host from dropping the subscription
+    let mut ticker = Ticker::new(heartbeat);
+    let mut buffer = buffer.lock().await;
     loop {
-        let msg: Msg<T> = udp::await_response(socket, buffer.clone())
-            .await?
-            .try_into()?;
+        let msg = tokio::select! {
+            msg = udp::recv_msg(socket, &mut buffer, cipher) => msg?,
+            _ = ticker.tick() => {
+                udp::send_msg(socket, GenericMsg::heartbeat().as_bytes()?, addr, cipher).await?;
+                continue;
+            }
+        };
+        pulse.beat();
+        if msg.msg_type == MsgType::Heartbeat {
+            continue;
+        }
+        // The Host has forgotten the subscription along with the Node, so it's made again
+        if udp::handshake_required(&msg) {
+            udp::shake_hands(socket, node_handshake, addr, &mut buffer, cipher, tries).await?;
+            udp::send_msg(socket, packet.clone(), addr, cipher).await?;
+            continue;
+        }
+        let msg: Msg<T> = msg.try_into()?;
         info!("UDP Msg<T> received: {:?}", &msg);
         let delta = Utc::now() - msg.timestamp;
         if delta <= chrono::Duration::zero() {
@@ -145,15 +218,23 @@ impl<T: Message> From<Node<Blocking, Udp, Idle, T>> for Node<Blocking, Udp, Subs
             runtime: node.runtime,
             rt_handle: node.rt_handle,
             stream: node.stream,
+            #[cfg(unix)]
+            uds_stream: node.uds_stream,
+            #[cfg(all(unix, feature = "shm"))]
+            shm: node.shm,
             topic: node.topic,
             socket: node.socket,
+            udp_cipher: node.udp_cipher,
+            in_process: node.in_process,
             buffer: node.buffer,
             #[cfg(feature = "quic")]
             endpoint: node.endpoint,
             #[cfg(feature = "quic")]
             connection: node.connection,
             subscription_data: node.subscription_data,
+            host_pulse: node.host_pulse,
             task_subscribe: None,
+            task_heartbeat: None,
         }
     }
 }
@@ -161,25 +242,45 @@ impl<T: Message> From<Node<Blocking, Udp, Idle, T>> for Node<Blocking, Udp, Subs
 impl<T: Message + 'static> Node<Blocking, Udp, Idle, T> {
     #[tracing::instrument(skip(self))]
     pub fn activate(mut self) -> Result<Node<Blocking, Udp, Active, T>, Error> {
+        self.discover_host_addr_blocking(Transport::Udp)?;
+        let host_addr = self.cfg.network_cfg.host_addr;
+        let node_handshake = self.handshake();
+        let buffer = self.buffer.clone();
+        let cipher = self.udp_cipher.clone();
+        let tries = self.cfg.network_cfg.send_tries;
         let handle = match &self.rt_handle {
             Some(handle) => handle,
             None => return Err(Error::HandleAccess),
         };
 
         match handle.block_on(async move {
-            match UdpSocket::bind("[::]:0").await {
+            let socket = match UdpSocket::bind(unspecified_addr(&host_addr)).await {
                 Ok(socket) => {
                     info!("Bound to socket: {:?}", &socket);
-                    Ok(socket)
+                    socket
                 }
-                Err(_e) => Err(Error::AccessSocket),
-            }
+                Err(_e) => return Err(Error::AccessSocket),
+            };
+            udp::handshake(
+                &socket,
+                &node_handshake,
+                host_addr,
+                buffer,
+                cipher.as_deref(),
+                tries,
+            )
+            .await?;
+            Ok(socket)
         }) {
-            Ok(socket) => self.socket = Some(socket),
+            Ok(socket) => self.socket = Some(Arc::new(socket)),
             Err(e) => return Err(e),
         };
 
-        Ok(Node::<Blocking, Udp, Active, T>::from(self))
+        let mut node = Node::<Blocking, Udp, Active, T>::from(self);
+        if let Some(handle) = node.rt_handle.clone() {
+            node.start_heartbeat(&handle);
+        }
+        Ok(node)
     }
 
     #[tracing::instrument(skip(self))]
@@ -187,11 +288,18 @@ impl<T: Message + 'static> Node<Blocking, Udp, Idle, T> {
         mut self,
         rate: Duration,
     ) -> Result<Node<Blocking, Udp, Subscription, T>, Error> {
+        self.discover_host_addr_blocking(Transport::Udp)?;
         let topic = self.topic.clone();
         let subscription_data: Arc<TokioMutex<Option<Msg<T>>>> = Arc::new(TokioMutex::new(None));
         let data = Arc::clone(&subscription_data);
         let addr = self.cfg.network_cfg.host_addr;
         let buffer = self.buffer.clone();
+        let cipher = self.udp_cipher.clone();
+        let node_handshake = self.handshake();
+        let tries = self.cfg.network_cfg.send_tries;
+        let heartbeat = self.cfg.network_cfg.heartbeat;
+        let pulse = Arc::new(Pulse::new(heartbeat));
+        self.host_pulse = Arc::clone(&pulse);
 
         let packet = GenericMsg::subscribe(topic, rate)?;
 
@@ -201,8 +309,22 @@ impl<T: Message + 'static> Node<Blocking, Udp, Idle, T> {
         };
 
         let task_subscribe = handle.spawn(async move {
-            if let Ok(socket) = UdpSocket::bind("[::]:0").await {
+            if let Ok(socket) = UdpSocket::bind(unspecified_addr(&addr)).await {
                 info!("Bound to socket: {:?}", &socket);
+                if let Err(e) = udp::handshake(
+                    &socket,
+                    &node_handshake,
+                    addr,
+                    buffer.clone(),
+                    cipher.as_deref(),
+                    tries,
+                )
+                .await
+                {
+                    error!("{:?}", e);
+                    pulse.lose();
+                    return;
+                }
                 loop {
                     if let Err(e) = run_subscription::<T>(
                         packet.clone(),
@@ -210,6 +332,11 @@ impl<T: Message + 'static> Node<Blocking, Udp, Idle, T> {
                         &socket,
                         data.clone(),
                         addr,
+                        cipher.as_deref(),
+                        &node_handshake,
+                        tries,
+                        heartbeat,
+                        &pulse,
                     )
                     .await
                     {
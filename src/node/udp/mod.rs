@@ -2,18 +2,25 @@ mod active;
 mod idle;
 mod subscription;
 
+use crate::handshake::Handshake;
+use crate::heartbeat::{HeartbeatConfig, Pulse};
 use crate::msg::{GenericMsg, Message, Msg, MsgType};
 use std::convert::TryInto;
 use std::sync::Arc;
 use tokio::net::UdpSocket;
 use tokio::sync::Mutex as TokioMutex;
+use tokio::time::{timeout, Duration};
 
 use tracing::*;
 
-use crate::error::Error;
+use crate::error::{Error, HostError};
+use crate::psk::DatagramCipher;
 use std::io::{Error as IoError, ErrorKind};
 use std::net::SocketAddr;
 
+/// How long to wait for the Host to answer each attempt at a handshake
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_millis(250);
+
 /* #[inline]
 #[tracing::instrument(skip(buffer))]
 pub async fn await_response<T: Message>(
@@ -56,17 +63,34 @@ pub async fn await_response<T: Message>(
     }
 } */
 
+/// Wait for the Host's response to a request, skipping any heartbeat answered too late to have
+/// been counted
 #[inline]
-#[tracing::instrument(skip(buffer))]
+#[tracing::instrument(skip(buf))]
 pub async fn await_response(
     socket: &UdpSocket,
-    buffer: Arc<TokioMutex<Vec<u8>>>,
+    buf: &mut [u8],
+    cipher: Option<&DatagramCipher>,
 ) -> Result<GenericMsg, Error> {
-    socket.readable().await?;
     loop {
-        let mut buf = buffer.lock().await;
+        let msg = recv_msg(socket, buf, cipher).await?;
+        if msg.msg_type != MsgType::Heartbeat {
+            return Ok(msg);
+        }
+    }
+}
 
-        match socket.recv(&mut buf).await {
+/// Wait for the next `GenericMsg` from the Host
+#[inline]
+#[tracing::instrument(skip(buf))]
+pub async fn recv_msg(
+    socket: &UdpSocket,
+    buf: &mut [u8],
+    cipher: Option<&DatagramCipher>,
+) -> Result<GenericMsg, Error> {
+    socket.readable().await?;
+    loop {
+        match socket.recv(buf).await {
             Ok(0) => {
                 info!("await_response received zero bytes");
                 continue;
@@ -74,7 +98,17 @@ pub async fn await_response(
             Ok(n) => {
                 // info!("await_response received {} bytes", n);
                 let bytes = &buf[..n];
-                let msg = postcard::from_bytes::<GenericMsg>(bytes)?;
+                let msg = match cipher {
+                    Some(cipher) => match cipher.open(bytes) {
+                        Ok(plaintext) => postcard::from_bytes::<GenericMsg>(&plaintext)?,
+                        Err(e) => {
+                            // Keep waiting for the Host's actual response
+                            warn!("Dropping {}-byte datagram: {}", n, e);
+                            continue;
+                        }
+                    },
+                    None => postcard::from_bytes::<GenericMsg>(bytes)?,
+                };
                 return Ok(msg);
             }
             Err(e) => {
@@ -87,14 +121,136 @@ pub async fn await_response(
     }
 }
 
+/// Run the initial Node <=> Host handshake, resending it up to `tries` times until the Host replies
+#[tracing::instrument(skip(socket, buffer, cipher))]
+pub(crate) async fn handshake(
+    socket: &UdpSocket,
+    handshake: &Handshake,
+    host_addr: SocketAddr,
+    buffer: Arc<TokioMutex<Vec<u8>>>,
+    cipher: Option<&DatagramCipher>,
+    tries: usize,
+) -> Result<(), Error> {
+    let mut buf = buffer.lock().await;
+    shake_hands(socket, handshake, host_addr, &mut buf, cipher, tries).await
+}
+
+/// Handshake with the Host using a buffer the caller already holds
+#[tracing::instrument(skip(socket, buf, cipher))]
+pub(crate) async fn shake_hands(
+    socket: &UdpSocket,
+    handshake: &Handshake,
+    host_addr: SocketAddr,
+    buf: &mut [u8],
+    cipher: Option<&DatagramCipher>,
+    tries: usize,
+) -> Result<(), Error> {
+    let packet = handshake.to_generic()?.as_bytes()?;
+    for _ in 0..tries {
+        send_msg(socket, packet.clone(), host_addr, cipher).await?;
+        match timeout(HANDSHAKE_TIMEOUT, await_response(socket, buf, cipher)).await {
+            Ok(response) => {
+                let version = crate::handshake::confirm(response?)?;
+                debug!("{}: Speaking protocol version {}", handshake.name, version);
+                return Ok(());
+            }
+            Err(_elapsed) => debug!("{}: Resending handshake to {}", handshake.name, host_addr),
+        }
+    }
+    Err(Error::HandshakeTimeout)
+}
+
+/// Whether the Host answered with `HostError::HandshakeRequired`, having forgotten the Node,
+/// e.g. after restarting
+pub(crate) fn handshake_required(msg: &GenericMsg) -> bool {
+    matches!(
+        msg.msg_type,
+        MsgType::Result(Err(Error::HostOperation(HostError::HandshakeRequired)))
+    )
+}
+
+/// Read whatever the Host has sent without being asked since the Node last listened, returning
+/// whether any of it says the Node has to shake hands again.
+///
+/// Publishing isn't acknowledged, so this is how a publisher learns that the Host has forgotten it.
+pub(crate) fn forgotten(
+    socket: &UdpSocket,
+    buf: &mut [u8],
+    cipher: Option<&DatagramCipher>,
+) -> bool {
+    let mut forgotten = false;
+    while let Ok(n) = socket.try_recv(buf) {
+        let msg = match cipher {
+            Some(cipher) => cipher
+                .open(&buf[..n])
+                .ok()
+                .and_then(|plaintext| postcard::from_bytes::<GenericMsg>(&plaintext).ok()),
+            None => postcard::from_bytes::<GenericMsg>(&buf[..n]).ok(),
+        };
+        forgotten |= msg.as_ref().is_some_and(handshake_required);
+    }
+    forgotten
+}
+
 #[inline]
 async fn send_msg(
     socket: &UdpSocket,
     packet: Vec<u8>,
     host_addr: SocketAddr,
+    cipher: Option<&DatagramCipher>,
 ) -> Result<usize, Error> {
+    let packet = match cipher {
+        Some(cipher) => cipher.seal(&packet)?,
+        None => packet,
+    };
     socket.writable().await?;
     // NOTE: This used to be done 10 times in a row to make sure it got through
     let n = socket.send_to(&packet, host_addr).await?;
     Ok(n)
 }
+
+/// Exchange heartbeats with the Host every interval from an Active Node's socket, shaking hands
+/// again if the Host has forgotten the Node
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn heartbeat(
+    socket: Arc<UdpSocket>,
+    host_addr: SocketAddr,
+    cipher: Option<Arc<DatagramCipher>>,
+    buffer: Arc<TokioMutex<Vec<u8>>>,
+    node_handshake: Handshake,
+    tries: usize,
+    heartbeat: HeartbeatConfig,
+    pulse: Arc<Pulse>,
+) {
+    crate::heartbeat::run(heartbeat, pulse, || {
+        let socket = Arc::clone(&socket);
+        let cipher = cipher.clone();
+        let buffer = Arc::clone(&buffer);
+        let node_handshake = node_handshake.clone();
+        async move {
+            // Holding the buffer keeps the exchange from interleaving with the Node's requests
+            let mut buf = buffer.lock().await;
+            let packet = GenericMsg::heartbeat().as_bytes()?;
+            send_msg(&socket, packet, host_addr, cipher.as_deref()).await?;
+            loop {
+                let msg = recv_msg(&socket, &mut buf, cipher.as_deref()).await?;
+                if handshake_required(&msg) {
+                    let cipher = cipher.as_deref();
+                    return shake_hands(
+                        &socket,
+                        &node_handshake,
+                        host_addr,
+                        &mut buf,
+                        cipher,
+                        tries,
+                    )
+                    .await;
+                }
+                if msg.msg_type == MsgType::Heartbeat {
+                    return Ok(());
+                }
+            }
+        }
+    })
+    .await
+}
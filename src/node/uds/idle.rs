@@ -0,0 +1,301 @@
+extern crate alloc;
+use crate::prelude::*;
+
+use crate::node::network_config::{Blocking, Nonblocking, Uds};
+use crate::node::uds::{await_response, handshake, send_msg, try_connection};
+use crate::node::*;
+
+use tokio::net::UnixStream;
+use tokio::sync::Mutex as TokioMutex;
+use tokio::time::Duration;
+
+use tracing::*;
+
+use std::convert::TryInto;
+use std::ops::DerefMut;
+use std::path::PathBuf;
+use std::result::Result;
+use std::sync::Arc;
+
+use alloc::vec::Vec;
+use std::marker::PhantomData;
+
+impl<T: Message> From<Node<Nonblocking, Uds, Idle, T>> for Node<Nonblocking, Uds, Active, T> {
+    fn from(node: Node<Nonblocking, Uds, Idle, T>) -> Self {
+        Self {
+            __state: PhantomData,
+            __data_type: PhantomData,
+            cfg: node.cfg,
+            runtime: node.runtime,
+            rt_handle: node.rt_handle,
+            stream: node.stream,
+            uds_stream: node.uds_stream,
+            #[cfg(all(unix, feature = "shm"))]
+            shm: node.shm,
+            topic: node.topic,
+            socket: node.socket,
+            udp_cipher: node.udp_cipher,
+            in_process: node.in_process,
+            buffer: node.buffer,
+            #[cfg(feature = "quic")]
+            endpoint: node.endpoint,
+            #[cfg(feature = "quic")]
+            connection: node.connection,
+            subscription_data: node.subscription_data,
+            host_pulse: node.host_pulse,
+            task_subscribe: None,
+            task_heartbeat: None,
+        }
+    }
+}
+
+impl<T: Message> From<Node<Nonblocking, Uds, Idle, T>> for Node<Nonblocking, Uds, Subscription, T> {
+    fn from(node: Node<Nonblocking, Uds, Idle, T>) -> Self {
+        Self {
+            __state: PhantomData,
+            __data_type: PhantomData,
+            cfg: node.cfg,
+            runtime: node.runtime,
+            rt_handle: node.rt_handle,
+            stream: node.stream,
+            uds_stream: node.uds_stream,
+            #[cfg(all(unix, feature = "shm"))]
+            shm: node.shm,
+            topic: node.topic,
+            socket: node.socket,
+            udp_cipher: node.udp_cipher,
+            in_process: node.in_process,
+            buffer: node.buffer,
+            #[cfg(feature = "quic")]
+            endpoint: node.endpoint,
+            #[cfg(feature = "quic")]
+            connection: node.connection,
+            subscription_data: node.subscription_data,
+            host_pulse: node.host_pulse,
+            task_subscribe: None,
+            task_heartbeat: None,
+        }
+    }
+}
+
+/// Look up the Host's socket path from the Node's network configuration
+fn socket_path<B: Block>(cfg: &NetworkConfig<B, Uds>) -> Result<PathBuf, Error> {
+    match &cfg.socket_path {
+        Some(path) => Ok(path.clone()),
+        None => Err(Error::NoSocketPath),
+    }
+}
+
+impl<T: Message + 'static> Node<Nonblocking, Uds, Idle, T> {
+    /// Attempt connection from the Node to the Host listening on the configured socket path
+    #[tracing::instrument(skip_all)]
+    pub async fn activate(mut self) -> Result<Node<Nonblocking, Uds, Active, T>, Error> {
+        let path = socket_path(&self.cfg.network_cfg)?;
+        let node_handshake = self.handshake();
+
+        let stream = try_connection(&path).await?;
+        let stream = handshake(stream, &node_handshake).await?;
+        debug!(
+            "Established Node<=>Host UDS stream: {:?}",
+            stream.local_addr()
+        );
+        self.uds_stream = Some(stream);
+
+        Ok(Node::<Nonblocking, Uds, Active, T>::from(self))
+    }
+
+    #[tracing::instrument]
+    pub async fn subscribe(
+        mut self,
+        rate: Duration,
+    ) -> Result<Node<Nonblocking, Uds, Subscription, T>, Error> {
+        let path = socket_path(&self.cfg.network_cfg)?;
+        let topic = self.topic.clone();
+        let node_handshake = self.handshake();
+
+        let subscription_data: Arc<TokioMutex<Option<Msg<T>>>> = Arc::new(TokioMutex::new(None));
+        let data = Arc::clone(&subscription_data);
+
+        let buffer = self.buffer.clone();
+        let packet = GenericMsg::subscribe(&topic, rate)?;
+
+        let task_subscribe = tokio::spawn(async move {
+            if let Ok(stream) = try_connection(&path).await {
+                if let Ok(stream) = handshake(stream, &node_handshake).await {
+                    if let Err(e) =
+                        run_subscription::<T>(packet, buffer, &stream, data.clone()).await
+                    {
+                        error!("{:?}", e);
+                    }
+                }
+            }
+        });
+        self.task_subscribe = Some(task_subscribe);
+
+        let mut subscription_node = Node::<Nonblocking, Uds, Subscription, T>::from(self);
+        subscription_node.subscription_data = subscription_data;
+
+        Ok(subscription_node)
+    }
+}
+
+async fn run_subscription<T: Message>(
+    packet: GenericMsg,
+    buffer: Arc<TokioMutex<Vec<u8>>>,
+    stream: &UnixStream,
+    data: Arc<TokioMutex<Option<Msg<T>>>>,
+) -> Result<(), Error> {
+    send_msg(stream, packet.as_bytes()?).await?;
+
+    let mut buffer = buffer.lock().await;
+    loop {
+        // Unlike TCP, a failed read on a Unix socket means the Host has gone away
+        let msg = await_response(stream, &mut buffer).await?;
+        match TryInto::<Msg<T>>::try_into(msg) {
+            Ok(msg) => {
+                let mut data = data.lock().await;
+                match data.deref_mut() {
+                    Some(existing) => {
+                        let delta = msg.timestamp - existing.timestamp;
+                        if delta <= chrono::Duration::zero() {
+                            continue;
+                        }
+                        *data = Some(msg);
+                    }
+                    None => {
+                        *data = Some(msg);
+                    }
+                }
+            }
+            Err(e) => {
+                error!("{}", e);
+            }
+        }
+    }
+}
+
+//------
+
+impl<T: Message> From<Node<Blocking, Uds, Idle, T>> for Node<Blocking, Uds, Active, T> {
+    fn from(node: Node<Blocking, Uds, Idle, T>) -> Self {
+        Self {
+            __state: PhantomData,
+            __data_type: PhantomData,
+            cfg: node.cfg,
+            runtime: node.runtime,
+            rt_handle: node.rt_handle,
+            stream: node.stream,
+            uds_stream: node.uds_stream,
+            #[cfg(all(unix, feature = "shm"))]
+            shm: node.shm,
+            topic: node.topic,
+            socket: node.socket,
+            udp_cipher: node.udp_cipher,
+            in_process: node.in_process,
+            buffer: node.buffer,
+            #[cfg(feature = "quic")]
+            endpoint: node.endpoint,
+            #[cfg(feature = "quic")]
+            connection: node.connection,
+            subscription_data: node.subscription_data,
+            host_pulse: node.host_pulse,
+            task_subscribe: None,
+            task_heartbeat: None,
+        }
+    }
+}
+
+impl<T: Message> From<Node<Blocking, Uds, Idle, T>> for Node<Blocking, Uds, Subscription, T> {
+    fn from(node: Node<Blocking, Uds, Idle, T>) -> Self {
+        Self {
+            __state: PhantomData,
+            __data_type: PhantomData,
+            cfg: node.cfg,
+            runtime: node.runtime,
+            rt_handle: node.rt_handle,
+            stream: node.stream,
+            uds_stream: node.uds_stream,
+            #[cfg(all(unix, feature = "shm"))]
+            shm: node.shm,
+            topic: node.topic,
+            socket: node.socket,
+            udp_cipher: node.udp_cipher,
+            in_process: node.in_process,
+            buffer: node.buffer,
+            #[cfg(feature = "quic")]
+            endpoint: node.endpoint,
+            #[cfg(feature = "quic")]
+            connection: node.connection,
+            subscription_data: node.subscription_data,
+            host_pulse: node.host_pulse,
+            task_subscribe: None,
+            task_heartbeat: None,
+        }
+    }
+}
+
+impl<T: Message + 'static> Node<Blocking, Uds, Idle, T> {
+    /// Attempt connection from the Node to the Host listening on the configured socket path
+    #[tracing::instrument(skip_all)]
+    pub fn activate(mut self) -> Result<Node<Blocking, Uds, Active, T>, Error> {
+        let path = socket_path(&self.cfg.network_cfg)?;
+        let node_handshake = self.handshake();
+
+        let handle = match &self.rt_handle {
+            Some(handle) => handle,
+            None => return Err(Error::HandleAccess),
+        };
+
+        let stream: UnixStream = handle.block_on(async {
+            let stream = try_connection(&path).await?;
+            let stream = handshake(stream, &node_handshake).await?;
+            Ok::<UnixStream, Error>(stream)
+        })?;
+        debug!(
+            "Established Node<=>Host UDS stream: {:?}",
+            stream.local_addr()
+        );
+        self.uds_stream = Some(stream);
+
+        Ok(Node::<Blocking, Uds, Active, T>::from(self))
+    }
+
+    #[tracing::instrument]
+    pub fn subscribe(
+        mut self,
+        rate: Duration,
+    ) -> Result<Node<Blocking, Uds, Subscription, T>, Error> {
+        let path = socket_path(&self.cfg.network_cfg)?;
+        let topic = self.topic.clone();
+        let node_handshake = self.handshake();
+
+        let subscription_data: Arc<TokioMutex<Option<Msg<T>>>> = Arc::new(TokioMutex::new(None));
+        let data = Arc::clone(&subscription_data);
+
+        let buffer = self.buffer.clone();
+        let packet = GenericMsg::subscribe(&topic, rate)?;
+
+        let handle = match &self.rt_handle {
+            Some(handle) => handle,
+            None => return Err(Error::HandleAccess),
+        };
+
+        let task_subscribe = handle.spawn(async move {
+            if let Ok(stream) = try_connection(&path).await {
+                if let Ok(stream) = handshake(stream, &node_handshake).await {
+                    if let Err(e) =
+                        run_subscription::<T>(packet, buffer, &stream, data.clone()).await
+                    {
+                        error!("{:?}", e);
+                    }
+                }
+            }
+        });
+        self.task_subscribe = Some(task_subscribe);
+
+        let mut subscription_node = Node::<Blocking, Uds, Subscription, T>::from(self);
+        subscription_node.subscription_data = subscription_data;
+
+        Ok(subscription_node)
+    }
+}
@@ -0,0 +1,101 @@
+mod active;
+mod idle;
+mod subscription;
+
+extern crate alloc;
+
+use tokio::net::UnixStream;
+use tokio::time::{sleep, Duration};
+
+use tracing::*;
+
+use std::path::Path;
+use std::result::Result;
+
+use alloc::vec::Vec;
+use postcard::from_bytes;
+
+use crate::handshake::Handshake;
+use crate::msg::GenericMsg;
+use crate::Error;
+
+/// Attempts to create an async `UnixStream` connection with a Host at the specified socket path
+pub async fn try_connection(path: impl AsRef<Path>) -> Result<UnixStream, Error> {
+    let path = path.as_ref();
+    let mut connection_attempts = 0;
+    let mut stream: Option<UnixStream> = None;
+    while connection_attempts < 5 {
+        match UnixStream::connect(path).await {
+            Ok(my_stream) => {
+                stream = Some(my_stream);
+                break;
+            }
+            Err(e) => {
+                connection_attempts += 1;
+                sleep(Duration::from_millis(1_000)).await;
+                warn!("{:?}", e);
+            }
+        }
+    }
+
+    match stream {
+        Some(stream) => Ok(stream),
+        None => Err(Error::StreamConnection),
+    }
+}
+
+/// Run the initial Node <=> Host connection handshake, waiting for the Host to accept it
+pub async fn handshake(stream: UnixStream, handshake: &Handshake) -> Result<UnixStream, Error> {
+    send_msg(&stream, handshake.to_generic()?.as_bytes()?).await?;
+
+    let mut buf = vec![0u8; 1024];
+    let version = crate::handshake::confirm(await_response(&stream, &mut buf).await?)?;
+    debug!(
+        "{}: Successfully connected to host with protocol version {}",
+        handshake.name, version
+    );
+
+    Ok(stream)
+}
+
+/// Send a `GenericMsg` of `MsgType` from the Node to the Host
+#[inline]
+pub async fn send_msg(stream: &UnixStream, packet: Vec<u8>) -> Result<(), Error> {
+    stream.writable().await?;
+
+    loop {
+        match stream.try_write(&packet) {
+            Ok(_n) => break,
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(())
+}
+
+/// Wait for a single `GenericMsg` response from the Host
+#[inline]
+pub async fn await_response(stream: &UnixStream, buf: &mut [u8]) -> Result<GenericMsg, Error> {
+    let n = read_response(stream, buf).await?;
+    Ok(from_bytes::<GenericMsg>(&buf[..n])?)
+}
+
+/// Read the next message from the Host into `buf` without decoding it, returning its length
+#[inline]
+pub async fn read_response(stream: &UnixStream, buf: &mut [u8]) -> Result<usize, Error> {
+    loop {
+        if let Err(e) = stream.readable().await {
+            error!("{}", e);
+        }
+        match stream.try_read(buf) {
+            // A zero-byte read on a Unix stream means the Host closed the socket
+            Ok(0) => return Err(Error::AccessStream),
+            Ok(n) => return Ok(n),
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                debug!("Would block");
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
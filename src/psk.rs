@@ -0,0 +1,292 @@
+use crate::Error;
+
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, KeyInit, OsRng};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// Length of the random identifier each sender places at the start of its nonces
+const SENDER_ID_LEN: usize = 16;
+/// Length of the XChaCha20-Poly1305 nonce prepended to every datagram
+const NONCE_LEN: usize = SENDER_ID_LEN + 8;
+/// Number of recent counters remembered per sender, so reordered datagrams are still accepted
+const REPLAY_WINDOW: u64 = 64;
+/// Number of senders whose replay windows are remembered. Nodes pick a new sender identifier
+/// every time they start, so past this the least recently heard from is forgotten.
+const MAX_SENDERS: usize = 1024;
+
+/// 256-bit key shared by a Host and its UDP Nodes.
+///
+/// Replay protection only lasts as long as the Host process: datagrams captured before a Host
+/// restart are accepted again afterwards, so rotate the key if that matters, see
+/// [`DatagramCipher`].
+#[derive(Clone, PartialEq, Eq)]
+pub struct PreSharedKey([u8; 32]);
+
+impl PreSharedKey {
+    pub fn new(key: [u8; 32]) -> Self {
+        PreSharedKey(key)
+    }
+
+    /// Generate a random key, e.g. to be distributed to every machine in the fleet
+    pub fn generate() -> Self {
+        let mut key = [0u8; 32];
+        OsRng.fill_bytes(&mut key);
+        PreSharedKey(key)
+    }
+
+    /// Parse a key written as 64 hexadecimal characters
+    pub fn from_hex(hex: &str) -> Result<Self, Error> {
+        let hex = hex.trim();
+        if hex.len() != 64 || !hex.is_ascii() {
+            return Err(Error::InvalidPreSharedKey);
+        }
+        let mut key = [0u8; 32];
+        for (i, byte) in key.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16)
+                .map_err(|_| Error::InvalidPreSharedKey)?;
+        }
+        Ok(PreSharedKey(key))
+    }
+
+    /// Write the key as 64 hexadecimal characters
+    pub fn to_hex(&self) -> String {
+        self.0.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+}
+
+// Keep the key itself out of logs
+impl fmt::Debug for PreSharedKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PreSharedKey(..)")
+    }
+}
+
+/// Counters recently received from one sender
+#[derive(Debug, Default)]
+struct ReplayWindow {
+    /// When the sender was last heard from, in datagrams opened by the cipher
+    last_heard: u64,
+    highest: u64,
+    /// Bit `i` is set once counter `highest - i` has been received
+    seen: u64,
+}
+
+impl ReplayWindow {
+    /// Record `counter`, returning false if it was already received or is too old to tell
+    fn accept(&mut self, counter: u64) -> bool {
+        if self.seen == 0 {
+            self.highest = counter;
+            self.seen = 1;
+            true
+        } else if counter > self.highest {
+            let shift = counter - self.highest;
+            self.seen = if shift >= REPLAY_WINDOW {
+                0
+            } else {
+                self.seen << shift
+            } | 1;
+            self.highest = counter;
+            true
+        } else {
+            let offset = self.highest - counter;
+            if offset >= REPLAY_WINDOW || self.seen & (1 << offset) != 0 {
+                false
+            } else {
+                self.seen |= 1 << offset;
+                true
+            }
+        }
+    }
+}
+
+/// Authenticated encryption of datagrams with a `PreSharedKey`.
+///
+/// Each datagram is sealed with XChaCha20-Poly1305 under a nonce made of a random sender
+/// identifier and a per-sender counter, which is sent in the clear ahead of the ciphertext.
+/// Datagrams that fail authentication, or whose counter was already seen from that sender,
+/// are rejected.
+/// Counters are remembered for the 1024 senders most recently heard from.
+///
+/// Counters are only kept in memory, so a restarted Host, or one that has since heard from
+/// 1024 other senders, accepts a replay of any datagram sealed with the same key. Until replay
+/// windows are persisted, a Host that must never take a replayed publish needs a new key
+/// whenever it restarts.
+pub struct DatagramCipher {
+    cipher: XChaCha20Poly1305,
+    sender_id: [u8; SENDER_ID_LEN],
+    counter: AtomicU64,
+    windows: Mutex<ReplayWindows>,
+}
+
+/// Replay windows of the senders most recently heard from
+#[derive(Debug, Default)]
+struct ReplayWindows {
+    /// Datagrams accepted so far, which orders the senders by how recently they were heard from
+    opened: u64,
+    windows: HashMap<[u8; SENDER_ID_LEN], ReplayWindow>,
+}
+
+impl ReplayWindows {
+    /// Record `counter` from `sender_id`, returning false if it's a replay
+    fn accept(&mut self, sender_id: [u8; SENDER_ID_LEN], counter: u64) -> bool {
+        if !self.windows.contains_key(&sender_id) && self.windows.len() >= MAX_SENDERS {
+            let quietest = self
+                .windows
+                .iter()
+                .min_by_key(|(_, window)| window.last_heard)
+                .map(|(sender_id, _)| *sender_id);
+            if let Some(quietest) = quietest {
+                self.windows.remove(&quietest);
+            }
+        }
+        let window = self.windows.entry(sender_id).or_default();
+        if !window.accept(counter) {
+            return false;
+        }
+        self.opened += 1;
+        window.last_heard = self.opened;
+        true
+    }
+}
+
+impl fmt::Debug for DatagramCipher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DatagramCipher")
+            .field("sender_id", &self.sender_id)
+            .field("counter", &self.counter)
+            .finish_non_exhaustive()
+    }
+}
+
+impl DatagramCipher {
+    pub fn new(key: &PreSharedKey) -> Self {
+        let mut sender_id = [0u8; SENDER_ID_LEN];
+        OsRng.fill_bytes(&mut sender_id);
+        DatagramCipher {
+            cipher: XChaCha20Poly1305::new(Key::from_slice(&key.0)),
+            sender_id,
+            counter: AtomicU64::new(0),
+            windows: Mutex::new(ReplayWindows::default()),
+        }
+    }
+
+    /// Encrypt `plaintext` into a datagram
+    pub fn seal(&self, plaintext: &[u8]) -> Result<Vec<u8>, Error> {
+        let mut nonce = [0u8; NONCE_LEN];
+        nonce[..SENDER_ID_LEN].copy_from_slice(&self.sender_id);
+        let counter = self.counter.fetch_add(1, Ordering::Relaxed);
+        nonce[SENDER_ID_LEN..].copy_from_slice(&counter.to_be_bytes());
+
+        let ciphertext = self
+            .cipher
+            .encrypt(XNonce::from_slice(&nonce), plaintext)
+            .map_err(|_| Error::DatagramRejected)?;
+        let mut datagram = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+        datagram.extend_from_slice(&nonce);
+        datagram.extend_from_slice(&ciphertext);
+        Ok(datagram)
+    }
+
+    /// Authenticate and decrypt a datagram, rejecting forgeries and replays
+    pub fn open(&self, datagram: &[u8]) -> Result<Vec<u8>, Error> {
+        if datagram.len() < NONCE_LEN {
+            return Err(Error::DatagramRejected);
+        }
+        let (nonce, ciphertext) = datagram.split_at(NONCE_LEN);
+        let sender_id: [u8; SENDER_ID_LEN] = nonce[..SENDER_ID_LEN]
+            .try_into()
+            .map_err(|_| Error::DatagramRejected)?;
+        // Our own datagrams reflected back at us
+        if sender_id == self.sender_id {
+            return Err(Error::DatagramRejected);
+        }
+
+        let plaintext = self
+            .cipher
+            .decrypt(XNonce::from_slice(nonce), ciphertext)
+            .map_err(|_| Error::DatagramRejected)?;
+
+        // Only authentic datagrams reach the replay check, so forged ones can't grow the map
+        let counter = u64::from_be_bytes(
+            nonce[SENDER_ID_LEN..]
+                .try_into()
+                .map_err(|_| Error::DatagramRejected)?,
+        );
+        let mut windows = self.windows.lock().map_err(|_| Error::LockFailure)?;
+        if windows.accept(sender_id, counter) {
+            Ok(plaintext)
+        } else {
+            Err(Error::DatagramRejected)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_and_replay() {
+        let key = PreSharedKey::generate();
+        let (node, host) = (DatagramCipher::new(&key), DatagramCipher::new(&key));
+
+        let first = node.seal(b"first").unwrap();
+        let second = node.seal(b"second").unwrap();
+        // Reordered datagrams are accepted once each
+        assert_eq!(host.open(&second).unwrap(), b"second");
+        assert_eq!(host.open(&first).unwrap(), b"first");
+        assert!(host.open(&first).is_err());
+        assert!(host.open(&second).is_err());
+
+        // Tampered datagrams, ones sealed with another key, and our own are all rejected
+        let mut tampered = node.seal(b"third").unwrap();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        assert!(host.open(&tampered).is_err());
+        let stranger = DatagramCipher::new(&PreSharedKey::generate());
+        assert!(host.open(&stranger.seal(b"forged").unwrap()).is_err());
+        assert!(node.open(&node.seal(b"echo").unwrap()).is_err());
+    }
+
+    #[test]
+    fn replay_window_expires_old_counters() {
+        let mut window = ReplayWindow::default();
+        assert!(window.accept(0));
+        assert!(window.accept(REPLAY_WINDOW + 1));
+        // Counter 1 was never seen, but is now too old to be told apart from a replay
+        assert!(!window.accept(1));
+        assert!(window.accept(REPLAY_WINDOW));
+        assert!(!window.accept(REPLAY_WINDOW));
+    }
+
+    #[test]
+    fn replay_windows_forget_quietest_sender() {
+        let mut windows = ReplayWindows::default();
+        for sender in 0..MAX_SENDERS as u64 {
+            let mut sender_id = [0u8; SENDER_ID_LEN];
+            sender_id[..8].copy_from_slice(&sender.to_be_bytes());
+            assert!(windows.accept(sender_id, 0));
+        }
+        // The first sender is heard from again, so the second is the quietest
+        assert!(windows.accept([0u8; SENDER_ID_LEN], 1));
+        assert!(windows.accept([0xff; SENDER_ID_LEN], 0));
+        assert_eq!(windows.windows.len(), MAX_SENDERS);
+        assert!(!windows.accept([0u8; SENDER_ID_LEN], 1));
+        let mut second = [0u8; SENDER_ID_LEN];
+        second[7] = 1;
+        assert!(!windows.windows.contains_key(&second));
+    }
+
+    #[test]
+    fn key_hex_round_trip() {
+        let key = PreSharedKey::generate();
+        assert_eq!(PreSharedKey::from_hex(&key.to_hex()).unwrap(), key);
+        assert!(PreSharedKey::from_hex("abcd").is_err());
+    }
+}
@@ -0,0 +1,248 @@
+use crate::compression::Compression;
+use crate::encoding::Encoding;
+use crate::error::{Error, HostError};
+use crate::msg::{GenericMsg, Msg, MsgType};
+
+use chrono::{DateTime, Utc};
+use serde::de::{self, Deserializer, SeqAccess, Visitor};
+use serde::{Deserialize, Serialize, Serializer};
+
+use std::fmt::{self, Debug};
+use std::marker::PhantomData;
+use std::mem::size_of;
+
+pub use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout, Unaligned};
+
+/// Fixed-layout data sent as its in-memory bytes, rather than serialized field by field.
+///
+/// Publishing a `Raw<T>` copies `T`'s bytes straight into the message, and Nodes on the
+/// TCP, UDS, and in-process transports can read them in place with `request_view` instead of
+/// deserializing them. `T` is typically a `#[repr(C)]` struct derived with `zerocopy`'s
+/// traits; using its little-endian types (e.g. `zerocopy::little_endian::F32`) for
+/// multi-byte fields keeps the layout the same on every machine and satisfies `Unaligned`,
+/// which views need since the received bytes can start at any offset.
+///
+/// `zerocopy` is used rather than `rkyv` because the payload is then exactly `T`'s own
+/// layout, which peers in other languages can read from the struct definition alone, and
+/// checking it takes only a length and alignment check rather than an archive validator.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[repr(transparent)]
+pub struct Raw<T>(pub T);
+
+impl<T: IntoBytes + Immutable> Serialize for Raw<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(self.0.as_bytes())
+    }
+}
+
+impl<'de, T: FromBytes> Deserialize<'de> for Raw<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_bytes(RawVisitor(PhantomData))
+    }
+}
+
+struct RawVisitor<T>(PhantomData<T>);
+
+impl<'de, T: FromBytes> Visitor<'de> for RawVisitor<T> {
+    type Value = Raw<T>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "the {} bytes of a {}",
+            size_of::<T>(),
+            std::any::type_name::<T>()
+        )
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        T::read_from_bytes(v)
+            .map(Raw)
+            .map_err(|_| E::invalid_length(v.len(), &self))
+    }
+
+    // Self-describing formats like JSON write bytes as a sequence of numbers
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut bytes = Vec::with_capacity(size_of::<T>());
+        while let Some(byte) = seq.next_element::<u8>()? {
+            bytes.push(byte);
+        }
+        self.visit_bytes(&bytes)
+    }
+}
+
+/// Fixed-layout data that can be published as a `Raw<T>` and viewed in place
+pub trait RawMessage:
+    FromBytes + IntoBytes + KnownLayout + Immutable + Unaligned + Debug + Clone + Send + Sync + 'static
+{
+}
+impl<T> RawMessage for T where
+    T: FromBytes
+        + IntoBytes
+        + KnownLayout
+        + Immutable
+        + Unaligned
+        + Debug
+        + Clone
+        + Send
+        + Sync
+        + 'static
+{
+}
+
+/// Message whose `Raw<T>` data is borrowed in place from the bytes it was received in
+#[derive(Debug)]
+pub struct MsgView<'a, T> {
+    /// Type of `meadow` message
+    pub msg_type: MsgType,
+    /// Message timestamp in Utc
+    pub timestamp: DateTime<Utc>,
+    /// Topic name
+    pub topic: &'a str,
+    /// Identity of the message's data type
+    pub data_type: &'a str,
+    /// Data, read directly from the received bytes
+    pub data: &'a T,
+}
+
+/// `GenericMsg` decoded without copying its strings or data out of the received bytes
+#[derive(Deserialize)]
+struct GenericMsgRef<'a> {
+    msg_type: MsgType,
+    timestamp: DateTime<Utc>,
+    topic: &'a str,
+    data_type: &'a str,
+    encoding: Encoding,
+    compression: Compression,
+    data: &'a [u8],
+}
+
+impl<'a, T: FromBytes + KnownLayout + Immutable + Unaligned> MsgView<'a, T> {
+    /// View a `postcard`-encoded `GenericMsg` carrying a `Raw<T>`
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<Self, Error> {
+        let msg: GenericMsgRef = postcard::from_bytes(bytes)?;
+        Self::new(
+            msg.msg_type,
+            msg.timestamp,
+            msg.topic,
+            msg.data_type,
+            msg.encoding,
+            msg.compression,
+            msg.data,
+        )
+    }
+
+    /// View the data of a `GenericMsg` carrying a `Raw<T>`
+    pub fn from_generic(msg: &'a GenericMsg) -> Result<Self, Error> {
+        Self::new(
+            msg.msg_type.clone(),
+            msg.timestamp,
+            &msg.topic,
+            &msg.data_type,
+            msg.encoding,
+            msg.compression,
+            &msg.data,
+        )
+    }
+
+    fn new(
+        msg_type: MsgType,
+        timestamp: DateTime<Utc>,
+        topic: &'a str,
+        data_type: &'a str,
+        encoding: Encoding,
+        compression: Compression,
+        data: &'a [u8],
+    ) -> Result<Self, Error> {
+        // The same checks `GenericMsg` makes before decoding a `Msg<T>`
+        if let MsgType::Result(Err(e)) = msg_type {
+            return Err(e);
+        }
+        let requested = crate::schema::data_type::<Raw<T>>();
+        if data_type != requested {
+            return Err(Error::HostOperation(HostError::TypeMismatch {
+                expected: data_type.to_string(),
+                found: requested,
+            }));
+        }
+        // Only uncompressed `postcard` stores the bytes as they are in memory
+        if encoding != Encoding::Postcard {
+            return Err(Error::UnsupportedEncoding(encoding));
+        }
+        if compression != Compression::None {
+            return Err(Error::Serialization(
+                "compressed data can't be viewed in place".into(),
+            ));
+        }
+        let bytes: &[u8] = postcard::from_bytes(data)?;
+        let data = T::ref_from_bytes(bytes).map_err(|_| {
+            Error::Serialization(format!(
+                "{} bytes don't fit the {}-byte layout of {}",
+                bytes.len(),
+                size_of::<T>(),
+                std::any::type_name::<T>()
+            ))
+        })?;
+        Ok(MsgView {
+            msg_type,
+            timestamp,
+            topic,
+            data_type,
+            data,
+        })
+    }
+
+    /// Copy the view into an owned message
+    pub fn to_msg(&self) -> Msg<Raw<T>>
+    where
+        T: Clone,
+    {
+        Msg {
+            msg_type: self.msg_type.clone(),
+            timestamp: self.timestamp,
+            topic: self.topic.to_string(),
+            data_type: self.data_type.to_string(),
+            data: Raw(self.data.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryInto;
+    use zerocopy::little_endian::F32;
+
+    #[derive(Debug, Clone, PartialEq, FromBytes, IntoBytes, KnownLayout, Immutable, Unaligned)]
+    #[repr(C)]
+    struct Scan {
+        seq: u8,
+        ranges: [F32; 4],
+    }
+
+    #[test]
+    fn views() {
+        let scan = Scan {
+            seq: 7,
+            ranges: [F32::new(0.5), F32::new(1.0), F32::new(1.5), F32::new(2.0)],
+        };
+        let msg = Msg::new(MsgType::Set, "scan", Raw(scan.clone()));
+        let generic = msg.clone().to_generic().unwrap();
+        // Length, then the bytes of the `Scan` as they are in memory
+        assert_eq!(generic.data.len(), 1 + 17);
+        assert_eq!(&generic.data[1..], scan.as_bytes());
+
+        let bytes = generic.as_bytes().unwrap();
+        let view = MsgView::<Scan>::from_bytes(&bytes).unwrap();
+        assert_eq!(view.data, &scan);
+        assert_eq!(view.topic, "scan");
+        assert_eq!(view.to_msg(), msg);
+        // The bytes are read in place, not copied
+        let range = bytes.as_ptr_range();
+        assert!(range.contains(&(view.data as *const Scan as *const u8)));
+
+        let decoded: Msg<Raw<Scan>> = generic.clone().try_into().unwrap();
+        assert_eq!(decoded.data.0, scan);
+        assert!(MsgView::<[u8; 17]>::from_generic(&generic).is_err());
+    }
+}
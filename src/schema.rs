@@ -0,0 +1,270 @@
+use crate::error::Error;
+use crate::host::{GenericStore, Store};
+use crate::msg::{GenericMsg, Message, Msg, MsgType};
+
+use serde::{Deserialize, Serialize};
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::convert::TryInto;
+use std::sync::{OnceLock, RwLock};
+use std::time::Duration;
+
+/// Derive [`Schema`] from a type's name and fields; see [`meadow_derive::Schema`]
+pub use meadow_derive::Schema;
+
+/// Prefix of the Host topics holding the description of each schema, e.g. `_meadow/schemas/Pose`
+pub const SCHEMA_TOPIC_PREFIX: &str = "_meadow/schemas/";
+
+/// Stable identity for a message type, independent of module paths and compiler versions.
+///
+/// Messages normally identify their data by `std::any::type_name`, which changes whenever a
+/// type is moved or renamed. Once a type implementing `Schema` is [`register`]ed, its
+/// messages are labelled with [`Schema::id`] instead, so binaries built from different trees
+/// agree on it as long as the schema's name and layout do.
+pub trait Schema {
+    /// Name of the schema, which defaults to the type's identifier when derived
+    const NAME: &'static str;
+
+    /// Human-readable description of the type's layout, e.g. `Pose struct { x: f64, y: f64 }`
+    fn description() -> String;
+
+    /// 64-bit FNV-1a hash of the description, which changes with the type's layout
+    fn hash() -> u64 {
+        fnv1a(Self::description().as_bytes())
+    }
+
+    /// Identifier used as the `data_type` of the type's messages, e.g. `Pose#9c2e5b1a7f3d0e84`
+    fn id() -> String {
+        format!("{}#{:016x}", Self::NAME, Self::hash())
+    }
+}
+
+/// Types described by their name alone
+macro_rules! primitive_schema {
+    ($($ty:ty),*) => {
+        $(
+            impl Schema for $ty {
+                const NAME: &'static str = stringify!($ty);
+                fn description() -> String {
+                    Self::NAME.to_string()
+                }
+            }
+        )*
+    };
+}
+
+primitive_schema!(
+    bool,
+    char,
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    f32,
+    f64,
+    String,
+    (),
+    Duration
+);
+
+impl<Tz: chrono::TimeZone> Schema for chrono::DateTime<Tz> {
+    const NAME: &'static str = "DateTime";
+    fn description() -> String {
+        // Serialized as an RFC 3339 string whatever the time zone
+        Self::NAME.to_string()
+    }
+}
+
+/// Containers described by their name and the schemas of what they hold, as written in Rust
+macro_rules! container_schema {
+    ($($name:ident<$($param:ident $(: $bound:path)?),+>),*) => {
+        $(
+            impl<$($param: Schema $(+ $bound)?),+> Schema for $name<$($param),+> {
+                const NAME: &'static str = stringify!($name);
+                fn description() -> String {
+                    let params: Vec<String> = vec![$($param::description()),+];
+                    format!("{}<{}>", Self::NAME, params.join(","))
+                }
+            }
+        )*
+    };
+}
+
+container_schema!(
+    Option<T>,
+    Box<T>,
+    Vec<T>,
+    VecDeque<T>,
+    BTreeSet<T>,
+    HashSet<T>,
+    BTreeMap<K, V>,
+    HashMap<K, V>
+);
+
+impl<T: Schema, const N: usize> Schema for [T; N] {
+    const NAME: &'static str = "array";
+    fn description() -> String {
+        format!("[{};{}]", T::description(), N)
+    }
+}
+
+macro_rules! tuple_schema {
+    ($($param:ident),+) => {
+        impl<$($param: Schema),+> Schema for ($($param,)+) {
+            const NAME: &'static str = "tuple";
+            fn description() -> String {
+                let params: Vec<String> = vec![$($param::description()),+];
+                format!("({})", params.join(","))
+            }
+        }
+    };
+}
+
+tuple_schema!(A);
+tuple_schema!(A, B);
+tuple_schema!(A, B, C);
+tuple_schema!(A, B, C, D);
+tuple_schema!(A, B, C, D, E);
+tuple_schema!(A, B, C, D, E, F);
+
+/// Description of a schema, as stored in the Host's schema registry
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SchemaDescription {
+    pub name: String,
+    pub hash: u64,
+    pub description: String,
+}
+
+impl SchemaDescription {
+    /// Describe the schema of `T`
+    pub fn of<T: Schema>() -> Self {
+        SchemaDescription {
+            name: T::NAME.to_string(),
+            hash: T::hash(),
+            description: T::description(),
+        }
+    }
+
+    /// Identifier used as the `data_type` of messages with this schema
+    pub fn id(&self) -> String {
+        format!("{}#{:016x}", self.name, self.hash)
+    }
+
+    /// Topic on the Host holding the descriptions of schemas with this name
+    pub fn topic(&self) -> String {
+        format!("{}{}", SCHEMA_TOPIC_PREFIX, self.name)
+    }
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+/// Schemas registered in this process, keyed by `std::any::type_name`
+fn registry() -> &'static RwLock<HashMap<&'static str, SchemaDescription>> {
+    static REGISTRY: OnceLock<RwLock<HashMap<&'static str, SchemaDescription>>> = OnceLock::new();
+    REGISTRY.get_or_init(Default::default)
+}
+
+/// Label messages of `T` with its schema identifier from now on, rather than its `type_name`.
+///
+/// Register a type before building any Node or Host that uses it, in every binary sharing
+/// its topics, so that all of them agree on the `data_type` of its messages.
+pub fn register<T: Schema>() {
+    let mut registry = registry().write().unwrap_or_else(|e| e.into_inner());
+    registry.insert(std::any::type_name::<T>(), SchemaDescription::of::<T>());
+}
+
+/// Schema registered for `T`, if any
+pub fn registered<T>() -> Option<SchemaDescription> {
+    let registry = registry().read().unwrap_or_else(|e| e.into_inner());
+    registry.get(std::any::type_name::<T>()).cloned()
+}
+
+/// `data_type` of messages carrying `T`: its schema identifier if registered, or its `type_name`
+pub fn data_type<T>() -> String {
+    match registered::<T>() {
+        Some(schema) => schema.id(),
+        None => std::any::type_name::<T>().to_string(),
+    }
+}
+
+/// Add a schema to the Host's registry, unless the latest entry for its name already matches
+pub(crate) fn record(db: &sled::Db, schema: &SchemaDescription) -> Result<(), Error> {
+    let topic = schema.topic();
+    if let Ok(latest) = db.get::<SchemaDescription>(&topic) {
+        if latest.data == *schema {
+            return Ok(());
+        }
+    }
+    let msg = Msg::new(MsgType::Set, topic, schema.clone());
+    db.clone().insert_msg(msg)
+}
+
+/// Look up the description of schema `id` in the Host's registry, newest entries first
+pub(crate) fn lookup(db: &sled::Db, id: &str) -> Result<Option<SchemaDescription>, Error> {
+    let name = match id.rsplit_once('#') {
+        Some((name, _hash)) => name,
+        None => return Ok(None),
+    };
+    let tree = db.open_tree(format!("{}{}", SCHEMA_TOPIC_PREFIX, name))?;
+    for entry in tree.iter().rev() {
+        let (_timestamp, bytes) = entry?;
+        let generic: GenericMsg = postcard::from_bytes(&bytes)?;
+        let msg: Msg<SchemaDescription> = generic.try_into()?;
+        if msg.data.id() == id {
+            return Ok(Some(msg.data));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct Pose {
+        x: f64,
+        y: f64,
+    }
+
+    impl Schema for Pose {
+        const NAME: &'static str = "Pose";
+        fn description() -> String {
+            "Pose struct { x: f64, y: f64 }".into()
+        }
+    }
+
+    #[test]
+    fn schema_ids() {
+        // Pinned, since every binary sharing the schema must compute the same hash
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+        let schema = SchemaDescription::of::<Pose>();
+        assert_eq!(schema.id(), Pose::id());
+        assert_eq!(schema.topic(), "_meadow/schemas/Pose");
+
+        assert_eq!(data_type::<Pose>(), std::any::type_name::<Pose>());
+        register::<Pose>();
+        assert_eq!(registered::<Pose>(), Some(schema));
+        assert_eq!(data_type::<Pose>(), Pose::id());
+        let msg = Msg::new(MsgType::Set, "pose", Pose { x: 1.0, y: 2.0 });
+        assert_eq!(msg.data_type, Pose::id());
+        let generic = msg.to_generic().unwrap();
+        let _: Msg<Pose> = generic.try_into().unwrap();
+    }
+}
@@ -11,6 +11,7 @@ pub struct Pose {
 }
 
 /// Example test struct for docs and tests, incompatible with Pose
+#[allow(dead_code)]
 #[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq, Eq)]
 pub struct NotPose {
     a: isize,
@@ -14,3 +14,30 @@ fn host_only_ops() {
         assert_eq!(data, back);
     }
 }
+
+#[test]
+fn host_bind_addr() {
+    use meadow::host::TcpConfig;
+    use std::net::SocketAddr;
+
+    // Interfaces resolve to their IPv4 address on the configured port
+    let cfg = TcpConfig::default("lo").set_socket_num(25_500u16);
+    assert_eq!(
+        cfg.socket_addr().unwrap(),
+        "127.0.0.1:25500".parse::<SocketAddr>().unwrap()
+    );
+    assert!(TcpConfig::default("not_an_interface")
+        .socket_addr()
+        .is_err());
+
+    // Explicit addresses take precedence, including IPv6
+    let addr: SocketAddr = "[::]:25501".parse().unwrap();
+    assert_eq!(TcpConfig::from_addr(addr).socket_addr().unwrap(), addr);
+    let cfg = TcpConfig::default("lo")
+        .set_bind_addr(addr)
+        .set_socket_num(25_502u16);
+    assert_eq!(
+        cfg.socket_addr().unwrap(),
+        "[::]:25502".parse::<SocketAddr>().unwrap()
+    );
+}
@@ -21,7 +21,7 @@ static INIT: Once = Once::new();
 #[cfg(feature = "quic")]
 pub fn initialize() {
     INIT.call_once(|| {
-        generate_certs(QuicCertGenConfig::default());
+        generate_certs(QuicCertGenConfig::default()).unwrap();
     });
 }
 
@@ -33,6 +33,40 @@ fn start_host() -> Result<Host, Error> {
     Ok(host)
 }
 
+/// Where a test Host listens, in place of the default TCP and UDP listeners
+enum Listener {
+    /// TCP and UDP on the same address
+    Ip(std::net::SocketAddr),
+    #[cfg(unix)]
+    Uds(std::path::PathBuf),
+    #[cfg(all(unix, feature = "shm"))]
+    Shm(std::path::PathBuf),
+}
+
+/// Start a Host with a temporary store on `listener`, with anything else set in `cfg`
+fn start_host_on(listener: Listener, cfg: HostConfig) -> Result<Host, Error> {
+    let sc = SledConfig::new().temporary(true);
+    let cfg = cfg.with_sled_config(sc);
+    let cfg = match listener {
+        Listener::Ip(addr) => cfg
+            .with_tcp_config(Some(meadow::host::TcpConfig::from_addr(addr)))
+            .with_udp_config(Some(UdpConfig::from_addr(addr))),
+        #[cfg(unix)]
+        Listener::Uds(path) => cfg
+            .with_tcp_config(None)
+            .with_udp_config(None)
+            .with_uds_config(Some(UdsConfig::new(path))),
+        #[cfg(all(unix, feature = "shm"))]
+        Listener::Shm(path) => cfg
+            .with_tcp_config(None)
+            .with_udp_config(None)
+            .with_shm_config(Some(ShmConfig::new(path).set_ring_size(4 * 1024 * 1024))),
+    };
+    let mut host = cfg.build()?;
+    host.start()?;
+    Ok(host)
+}
+
 macro_rules! integrate_host_and_single_node {
     // macth like arm for macro
     ($a:ty) => {
@@ -421,3 +455,1892 @@ fn back_nth_operation_fallible_macros() {
     #[cfg(feature = "quic")]
     back_nth_operation_fallible!(Quic);
 }
+
+#[cfg(unix)]
+#[test]
+fn uds_publish_request() {
+    let path = std::env::temp_dir().join("meadow_uds_publish_request.sock");
+    let host = start_host_on(Listener::Uds(path.clone()), HostConfig::default()).unwrap();
+
+    let cfg = NetworkConfig::<Blocking, Uds>::default().set_socket_path(&path);
+    let node = NodeConfig::<Blocking, Uds, Pose>::new("pose")
+        .with_config(cfg)
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+
+    for i in 0..5 {
+        let pose = Pose {
+            x: i as f32,
+            y: i as f32,
+        };
+        node.publish(pose.clone()).unwrap();
+        assert_eq!(node.request().unwrap().data, pose);
+    }
+    assert_eq!(
+        node.request_nth_back(2).unwrap().data,
+        Pose { x: 2.0, y: 2.0 }
+    );
+    assert_eq!(host.topics(), node.topics().unwrap().data);
+}
+
+#[cfg(unix)]
+#[test]
+fn uds_subscription() {
+    let path = std::env::temp_dir().join("meadow_uds_subscription.sock");
+    let _host = start_host_on(Listener::Uds(path.clone()), HostConfig::default()).unwrap();
+
+    let cfg = NetworkConfig::<Blocking, Uds>::default().set_socket_path(&path);
+    let writer = NodeConfig::<Blocking, Uds, usize>::new("subscription")
+        .with_config(cfg)
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+    let reader = writer
+        .config()
+        .clone()
+        .build()
+        .unwrap()
+        .subscribe(Duration::from_millis(10))
+        .unwrap();
+
+    for i in 0..5usize {
+        writer.publish(i).unwrap();
+        thread::sleep(Duration::from_millis(100));
+        assert_eq!(reader.get_subscribed_data().unwrap().data, i);
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn uds_socket_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = std::env::temp_dir().join("meadow_uds_permissions.sock");
+    let sc = SledConfig::new().temporary(true);
+    let mut host = HostConfig::default()
+        .with_sled_config(sc)
+        .with_tcp_config(None)
+        .with_udp_config(None)
+        .with_uds_config(Some(UdsConfig::new(&path).set_permissions(0o600)))
+        .build()
+        .unwrap();
+    host.start().unwrap();
+
+    let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+
+    // Dropping the Host cleans up its socket file
+    drop(host);
+    assert!(!path.exists());
+}
+
+#[cfg(all(unix, feature = "shm"))]
+#[test]
+fn shm_publish_request() {
+    let path = std::env::temp_dir().join("meadow_shm_publish_request.sock");
+    let host = start_host_on(Listener::Shm(path.clone()), HostConfig::default()).unwrap();
+
+    let cfg = NetworkConfig::<Blocking, Shm>::default().set_socket_path(&path);
+    let node = NodeConfig::<Blocking, Shm, Pose>::new("pose")
+        .with_config(cfg)
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+
+    for i in 0..5 {
+        let pose = Pose {
+            x: i as f32,
+            y: i as f32,
+        };
+        node.publish(pose.clone()).unwrap();
+        assert_eq!(node.request().unwrap().data, pose);
+    }
+    assert_eq!(
+        node.request_nth_back(2).unwrap().data,
+        Pose { x: 2.0, y: 2.0 }
+    );
+    assert_eq!(host.topics(), node.topics().unwrap().data);
+
+    // The Host still records everything published over shared memory
+    let stored: Msg<Pose> = host.get("pose").unwrap();
+    assert_eq!(stored.data, Pose { x: 4.0, y: 4.0 });
+}
+
+#[cfg(all(unix, feature = "shm"))]
+#[test]
+fn shm_large_frames() {
+    let path = std::env::temp_dir().join("meadow_shm_large_frames.sock");
+    let _host = start_host_on(Listener::Shm(path.clone()), HostConfig::default()).unwrap();
+
+    let cfg = NetworkConfig::<Blocking, Shm>::default().set_socket_path(&path);
+    let node = NodeConfig::<Blocking, Shm, Vec<u8>>::new("frame")
+        .with_config(cfg)
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+
+    // Enough 640x480 RGB frames to wrap around the ring several times
+    for i in 0..20u8 {
+        let frame = vec![i; 640 * 480 * 3];
+        node.publish(frame.clone()).unwrap();
+        assert_eq!(node.request().unwrap().data, frame);
+    }
+
+    // Messages larger than the ring are rejected rather than corrupting it
+    assert_eq!(
+        node.publish(vec![0u8; 8 * 1024 * 1024]),
+        Err(Error::ShmFull)
+    );
+}
+
+#[cfg(all(unix, feature = "shm"))]
+#[test]
+fn shm_subscription() {
+    let path = std::env::temp_dir().join("meadow_shm_subscription.sock");
+    let _host = start_host_on(Listener::Shm(path.clone()), HostConfig::default()).unwrap();
+
+    let cfg = NetworkConfig::<Blocking, Shm>::default().set_socket_path(&path);
+    let writer = NodeConfig::<Blocking, Shm, usize>::new("subscription")
+        .with_config(cfg)
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+    let reader = writer
+        .config()
+        .clone()
+        .build()
+        .unwrap()
+        .subscribe(Duration::from_millis(10))
+        .unwrap();
+
+    for i in 0..5usize {
+        writer.publish(i).unwrap();
+        thread::sleep(Duration::from_millis(100));
+        assert_eq!(reader.get_subscribed_data().unwrap().data, i);
+    }
+}
+
+#[test]
+fn in_process_publish_request() {
+    let sc = SledConfig::new().temporary(true);
+    let mut host = HostConfig::default()
+        .with_sled_config(sc)
+        .with_tcp_config(None)
+        .with_udp_config(None)
+        .build()
+        .unwrap();
+    host.start().unwrap();
+
+    let cfg = NetworkConfig::<Blocking, InProcess>::default().set_host_handle(host.handle());
+    let node = NodeConfig::<Blocking, InProcess, Pose>::new("pose")
+        .with_config(cfg)
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+
+    for i in 0..5 {
+        let pose = Pose {
+            x: i as f32,
+            y: i as f32,
+        };
+        node.publish(pose.clone()).unwrap();
+        assert_eq!(node.request().unwrap().data, pose);
+    }
+    assert_eq!(
+        node.request_nth_back(2).unwrap().data,
+        Pose { x: 2.0, y: 2.0 }
+    );
+    assert_eq!(host.topics(), node.topics().unwrap().data);
+    let stored: Msg<Pose> = host.get("pose").unwrap();
+    assert_eq!(stored.data, Pose { x: 4.0, y: 4.0 });
+}
+
+#[test]
+fn in_process_subscription() {
+    let sc = SledConfig::new().temporary(true);
+    let mut host = HostConfig::default()
+        .with_sled_config(sc)
+        .with_tcp_config(None)
+        .with_udp_config(None)
+        .build()
+        .unwrap();
+    host.start().unwrap();
+
+    let cfg = NetworkConfig::<Blocking, InProcess>::default().set_host_handle(host.handle());
+    let writer = NodeConfig::<Blocking, InProcess, usize>::new("subscription")
+        .with_config(cfg)
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+    let reader = writer
+        .config()
+        .clone()
+        .build()
+        .unwrap()
+        .subscribe(Duration::from_millis(10))
+        .unwrap();
+
+    for i in 0..5usize {
+        writer.publish(i).unwrap();
+        thread::sleep(Duration::from_millis(100));
+        assert_eq!(reader.get_subscribed_data().unwrap().data, i);
+    }
+}
+
+#[test]
+fn in_process_requires_host_handle() {
+    let node = NodeConfig::<Blocking, InProcess, usize>::new("no_handle")
+        .build()
+        .unwrap();
+    assert_eq!(node.activate().unwrap_err(), Error::NoHostHandle);
+}
+
+#[tokio::test]
+async fn in_process_nonblocking() {
+    let sc = SledConfig::new().temporary(true);
+    let host = tokio::task::spawn_blocking(move || {
+        let mut host = HostConfig::default()
+            .with_sled_config(sc)
+            .with_tcp_config(None)
+            .with_udp_config(None)
+            .build()
+            .unwrap();
+        host.start().unwrap();
+        host
+    })
+    .await
+    .unwrap();
+
+    let cfg = NetworkConfig::<Nonblocking, InProcess>::default().set_host_handle(host.handle());
+    let rt_cfg = RuntimeConfig::default()
+        .with_owned_runtime(false)
+        .with_rt_handle(Some(tokio::runtime::Handle::current()));
+    let node = NodeConfig::<Nonblocking, InProcess, usize>::new("nonblocking")
+        .with_config(cfg)
+        .with_runtime_config(rt_cfg)
+        .build()
+        .unwrap()
+        .activate()
+        .await
+        .unwrap();
+    for i in 0..10usize {
+        node.publish(i).await.unwrap();
+        assert_eq!(node.request().await.unwrap().data, i);
+    }
+
+    // The Host owns a runtime, which can't be dropped from within an async context
+    tokio::task::spawn_blocking(move || drop(host))
+        .await
+        .unwrap();
+}
+
+#[test]
+fn ipv6_tcp_and_udp() {
+    let addr: std::net::SocketAddr = "[::1]:25101".parse().unwrap();
+    let _host = start_host_on(Listener::Ip(addr), HostConfig::default()).unwrap();
+
+    let tcp = NodeConfig::<Blocking, Tcp, Pose>::new("ipv6_tcp")
+        .with_config(NetworkConfig::<Blocking, Tcp>::default().set_host_addr(addr))
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+    let udp = NodeConfig::<Blocking, Udp, Pose>::new("ipv6_udp")
+        .with_config(NetworkConfig::<Blocking, Udp>::default().set_host_addr(addr))
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+
+    for i in 0..5 {
+        let pose = Pose {
+            x: i as f32,
+            y: i as f32,
+        };
+        tcp.publish(pose.clone()).unwrap();
+        assert_eq!(tcp.request().unwrap().data, pose);
+        udp.publish(pose.clone()).unwrap();
+        thread::sleep(Duration::from_millis(10));
+        assert_eq!(udp.request().unwrap().data, pose);
+    }
+}
+
+#[test]
+fn dual_stack_tcp() {
+    let _host = start_host_on(
+        Listener::Ip("[::]:25102".parse().unwrap()),
+        HostConfig::default(),
+    )
+    .unwrap();
+
+    for host_addr in ["127.0.0.1:25102", "[::1]:25102"] {
+        let host_addr: std::net::SocketAddr = host_addr.parse().unwrap();
+        let node = NodeConfig::<Blocking, Tcp, usize>::new("dual_stack")
+            .with_config(NetworkConfig::<Blocking, Tcp>::default().set_host_addr(host_addr))
+            .build()
+            .unwrap()
+            .activate()
+            .unwrap();
+        node.publish(host_addr.port() as usize).unwrap();
+        assert_eq!(node.request().unwrap().data, 25102);
+    }
+}
+
+#[test]
+fn discover_host_by_name() {
+    use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+
+    let group = SocketAddrV4::new(Ipv4Addr::new(239, 255, 77, 77), 25_104);
+    let addr: SocketAddr = "127.0.0.1:25103".parse().unwrap();
+    let sc = SledConfig::new().temporary(true);
+    let mut host = HostConfig::default()
+        .with_sled_config(sc)
+        .with_tcp_config(Some(meadow::host::TcpConfig::from_addr(addr)))
+        .with_udp_config(Some(UdpConfig::from_addr(addr)))
+        .with_discovery_config(Some(
+            DiscoveryConfig::new("discoverable")
+                .set_group(group)
+                .set_interface(Ipv4Addr::LOCALHOST)
+                .set_period(Duration::from_millis(100)),
+        ))
+        .build()
+        .unwrap();
+    host.start().unwrap();
+
+    let discovery = Discovery::new("discoverable")
+        .set_group(group)
+        .set_interface(Ipv4Addr::LOCALHOST)
+        .set_timeout(Duration::from_secs(3));
+
+    let tcp = NodeConfig::<Blocking, Tcp, usize>::new("discovered")
+        .with_config(NetworkConfig::<Blocking, Tcp>::default().set_discovery(discovery.clone()))
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+    assert_eq!(tcp.config().network_cfg.host_addr, addr);
+    tcp.publish(1).unwrap();
+    assert_eq!(tcp.request().unwrap().data, 1);
+
+    let udp = NodeConfig::<Blocking, Udp, usize>::new("discovered")
+        .with_config(NetworkConfig::<Blocking, Udp>::default().set_discovery(discovery.clone()))
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+    assert_eq!(udp.config().network_cfg.host_addr, addr);
+    assert_eq!(udp.request().unwrap().data, 1);
+
+    // Hosts announcing under other names are ignored
+    let missing = NodeConfig::<Blocking, Tcp, usize>::new("discovered")
+        .with_config(
+            NetworkConfig::<Blocking, Tcp>::default().set_discovery(
+                Discovery::new("not_discoverable")
+                    .set_group(group)
+                    .set_interface(Ipv4Addr::LOCALHOST)
+                    .set_timeout(Duration::from_millis(300)),
+            ),
+        )
+        .build()
+        .unwrap();
+    assert_eq!(
+        missing.activate().unwrap_err(),
+        Error::HostNotDiscovered("not_discoverable".to_string())
+    );
+}
+
+#[cfg(feature = "quic")]
+#[test]
+fn quic_mutual_tls() {
+    use meadow::host::quic::{generate_ca, generate_signed_certs};
+    use std::path::Path;
+
+    // Issue Host and client certificates from a throwaway fleet CA
+    let ca_path = Path::new("target").join("mtls_ca.pem");
+    let ca_key_path = Path::new("target").join("mtls_ca_key.pem");
+    let host_cert_path = Path::new("target").join("mtls_host_cert.pem");
+    let host_key_path = Path::new("target").join("mtls_host_key.pem");
+    let client_cert_path = Path::new("target").join("mtls_client_cert.pem");
+    let client_key_path = Path::new("target").join("mtls_client_key.pem");
+    generate_ca(
+        QuicCertGenConfig::new()
+            .set_common_name("meadow test CA")
+            .set_cert_pem_path(&ca_path)
+            .set_priv_key_pem_path(&ca_key_path),
+    )
+    .unwrap();
+    generate_signed_certs(
+        QuicCertGenConfig::default()
+            .set_cert_pem_path(&host_cert_path)
+            .set_priv_key_pem_path(&host_key_path),
+        &ca_path,
+        &ca_key_path,
+    )
+    .unwrap();
+    generate_signed_certs(
+        QuicCertGenConfig::new()
+            .set_common_name("pose_node")
+            .set_client_auth(true)
+            .set_validity(Duration::from_secs(60 * 60))
+            .set_cert_pem_path(&client_cert_path)
+            .set_priv_key_pem_path(&client_key_path),
+        &ca_path,
+        &ca_key_path,
+    )
+    .unwrap();
+
+    let addr: std::net::SocketAddr = "127.0.0.1:25105".parse().unwrap();
+    let sc = SledConfig::new().temporary(true);
+    let mut host = HostConfig::default()
+        .with_sled_config(sc)
+        .with_tcp_config(None)
+        .with_udp_config(None)
+        .with_quic_config(Some(
+            meadow::host::QuicConfig {
+                cert_path: host_cert_path,
+                key_path: host_key_path,
+                ..meadow::host::QuicConfig::from_addr(addr)
+            }
+            .set_client_ca_path(&ca_path),
+        ))
+        .build()
+        .unwrap();
+    host.start().unwrap();
+
+    let node = NodeConfig::<Blocking, Quic, Pose>::new("pose")
+        .with_config(
+            NetworkConfig::<Blocking, Quic>::default()
+                .set_host_addr(addr)
+                .set_cert_path(&ca_path)
+                .set_client_cert(&client_cert_path, &client_key_path),
+        )
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+    let pose = Pose { x: 1.0, y: 2.0 };
+    node.publish(pose.clone()).unwrap();
+    assert_eq!(node.request().unwrap().data, pose);
+
+    // Nodes without a client certificate are turned away
+    let anonymous = NodeConfig::<Blocking, Quic, Pose>::new("pose")
+        .with_config(
+            NetworkConfig::<Blocking, Quic>::default()
+                .set_host_addr(addr)
+                .set_cert_path(&ca_path),
+        )
+        .build()
+        .unwrap()
+        .activate()
+        .and_then(|node| node.request());
+    assert!(anonymous.is_err());
+}
+
+#[cfg(feature = "quic")]
+#[test]
+fn quic_server_name() {
+    use std::path::Path;
+
+    let cert_path = Path::new("target").join("server_name_cert.pem");
+    let key_path = Path::new("target").join("server_name_key.pem");
+    generate_certs(
+        QuicCertGenConfig::new()
+            .add_dns_name("meadow-host.example")
+            .add_ip_address([127, 0, 0, 1])
+            .set_cert_pem_path(&cert_path)
+            .set_priv_key_pem_path(&key_path),
+    )
+    .unwrap();
+
+    let addr: std::net::SocketAddr = "127.0.0.1:25106".parse().unwrap();
+    let sc = SledConfig::new().temporary(true);
+    let mut host = HostConfig::default()
+        .with_sled_config(sc)
+        .with_tcp_config(None)
+        .with_udp_config(None)
+        .with_quic_config(Some(meadow::host::QuicConfig {
+            cert_path: cert_path.clone(),
+            key_path,
+            ..meadow::host::QuicConfig::from_addr(addr)
+        }))
+        .build()
+        .unwrap();
+    host.start().unwrap();
+
+    let node = NodeConfig::<Blocking, Quic, usize>::new("server_name")
+        .with_config(
+            NetworkConfig::<Blocking, Quic>::default()
+                .set_host_addr(addr)
+                .set_cert_path(&cert_path)
+                .set_server_name("meadow-host.example"),
+        )
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+    node.publish(1).unwrap();
+    assert_eq!(node.request().unwrap().data, 1);
+
+    // The certificate isn't valid for the default name of "localhost"
+    let mismatched = NodeConfig::<Blocking, Quic, usize>::new("server_name")
+        .with_config(
+            NetworkConfig::<Blocking, Quic>::default()
+                .set_host_addr(addr)
+                .set_cert_path(&cert_path),
+        )
+        .build()
+        .unwrap()
+        .activate();
+    assert!(mismatched.is_err());
+}
+
+#[cfg(feature = "tls")]
+#[test]
+fn tcp_tls() {
+    use meadow::host::{TcpConfig, TlsConfig};
+    use std::path::Path;
+
+    initialize();
+    let addr: std::net::SocketAddr = "127.0.0.1:25107".parse().unwrap();
+    let sc = SledConfig::new().temporary(true);
+    let mut host = HostConfig::default()
+        .with_sled_config(sc)
+        .with_tcp_config(Some(
+            TcpConfig::from_addr(addr).set_tls(TlsConfig::default()),
+        ))
+        .with_udp_config(None)
+        .with_quic_config(None)
+        .build()
+        .unwrap();
+    host.start().unwrap();
+
+    let node = NodeConfig::<Blocking, Tcp, usize>::new("tls")
+        .with_config(
+            NetworkConfig::<Blocking, Tcp>::default()
+                .set_host_addr(addr)
+                .set_tls(Path::new("target").join("cert.pem")),
+        )
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+    node.publish(1).unwrap();
+    assert_eq!(node.request().unwrap().data, 1);
+
+    // A Node without TLS can't talk to the Host
+    let plain = NodeConfig::<Blocking, Tcp, usize>::new("tls")
+        .with_config(NetworkConfig::<Blocking, Tcp>::default().set_host_addr(addr))
+        .build()
+        .unwrap()
+        .activate()
+        .and_then(|node| node.request());
+    assert!(plain.is_err());
+}
+
+#[test]
+fn udp_psk() {
+    use meadow::host::UdpConfig;
+
+    let addr: std::net::SocketAddr = "127.0.0.1:25108".parse().unwrap();
+    let psk = PreSharedKey::generate();
+    let sc = SledConfig::new().temporary(true);
+    let mut host = HostConfig::default()
+        .with_sled_config(sc)
+        .with_tcp_config(None)
+        .with_udp_config(Some(UdpConfig::from_addr(addr).set_psk(psk.clone())))
+        .build()
+        .unwrap();
+    host.start().unwrap();
+
+    let node = NodeConfig::<Blocking, Udp, usize>::new("psk")
+        .with_config(
+            NetworkConfig::<Blocking, Udp>::default()
+                .set_host_addr(addr)
+                .set_psk(psk),
+        )
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+    node.publish(1).unwrap();
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(node.request().unwrap().data, 1);
+
+    // Datagrams sealed with another key, or not at all, are dropped by the Host, so the
+    // handshake goes unanswered
+    let stranger = NodeConfig::<Blocking, Udp, usize>::new("psk")
+        .with_config(
+            NetworkConfig::<Blocking, Udp>::default()
+                .set_host_addr(addr)
+                .set_psk(PreSharedKey::generate()),
+        )
+        .build()
+        .unwrap()
+        .activate();
+    assert_eq!(stranger.err(), Some(Error::HandshakeTimeout));
+    let plain = NodeConfig::<Blocking, Udp, usize>::new("psk")
+        .with_config(NetworkConfig::<Blocking, Udp>::default().set_host_addr(addr))
+        .build()
+        .unwrap()
+        .activate();
+    assert_eq!(plain.err(), Some(Error::HandshakeTimeout));
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(node.request().unwrap().data, 1);
+}
+
+#[test]
+fn access_control() {
+    use meadow::error::HostError;
+    use meadow::host::{AccessControl, TcpConfig};
+
+    let addr: std::net::SocketAddr = "127.0.0.1:25109".parse().unwrap();
+    let sc = SledConfig::new().temporary(true);
+    let mut host = HostConfig::default()
+        .with_sled_config(sc)
+        .with_tcp_config(Some(TcpConfig::from_addr(addr)))
+        .with_udp_config(None)
+        .with_access_control(Some(
+            AccessControl::new()
+                .allow_publish("cmd/throttle", "cmd/*")
+                .allow_request("*", "*"),
+        ))
+        .build()
+        .unwrap();
+    host.start().unwrap();
+
+    // Nodes are identified by the name sent in their handshake, which is their topic
+    let node = |topic: &str| {
+        NodeConfig::<Blocking, Tcp, usize>::new(topic)
+            .with_config(NetworkConfig::<Blocking, Tcp>::default().set_host_addr(addr))
+            .build()
+            .unwrap()
+            .activate()
+            .unwrap()
+    };
+    let throttle = node("cmd/throttle");
+    throttle.publish(1).unwrap();
+    assert_eq!(throttle.request().unwrap().data, 1);
+
+    let brake = node("cmd/brake");
+    assert_eq!(
+        brake.publish(1),
+        Err(Error::HostOperation(HostError::Unauthorized))
+    );
+    // Requests are allowed, but nothing was published
+    assert_eq!(brake.request(), Err(Error::NoNthValue));
+}
+
+#[test]
+fn handshake_tokens() {
+    use meadow::error::HostError;
+    use meadow::host::{TcpConfig, UdpConfig};
+
+    let tcp_addr: std::net::SocketAddr = "127.0.0.1:25110".parse().unwrap();
+    let udp_addr: std::net::SocketAddr = "127.0.0.1:25111".parse().unwrap();
+    let sc = SledConfig::new().temporary(true);
+    let mut host = HostConfig::default()
+        .with_sled_config(sc)
+        .with_tcp_config(Some(TcpConfig::from_addr(tcp_addr)))
+        .with_udp_config(Some(UdpConfig::from_addr(udp_addr)))
+        .with_node_token("controller", "secret")
+        .build()
+        .unwrap();
+    host.start().unwrap();
+
+    let controller = NodeConfig::<Blocking, Tcp, usize>::new("cmd/throttle")
+        .with_config(NetworkConfig::<Blocking, Tcp>::default().set_host_addr(tcp_addr))
+        .with_name("controller")
+        .with_token("secret")
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+    controller.publish(1).unwrap();
+    assert_eq!(controller.request().unwrap().data, 1);
+
+    let impostor = NodeConfig::<Blocking, Tcp, usize>::new("cmd/throttle")
+        .with_config(NetworkConfig::<Blocking, Tcp>::default().set_host_addr(tcp_addr))
+        .with_name("controller")
+        .with_token("guess")
+        .build()
+        .unwrap()
+        .activate();
+    assert!(matches!(
+        impostor.err(),
+        Some(Error::HostOperation(HostError::HandshakeRejected(_)))
+    ));
+
+    // Once tokens are registered, Nodes without one can't connect over any transport
+    let anonymous = NodeConfig::<Blocking, Udp, usize>::new("cmd/throttle")
+        .with_config(NetworkConfig::<Blocking, Udp>::default().set_host_addr(udp_addr))
+        .build()
+        .unwrap()
+        .activate();
+    assert!(matches!(
+        anonymous.err(),
+        Some(Error::HostOperation(HostError::HandshakeRejected(_)))
+    ));
+    let controller = NodeConfig::<Blocking, Udp, usize>::new("cmd/throttle")
+        .with_config(NetworkConfig::<Blocking, Udp>::default().set_host_addr(udp_addr))
+        .with_name("controller")
+        .with_token("secret")
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+    assert_eq!(controller.request().unwrap().data, 1);
+}
+
+#[test]
+fn silent_handshake() {
+    use meadow::host::TcpConfig;
+    use std::io::Read;
+
+    let addr: std::net::SocketAddr = "127.0.0.1:25126".parse().unwrap();
+    let sc = SledConfig::new().temporary(true);
+    let mut host = HostConfig::default()
+        .with_sled_config(sc)
+        .with_tcp_config(Some(TcpConfig::from_addr(addr)))
+        .with_handshake_timeout(Duration::from_millis(200))
+        .build()
+        .unwrap();
+    host.start().unwrap();
+
+    // A client that connects but never sends a handshake doesn't hold up other Nodes. The
+    // listener binds in the background, so it may take a few tries to reach it.
+    let mut silent = (0..100)
+        .find_map(|_| match std::net::TcpStream::connect(addr) {
+            Ok(stream) => Some(stream),
+            Err(_) => {
+                thread::sleep(Duration::from_millis(10));
+                None
+            }
+        })
+        .unwrap();
+    let node = NodeConfig::<Blocking, Tcp, usize>::new("count")
+        .with_config(NetworkConfig::<Blocking, Tcp>::default().set_host_addr(addr))
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+    node.publish(1).unwrap();
+    assert_eq!(node.request().unwrap().data, 1);
+
+    // and is hung up on once its time is up
+    silent
+        .set_read_timeout(Some(Duration::from_secs(2)))
+        .unwrap();
+    assert_eq!(silent.read(&mut [0u8; 16]).unwrap(), 0);
+    assert_eq!(host.connections().unwrap().len(), 1);
+}
+
+#[test]
+fn type_mismatch() {
+    use meadow::error::HostError;
+    use meadow::host::TcpConfig;
+
+    let addr: std::net::SocketAddr = "127.0.0.1:25112".parse().unwrap();
+    let sc = SledConfig::new().temporary(true);
+    let mut host = HostConfig::default()
+        .with_sled_config(sc)
+        .with_tcp_config(Some(TcpConfig::from_addr(addr)))
+        .with_udp_config(None)
+        .with_topic_type::<Pose>("pose")
+        .build()
+        .unwrap();
+    host.start().unwrap();
+
+    let cfg = NetworkConfig::<Blocking, Tcp>::default().set_host_addr(addr);
+    let count = NodeConfig::<Blocking, Tcp, usize>::new("count")
+        .with_config(cfg.clone())
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+    count.publish(1).unwrap();
+
+    // The first type published to a topic is the one it holds
+    let float = NodeConfig::<Blocking, Tcp, f32>::new("count")
+        .with_config(cfg.clone())
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+    let mismatch = Err(Error::HostOperation(HostError::TypeMismatch {
+        expected: "usize".into(),
+        found: "f32".into(),
+    }));
+    assert_eq!(float.publish(1.0).map(|_| ()), mismatch);
+    // Requesters find out before trying to decode the data
+    assert_eq!(float.request().map(|_| ()), mismatch);
+    assert_eq!(count.request().unwrap().data, 1);
+
+    // Topics can also be registered with a type before anything is published
+    let pose = NodeConfig::<Blocking, Tcp, usize>::new("pose")
+        .with_config(cfg)
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+    assert!(matches!(
+        pose.publish(1),
+        Err(Error::HostOperation(HostError::TypeMismatch { .. }))
+    ));
+    assert_eq!(host.topics(), vec!["count".to_string()]);
+}
+
+#[test]
+fn nested_schemas() {
+    use meadow::schema::Schema;
+
+    mod v1 {
+        use meadow::schema::Schema;
+        use meadow::Serialize;
+        #[derive(Serialize, Schema)]
+        pub struct Point {
+            pub x: f32,
+            pub y: f32,
+        }
+        #[derive(Serialize, Schema)]
+        pub struct Path {
+            pub points: Vec<Point>,
+        }
+    }
+    mod v2 {
+        use meadow::schema::Schema;
+        use meadow::Serialize;
+        #[derive(Serialize, Schema)]
+        pub struct Point {
+            pub x: f64,
+            pub y: f64,
+            pub z: f64,
+        }
+        #[derive(Serialize, Schema)]
+        pub struct Path {
+            pub points: Vec<Point>,
+        }
+    }
+
+    // Fields are described by their types' layouts rather than just their names
+    assert_eq!(
+        v1::Path::description(),
+        "Path struct { points: Vec<Point struct { x: f32, y: f32 }> }"
+    );
+    assert_ne!(v1::Path::id(), v2::Path::id());
+}
+
+#[test]
+fn schema_registry() {
+    use meadow::host::TcpConfig;
+    use meadow::schema::{self, Schema};
+
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Schema)]
+    #[schema(name = "Odometry")]
+    struct Odom {
+        x: f64,
+        heading: Option<f32>,
+    }
+    schema::register::<Odom>();
+    assert_eq!(
+        Odom::description(),
+        "Odometry struct { x: f64, heading: Option<f32> }"
+    );
+
+    let addr: std::net::SocketAddr = "127.0.0.1:25113".parse().unwrap();
+    let sc = SledConfig::new().temporary(true);
+    let mut host = HostConfig::default()
+        .with_sled_config(sc)
+        .with_tcp_config(Some(TcpConfig::from_addr(addr)))
+        .with_udp_config(None)
+        .build()
+        .unwrap();
+    host.start().unwrap();
+
+    let cfg = NetworkConfig::<Blocking, Tcp>::default().set_host_addr(addr);
+    let node = NodeConfig::<Blocking, Tcp, Odom>::new("odom")
+        .with_config(cfg.clone())
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+    let odom = Odom {
+        x: 1.0,
+        heading: None,
+    };
+    node.publish(odom.clone()).unwrap();
+    let msg = node.request().unwrap();
+    assert_eq!(msg.data, odom);
+    // Messages are labelled with the schema rather than the type's path
+    assert_eq!(msg.data_type, Odom::id());
+
+    // The Node's handshake added its schema to the registry, where tools can find it
+    let described = host.schema("odom").unwrap().unwrap();
+    assert_eq!(described, SchemaDescription::of::<Odom>());
+    let registry = NodeConfig::<Blocking, Tcp, SchemaDescription>::new("_meadow/schemas/Odometry")
+        .with_config(cfg)
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+    assert_eq!(registry.request().unwrap().data, described);
+    host.insert("plain", 1usize).unwrap();
+    assert_eq!(host.schema("plain").unwrap(), None);
+}
+
+#[test]
+#[cfg(feature = "json")]
+fn json_encoding() {
+    let sc = SledConfig::new().temporary(true);
+    let mut host = HostConfig::default()
+        .with_sled_config(sc)
+        .with_tcp_config(None)
+        .with_udp_config(None)
+        .build()
+        .unwrap();
+    host.start().unwrap();
+
+    let cfg = NetworkConfig::<Blocking, InProcess>::default().set_host_handle(host.handle());
+    let writer = NodeConfig::<Blocking, InProcess, Pose>::new("pose")
+        .with_config(cfg.clone())
+        .with_encoding(Encoding::Json)
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+    let pose = Pose { x: 1.5, y: 2.0 };
+    writer.publish(pose.clone()).unwrap();
+
+    // The stored message records its encoding, so tools can read the payload without Rust
+    let (_, bytes) = host
+        .db()
+        .open_tree("pose")
+        .unwrap()
+        .last()
+        .unwrap()
+        .unwrap();
+    let generic: GenericMsg = postcard::from_bytes(&bytes).unwrap();
+    assert_eq!(generic.encoding, Encoding::Json);
+    assert_eq!(generic.data, br#"{"x":1.5,"y":2.0}"#);
+
+    // Readers decode whichever encoding the data was published in
+    let reader = NodeConfig::<Blocking, InProcess, Pose>::new("pose")
+        .with_config(cfg)
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+    assert_eq!(reader.request().unwrap().data, pose);
+    assert_eq!(host.get::<Pose>("pose").unwrap().data, pose);
+}
+
+#[test]
+#[cfg(all(unix, feature = "zerocopy"))]
+fn raw_views() {
+    use meadow::raw::{FromBytes, Immutable, IntoBytes, KnownLayout, Unaligned};
+    use zerocopy::little_endian::F32;
+
+    #[derive(Debug, Clone, PartialEq, FromBytes, IntoBytes, KnownLayout, Immutable, Unaligned)]
+    #[repr(C)]
+    struct Scan {
+        ranges: [F32; 64],
+    }
+    let scan = |i: usize| Scan {
+        ranges: [F32::new(i as f32); 64],
+    };
+
+    let path = std::env::temp_dir().join("meadow_raw_views.sock");
+    let host = start_host_on(Listener::Uds(path.clone()), HostConfig::default()).unwrap();
+    let cfg = NetworkConfig::<Blocking, Uds>::default().set_socket_path(&path);
+    let node = NodeConfig::<Blocking, Uds, Raw<Scan>>::new("scan")
+        .with_config(cfg)
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+    for i in 0..3 {
+        node.publish(Raw(scan(i))).unwrap();
+    }
+    // Views read the data where it was received, without deserializing it
+    let first = node.request_view(|view| view.data.ranges[0].get()).unwrap();
+    assert_eq!(first, 2.0);
+    let copied = node.request_nth_back_view(2, |view| view.to_msg()).unwrap();
+    assert_eq!(copied.data, Raw(scan(0)));
+    // Raw data is still an ordinary message for everything else
+    assert_eq!(node.request().unwrap().data, Raw(scan(2)));
+
+    let cfg = NetworkConfig::<Blocking, InProcess>::default().set_host_handle(host.handle());
+    let node = NodeConfig::<Blocking, InProcess, Raw<Scan>>::new("scan")
+        .with_config(cfg.clone())
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+    assert!(node.request_view(|view| view.data == &scan(2)).unwrap());
+    // Viewing a topic that holds another type fails rather than misreading it
+    let other = NodeConfig::<Blocking, InProcess, Raw<[u8; 4]>>::new("scan")
+        .with_config(cfg)
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+    assert!(matches!(
+        other.request_view(|_| ()),
+        Err(Error::HostOperation(
+            meadow::error::HostError::TypeMismatch { .. }
+        ))
+    ));
+}
+
+#[test]
+#[cfg(feature = "zerocopy")]
+fn raw_views_with_heartbeats() {
+    use meadow::host::TcpConfig;
+
+    let addr: std::net::SocketAddr = "127.0.0.1:25125".parse().unwrap();
+    let sc = SledConfig::new().temporary(true);
+    let mut host = HostConfig::default()
+        .with_sled_config(sc)
+        .with_tcp_config(Some(TcpConfig::from_addr(addr)))
+        .build()
+        .unwrap();
+    host.start().unwrap();
+
+    // Heartbeats that time out almost at once leave their answers to be read by requests
+    let heartbeat = HeartbeatConfig::new(Duration::from_millis(2), Duration::ZERO);
+    let cfg = NetworkConfig::<Blocking, Tcp>::default()
+        .set_host_addr(addr)
+        .set_heartbeat(Some(heartbeat));
+    let node = NodeConfig::<Blocking, Tcp, Raw<[u8; 16]>>::new("bytes")
+        .with_config(cfg)
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+    node.publish(Raw([7; 16])).unwrap();
+    for _ in 0..200 {
+        assert!(node.request_view(|view| view.data == &[7; 16]).unwrap());
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+#[test]
+fn compression() {
+    let sc = SledConfig::new().temporary(true);
+    let mut host = HostConfig::default()
+        .with_sled_config(sc)
+        .with_tcp_config(None)
+        .with_udp_config(None)
+        .with_topic_compression("map", Compression::Lz4)
+        .build()
+        .unwrap();
+    host.start().unwrap();
+    let stored = |topic: &str| -> GenericMsg {
+        let (_, bytes) = host.db().open_tree(topic).unwrap().last().unwrap().unwrap();
+        postcard::from_bytes(&bytes).unwrap()
+    };
+
+    let cfg = NetworkConfig::<Blocking, InProcess>::default().set_host_handle(host.handle());
+    let image = NodeConfig::<Blocking, InProcess, Vec<u8>>::new("image")
+        .with_config(cfg.clone())
+        .with_compression(Compression::Lz4)
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+    let frame = vec![0u8; 64 * 1024];
+    image.publish(frame.clone()).unwrap();
+    // Compressed by the Node, stored as it was received, and decompressed when requested
+    let msg = stored("image");
+    assert_eq!(msg.compression, Compression::Lz4);
+    assert!(msg.data.len() < 1024);
+    assert_eq!(image.request().unwrap().data, frame);
+
+    // Topics can be compressed by the Host, whatever their publishers do
+    let map = NodeConfig::<Blocking, InProcess, Vec<u8>>::new("map")
+        .with_config(cfg)
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+    map.publish(frame.clone()).unwrap();
+    assert_eq!(stored("map").compression, Compression::Lz4);
+    assert_eq!(map.request().unwrap().data, frame);
+    // Unless compression wouldn't make the data any smaller
+    map.publish(vec![1, 2, 3]).unwrap();
+    assert_eq!(stored("map").compression, Compression::None);
+    assert_eq!(host.get::<Vec<u8>>("map").unwrap().data, vec![1, 2, 3]);
+}
+
+/// Behavior every transport shares, written once against the Node traits
+fn exercise_transport<N: Publisher<Pose> + Requester<Pose>>(node: &N, topic: &str) {
+    for i in 0..5 {
+        let pose = Pose {
+            x: i as f32,
+            y: -i as f32,
+        };
+        node.publish(pose).unwrap();
+        // UDP publishes aren't acknowledged
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(node.request().unwrap().data, Pose { x: 4.0, y: -4.0 });
+    assert_eq!(
+        node.request_nth_back(2).unwrap().data,
+        Pose { x: 2.0, y: -2.0 }
+    );
+    assert!(node.request_nth_back(5).is_err());
+    assert!(node.topics().unwrap().data.contains(&topic.to_string()));
+}
+
+fn exercise_subscription<N: Subscriber<Pose>>(node: &N) {
+    for _ in 0..50 {
+        if let Ok(msg) = node.get_subscribed_data() {
+            assert_eq!(msg.data, Pose { x: 4.0, y: -4.0 });
+            return;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    panic!("no subscribed data received");
+}
+
+async fn exercise_transport_async<N: AsyncPublisher<Pose> + AsyncRequester<Pose>>(
+    node: &N,
+    topic: &str,
+) {
+    for i in 0..5 {
+        let pose = Pose {
+            x: i as f32,
+            y: -i as f32,
+        };
+        node.publish(pose).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(node.request().await.unwrap().data, Pose { x: 4.0, y: -4.0 });
+    assert_eq!(
+        node.request_nth_back(2).await.unwrap().data,
+        Pose { x: 2.0, y: -2.0 }
+    );
+    assert!(node.request_nth_back(5).await.is_err());
+    assert!(node
+        .topics()
+        .await
+        .unwrap()
+        .data
+        .contains(&topic.to_string()));
+}
+
+async fn exercise_subscription_async<N: AsyncSubscriber<Pose>>(node: &N) {
+    for _ in 0..50 {
+        if let Ok(msg) = node.get_subscribed_data().await {
+            assert_eq!(msg.data, Pose { x: 4.0, y: -4.0 });
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("no subscribed data received");
+}
+
+/// Configuration of the Host the transport matrix runs against, which also serves UDS, SHM and
+/// QUIC where they're available, on the sockets named `_name` and at `_quic_addr`
+fn matrix_config(_name: &str, _quic_addr: std::net::SocketAddr) -> HostConfig {
+    #[cfg(feature = "quic")]
+    initialize();
+    let cfg = HostConfig::default();
+    #[cfg(unix)]
+    let cfg = cfg.with_uds_config(Some(UdsConfig::new(matrix_path(_name, "uds"))));
+    #[cfg(all(unix, feature = "shm"))]
+    let cfg = cfg.with_shm_config(Some(
+        ShmConfig::new(matrix_path(_name, "shm")).set_ring_size(4 * 1024 * 1024),
+    ));
+    #[cfg(feature = "quic")]
+    let cfg = cfg.with_quic_config(Some(meadow::host::QuicConfig::from_addr(_quic_addr)));
+    cfg
+}
+
+/// Socket path of a transport matrix Host's local `transport`
+#[cfg(unix)]
+fn matrix_path(name: &str, transport: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("meadow_{}_{}.sock", name, transport))
+}
+
+macro_rules! transport_matrix {
+    ($interface:ty, $setter:ident($target:expr)) => {{
+        let topic = format!("matrix_{}", stringify!($interface).to_lowercase());
+        let cfg = NetworkConfig::<Blocking, $interface>::default().$setter($target);
+        let node = NodeConfig::<Blocking, $interface, Pose>::new(&topic)
+            .with_config(cfg.clone())
+            .build()
+            .unwrap()
+            .activate()
+            .unwrap();
+        exercise_transport(&node, &topic);
+        let subscriber = NodeConfig::<Blocking, $interface, Pose>::new(&topic)
+            .with_config(cfg)
+            .build()
+            .unwrap()
+            .subscribe(Duration::from_millis(10))
+            .unwrap();
+        exercise_subscription(&subscriber);
+    }};
+}
+
+#[test]
+fn transport_matrix() {
+    let addr: std::net::SocketAddr = "127.0.0.1:25114".parse().unwrap();
+    let quic_addr: std::net::SocketAddr = "127.0.0.1:25115".parse().unwrap();
+    let host = start_host_on(Listener::Ip(addr), matrix_config("matrix", quic_addr)).unwrap();
+
+    transport_matrix!(Tcp, set_host_addr(addr));
+    transport_matrix!(Udp, set_host_addr(addr));
+    #[cfg(feature = "quic")]
+    transport_matrix!(Quic, set_host_addr(quic_addr));
+    #[cfg(unix)]
+    transport_matrix!(Uds, set_socket_path(matrix_path("matrix", "uds")));
+    #[cfg(all(unix, feature = "shm"))]
+    transport_matrix!(Shm, set_socket_path(matrix_path("matrix", "shm")));
+    transport_matrix!(InProcess, set_host_handle(host.handle()));
+}
+
+macro_rules! transport_matrix_async {
+    ($interface:ty, $setter:ident($target:expr)) => {{
+        let topic = format!("matrix_{}", stringify!($interface).to_lowercase());
+        let cfg = NetworkConfig::<Nonblocking, $interface>::default().$setter($target);
+        let rt_cfg = RuntimeConfig::default()
+            .with_owned_runtime(false)
+            .with_rt_handle(Some(tokio::runtime::Handle::current()));
+        let node = NodeConfig::<Nonblocking, $interface, Pose>::new(&topic)
+            .with_config(cfg.clone())
+            .with_runtime_config(rt_cfg.clone())
+            .build()
+            .unwrap()
+            .activate()
+            .await
+            .unwrap();
+        exercise_transport_async(&node, &topic).await;
+        let subscriber = NodeConfig::<Nonblocking, $interface, Pose>::new(&topic)
+            .with_config(cfg)
+            .with_runtime_config(rt_cfg)
+            .build()
+            .unwrap()
+            .subscribe(Duration::from_millis(10))
+            .await
+            .unwrap();
+        exercise_subscription_async(&subscriber).await;
+    }};
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn transport_matrix_nonblocking() {
+    let addr: std::net::SocketAddr = "127.0.0.1:25116".parse().unwrap();
+    let quic_addr: std::net::SocketAddr = "127.0.0.1:25117".parse().unwrap();
+    let host = tokio::task::spawn_blocking(move || {
+        start_host_on(Listener::Ip(addr), matrix_config("matrix_nb", quic_addr)).unwrap()
+    })
+    .await
+    .unwrap();
+
+    transport_matrix_async!(Tcp, set_host_addr(addr));
+    transport_matrix_async!(Udp, set_host_addr(addr));
+    #[cfg(feature = "quic")]
+    transport_matrix_async!(Quic, set_host_addr(quic_addr));
+    #[cfg(unix)]
+    transport_matrix_async!(Uds, set_socket_path(matrix_path("matrix_nb", "uds")));
+    #[cfg(all(unix, feature = "shm"))]
+    transport_matrix_async!(Shm, set_socket_path(matrix_path("matrix_nb", "shm")));
+    transport_matrix_async!(InProcess, set_host_handle(host.handle()));
+
+    tokio::task::spawn_blocking(move || drop(host))
+        .await
+        .unwrap();
+}
+
+#[test]
+fn transport_fallback() {
+    // The Host only listens on UDP, as on a network where TCP is blocked
+    let addr: std::net::SocketAddr = "127.0.0.1:25118".parse().unwrap();
+    let sc = SledConfig::new().temporary(true);
+    let mut host = HostConfig::default()
+        .with_sled_config(sc)
+        .with_tcp_config(None)
+        .with_udp_config(Some(UdpConfig::from_addr(addr)))
+        .build()
+        .unwrap();
+    host.start().unwrap();
+
+    let transports: Vec<Transport> = "TCP, udp".split(',').map(|t| t.parse().unwrap()).collect();
+    assert_eq!(transports, [Transport::Tcp, Transport::Udp]);
+    assert!("carrier pigeon".parse::<Transport>().is_err());
+    let cfg = DynamicNodeConfig::<Blocking, Pose>::new("fallback")
+        .with_transports(transports)
+        .with_tcp_config(NetworkConfig::<Blocking, Tcp>::default().set_host_addr(addr))
+        .with_udp_config(NetworkConfig::<Blocking, Udp>::default().set_host_addr(addr));
+
+    let node = cfg.clone().build().unwrap().activate().unwrap();
+    assert_eq!(node.transport(), Transport::Udp);
+    exercise_transport(&node, "fallback");
+    let subscriber = cfg
+        .clone()
+        .build()
+        .unwrap()
+        .subscribe(Duration::from_millis(10))
+        .unwrap();
+    assert_eq!(subscriber.transport(), Transport::Udp);
+    exercise_subscription(&subscriber);
+
+    // Every failed attempt is reported when no transport reaches the Host
+    let unreachable: std::net::SocketAddr = "127.0.0.1:25119".parse().unwrap();
+    let result = cfg
+        .with_tcp_config(NetworkConfig::<Blocking, Tcp>::default().set_host_addr(unreachable))
+        .with_udp_config(NetworkConfig::<Blocking, Udp>::default().set_host_addr(unreachable))
+        .build()
+        .unwrap()
+        .activate();
+    match result {
+        Err(Error::NoTransport(failures)) => {
+            let tried: Vec<Transport> = failures.iter().map(|(t, _)| *t).collect();
+            assert_eq!(tried, [Transport::Tcp, Transport::Udp]);
+        }
+        other => panic!("expected every transport to fail, got {:?}", other),
+    }
+}
+
+#[test]
+fn graceful_shutdown() {
+    use meadow::error::HostError;
+    use meadow::host::TcpConfig;
+
+    let addr: std::net::SocketAddr = "127.0.0.1:25120".parse().unwrap();
+    let sc = SledConfig::new().temporary(true);
+    let mut host = HostConfig::default()
+        .with_sled_config(sc)
+        .with_tcp_config(Some(TcpConfig::from_addr(addr)))
+        .with_udp_config(None)
+        .with_shutdown_timeout(Duration::from_secs(2))
+        .build()
+        .unwrap();
+    host.start().unwrap();
+
+    let cfg = NetworkConfig::<Blocking, Tcp>::default().set_host_addr(addr);
+    let node = NodeConfig::<Blocking, Tcp, usize>::new("count")
+        .with_config(cfg.clone())
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+    node.publish(1).unwrap();
+    let subscriber = NodeConfig::<Blocking, Tcp, usize>::new("count")
+        .with_config(cfg.clone())
+        .build()
+        .unwrap()
+        .subscribe(Duration::from_millis(10))
+        .unwrap();
+    thread::sleep(Duration::from_millis(50));
+
+    let report = host.stop().unwrap();
+    assert_eq!((report.drained, report.aborted), (2, 0));
+    assert!(report.elapsed < Duration::from_secs(2));
+
+    // Connected Nodes are told why the Host went away, and no new ones are accepted
+    let shutting_down = Err(Error::HostOperation(HostError::ShuttingDown));
+    assert_eq!(node.request().map(|_| ()), shutting_down);
+    let in_process = NodeConfig::<Blocking, InProcess, usize>::new("count")
+        .with_config(NetworkConfig::<Blocking, InProcess>::default().set_host_handle(host.handle()))
+        .build()
+        .unwrap()
+        .activate();
+    assert_eq!(in_process.map(|_| ()), shutting_down);
+    drop(subscriber);
+
+    // The same Host comes back on the same port, with everything it stored
+    host.start().unwrap();
+    let node = NodeConfig::<Blocking, Tcp, usize>::new("count")
+        .with_config(cfg)
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+    assert_eq!(node.request().unwrap().data, 1);
+    node.publish(2).unwrap();
+    assert_eq!(node.request().unwrap().data, 2);
+}
+
+#[cfg(feature = "quic")]
+#[test]
+fn quic_graceful_shutdown() {
+    initialize();
+    let addr: std::net::SocketAddr = "127.0.0.1:25129".parse().unwrap();
+    let sc = SledConfig::new().temporary(true);
+    let mut host = HostConfig::default()
+        .with_sled_config(sc)
+        .with_tcp_config(None)
+        .with_udp_config(None)
+        .with_quic_config(Some(meadow::host::QuicConfig::from_addr(addr)))
+        .with_shutdown_timeout(Duration::from_secs(2))
+        .build()
+        .unwrap();
+    host.start().unwrap();
+
+    let cfg = NetworkConfig::<Blocking, Quic>::default().set_host_addr(addr);
+    let node = NodeConfig::<Blocking, Quic, usize>::new("count")
+        .with_config(cfg.clone())
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+    node.publish(1).unwrap();
+    let subscriber = NodeConfig::<Blocking, Quic, usize>::new("count")
+        .with_config(cfg)
+        .build()
+        .unwrap()
+        .subscribe(Duration::from_millis(10))
+        .unwrap();
+    thread::sleep(Duration::from_millis(50));
+
+    // Subscriptions end with the shutdown, so each connection's streams drain in time
+    let report = host.stop().unwrap();
+    assert_eq!((report.drained, report.aborted), (2, 0));
+    assert!(report.elapsed < Duration::from_secs(2));
+    assert!(node.request().is_err());
+    drop(subscriber);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn host_on_external_runtime() {
+    use meadow::host::TcpConfig;
+
+    let addr: std::net::SocketAddr = "127.0.0.1:25121".parse().unwrap();
+    let runtime_cfg = RuntimeConfig::default()
+        .with_owned_runtime(false)
+        .with_rt_handle(Some(tokio::runtime::Handle::current()));
+    let sc = SledConfig::new().temporary(true);
+    let mut host = HostConfig::default()
+        .with_sled_config(sc)
+        .with_tcp_config(Some(TcpConfig::from_addr(addr)))
+        .with_udp_config(None)
+        .with_runtime_config(runtime_cfg.clone())
+        .build()
+        .unwrap();
+    assert!(host.runtime().is_none());
+    host.start_async().await.unwrap();
+
+    let node = NodeConfig::<Nonblocking, Tcp, usize>::new("count")
+        .with_config(NetworkConfig::<Nonblocking, Tcp>::default().set_host_addr(addr))
+        .with_runtime_config(runtime_cfg)
+        .build()
+        .unwrap()
+        .activate()
+        .await
+        .unwrap();
+    node.publish(1).await.unwrap();
+    assert_eq!(node.request().await.unwrap().data, 1);
+
+    let report = host.stop_async().await.unwrap();
+    assert_eq!((report.drained, report.aborted), (1, 0));
+    host.start_async().await.unwrap();
+    drop(host);
+
+    // A Host owning its runtime can also be built and dropped from async code
+    let sc = SledConfig::new().temporary(true);
+    let host = HostConfig::default()
+        .with_sled_config(sc)
+        .with_tcp_config(None)
+        .with_udp_config(None)
+        .build()
+        .unwrap();
+    drop(host);
+}
+
+#[test]
+fn connection_registry() {
+    use meadow::host::{ConnectionInfo, TcpConfig, CONNECTIONS_TOPIC};
+
+    let addr: std::net::SocketAddr = "127.0.0.1:25122".parse().unwrap();
+    let sc = SledConfig::new().temporary(true);
+    let mut host = HostConfig::default()
+        .with_sled_config(sc)
+        .with_tcp_config(Some(TcpConfig::from_addr(addr)))
+        .with_udp_config(Some(UdpConfig::from_addr(addr)))
+        .build()
+        .unwrap();
+    host.start().unwrap();
+
+    let tcp_cfg = NetworkConfig::<Blocking, Tcp>::default().set_host_addr(addr);
+    let publisher = NodeConfig::<Blocking, Tcp, usize>::new("count")
+        .with_name("publisher")
+        .with_config(tcp_cfg.clone())
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+    for i in 0..3 {
+        publisher.publish(i).unwrap();
+    }
+    let subscriber = NodeConfig::<Blocking, Tcp, usize>::new("count")
+        .with_name("subscriber")
+        .with_config(tcp_cfg.clone())
+        .build()
+        .unwrap()
+        .subscribe(Duration::from_millis(10))
+        .unwrap();
+    let udp = NodeConfig::<Blocking, Udp, usize>::new("count")
+        .with_name("udp")
+        .with_config(NetworkConfig::<Blocking, Udp>::default().set_host_addr(addr))
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+    udp.publish(3).unwrap();
+    let in_process = NodeConfig::<Blocking, InProcess, usize>::new("count")
+        .with_name("in_process")
+        .with_config(NetworkConfig::<Blocking, InProcess>::default().set_host_handle(host.handle()))
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+    in_process.publish(4).unwrap();
+    thread::sleep(Duration::from_millis(50));
+
+    let connections = host.connections().unwrap();
+    let described: Vec<(&str, Transport)> = connections
+        .iter()
+        .map(|info| (info.name.as_str(), info.transport))
+        .collect();
+    assert_eq!(
+        described,
+        [
+            ("publisher", Transport::Tcp),
+            ("subscriber", Transport::Tcp),
+            ("udp", Transport::Udp),
+            ("in_process", Transport::InProcess),
+        ]
+    );
+    // Every publish over TCP is acknowledged
+    let published = &connections[0];
+    assert_eq!((published.msgs_received, published.msgs_sent), (3, 3));
+    assert!(published.bytes_received > 0 && published.bytes_sent > 0);
+    assert!(published.remote_addr.starts_with("127.0.0.1:"));
+    assert_eq!(connections[1].subscriptions, ["count".to_string()]);
+    assert!(connections[1].msgs_sent > 1);
+    assert_eq!(connections[2].msgs_received, 1);
+    assert_eq!(
+        host.connection(connections[3].id),
+        Some(connections[3].clone())
+    );
+
+    // Nodes can query the registry too
+    let monitor = NodeConfig::<Blocking, Tcp, Vec<ConnectionInfo>>::new(CONNECTIONS_TOPIC)
+        .with_name("monitor")
+        .with_config(tcp_cfg)
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+    let listed = monitor.request().unwrap().data;
+    assert_eq!(listed.len(), 5);
+    assert_eq!(listed[4].name, "monitor");
+
+    // Connections are pruned once the Node hangs up
+    drop(publisher);
+    drop(subscriber);
+    drop(in_process);
+    thread::sleep(Duration::from_millis(100));
+    let remaining: Vec<String> = host
+        .connections()
+        .unwrap()
+        .into_iter()
+        .map(|info| info.name)
+        .collect();
+    assert_eq!(remaining, ["udp", "monitor"]);
+}
+
+#[test]
+fn heartbeats() {
+    use meadow::host::TcpConfig;
+
+    let addr: std::net::SocketAddr = "127.0.0.1:25123".parse().unwrap();
+    let heartbeat = HeartbeatConfig::new(Duration::from_millis(50), Duration::from_millis(250));
+    let sc = SledConfig::new().temporary(true);
+    let mut host = HostConfig::default()
+        .with_sled_config(sc)
+        .with_tcp_config(Some(TcpConfig::from_addr(addr)))
+        .with_udp_config(Some(UdpConfig::from_addr(addr)))
+        .with_heartbeat(Some(heartbeat))
+        .build()
+        .unwrap();
+    host.start().unwrap();
+
+    let tcp = NodeConfig::<Blocking, Tcp, usize>::new("count")
+        .with_name("tcp")
+        .with_config(
+            NetworkConfig::<Blocking, Tcp>::default()
+                .set_host_addr(addr)
+                .set_heartbeat(Some(heartbeat)),
+        )
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+    let udp_cfg = NetworkConfig::<Blocking, Udp>::default().set_host_addr(addr);
+    let udp = NodeConfig::<Blocking, Udp, usize>::new("count")
+        .with_name("udp")
+        .with_config(udp_cfg.clone().set_heartbeat(Some(heartbeat)))
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+    let _silent = NodeConfig::<Blocking, Udp, usize>::new("count")
+        .with_name("silent")
+        .with_config(udp_cfg.set_heartbeat(None))
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+
+    // Idle Nodes stay connected as long as they keep sending heartbeats
+    thread::sleep(Duration::from_millis(500));
+    let connections = host.connections().unwrap();
+    let described: Vec<(&str, Liveness)> = connections
+        .iter()
+        .map(|info| (info.name.as_str(), info.liveness))
+        .collect();
+    assert_eq!(
+        described,
+        [("tcp", Liveness::Alive), ("udp", Liveness::Alive)]
+    );
+    assert_eq!(tcp.host_liveness(), Liveness::Alive);
+    assert_eq!(udp.host_liveness(), Liveness::Alive);
+    tcp.publish(1).unwrap();
+    assert_eq!(tcp.request().unwrap().data, 1);
+
+    // Nodes notice once the Host goes away
+    host.stop().unwrap();
+    thread::sleep(Duration::from_millis(500));
+    assert_eq!(tcp.host_liveness(), Liveness::Dead);
+    assert_eq!(udp.host_liveness(), Liveness::Dead);
+}
+
+#[test]
+fn udp_peer_timeout() {
+    let start = |udp_cfg: UdpConfig| {
+        let addr = udp_cfg.socket_addr().unwrap();
+        let sc = SledConfig::new().temporary(true);
+        let mut host = HostConfig::default()
+            .with_sled_config(sc)
+            .with_tcp_config(None)
+            .with_udp_config(Some(udp_cfg))
+            .with_heartbeat(None)
+            .build()
+            .unwrap();
+        host.start().unwrap();
+
+        let node = NodeConfig::<Blocking, Udp, usize>::new("count")
+            .with_config(
+                NetworkConfig::<Blocking, Udp>::default()
+                    .set_host_addr(addr)
+                    .set_heartbeat(None),
+            )
+            .build()
+            .unwrap()
+            .activate()
+            .unwrap();
+        node.publish(1).unwrap();
+        (host, node)
+    };
+    let addr: std::net::SocketAddr = "127.0.0.1:25127".parse().unwrap();
+    let (host, node) =
+        start(UdpConfig::from_addr(addr).set_peer_timeout(Duration::from_millis(200)));
+    let keeping: std::net::SocketAddr = "127.0.0.1:25131".parse().unwrap();
+    let (keeping_host, _keeping_node) = start(UdpConfig::from_addr(keeping));
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(host.connections().unwrap().len(), 1);
+    assert_eq!(keeping_host.connections().unwrap().len(), 1);
+
+    // Without heartbeats, UDP peers are only forgotten for going quiet if a timeout is set
+    thread::sleep(Duration::from_millis(1500));
+    assert!(host.connections().unwrap().is_empty());
+    assert_eq!(keeping_host.connections().unwrap().len(), 1);
+    // The Node shakes hands again when its request is refused
+    assert_eq!(node.request().unwrap().data, 1);
+    assert_eq!(host.connections().unwrap().len(), 1);
+}
+
+#[test]
+fn udp_malformed_subscription() {
+    use meadow::handshake::Handshake;
+
+    let addr: std::net::SocketAddr = "127.0.0.1:25128".parse().unwrap();
+    let sc = SledConfig::new().temporary(true);
+    let mut host = HostConfig::default()
+        .with_sled_config(sc)
+        .with_tcp_config(None)
+        .with_udp_config(Some(UdpConfig::from_addr(addr)))
+        .build()
+        .unwrap();
+    host.start().unwrap();
+
+    let socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+    socket
+        .set_read_timeout(Some(Duration::from_secs(2)))
+        .unwrap();
+    let mut buf = [0u8; 1024];
+    let mut exchange = |msg: GenericMsg| {
+        socket.send_to(&msg.as_bytes().unwrap(), addr).unwrap();
+        let (n, _) = socket.recv_from(&mut buf).unwrap();
+        postcard::from_bytes::<GenericMsg>(&buf[..n]).unwrap()
+    };
+    exchange(Handshake::new("raw").to_generic().unwrap());
+
+    // A subscription whose rate can't be read is refused rather than taking the Host down
+    let malformed = Msg::new(MsgType::Subscribe, "count", ())
+        .to_generic()
+        .unwrap();
+    assert!(matches!(
+        exchange(malformed).msg_type,
+        MsgType::Result(Err(_))
+    ));
+
+    let node = NodeConfig::<Blocking, Udp, usize>::new("count")
+        .with_config(NetworkConfig::<Blocking, Udp>::default().set_host_addr(addr))
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+    node.publish(1).unwrap();
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(node.request().unwrap().data, 1);
+}
+
+#[test]
+fn udp_host_restart() {
+    let addr: std::net::SocketAddr = "127.0.0.1:25130".parse().unwrap();
+    let sc = SledConfig::new().temporary(true);
+    let mut host = HostConfig::default()
+        .with_sled_config(sc)
+        .with_tcp_config(None)
+        .with_udp_config(Some(UdpConfig::from_addr(addr)))
+        .with_heartbeat(None)
+        .build()
+        .unwrap();
+    host.start().unwrap();
+
+    let node = NodeConfig::<Blocking, Udp, usize>::new("count")
+        .with_config(
+            NetworkConfig::<Blocking, Udp>::default()
+                .set_host_addr(addr)
+                .set_heartbeat(None),
+        )
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+    node.publish(1).unwrap();
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(node.request().unwrap().data, 1);
+
+    // The restarted Host has forgotten the Node, and says so to its first publish, which is lost
+    host.stop().unwrap();
+    host.start().unwrap();
+    thread::sleep(Duration::from_millis(50));
+    node.publish(2).unwrap();
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(host.connections().unwrap().len(), 0);
+
+    // Publishing again shakes hands first, so the Host takes the value
+    node.publish(3).unwrap();
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(host.connections().unwrap().len(), 1);
+    assert_eq!(node.request().unwrap().data, 3);
+}
+
+#[test]
+fn system_topics() {
+    use meadow::error::HostError;
+    use meadow::host::{HostStats, TcpConfig, TopicInfo, STATS_TOPIC, TOPICS_TOPIC};
+
+    let addr: std::net::SocketAddr = "127.0.0.1:25124".parse().unwrap();
+    let sc = SledConfig::new().temporary(true);
+    let mut host = HostConfig::default()
+        .with_sled_config(sc)
+        .with_tcp_config(Some(TcpConfig::from_addr(addr)))
+        .build()
+        .unwrap();
+    host.start().unwrap();
+    host.insert("local", 1usize).unwrap();
+
+    let tcp_cfg = NetworkConfig::<Blocking, Tcp>::default().set_host_addr(addr);
+    let publisher = NodeConfig::<Blocking, Tcp, usize>::new("count")
+        .with_config(tcp_cfg.clone())
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+    for i in 0..3 {
+        publisher.publish(i).unwrap();
+    }
+
+    let topics = NodeConfig::<Blocking, Tcp, Vec<TopicInfo>>::new(TOPICS_TOPIC)
+        .with_config(tcp_cfg.clone())
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+    let listed = topics.request().unwrap().data;
+    let names: Vec<&str> = listed.iter().map(|info| info.name.as_str()).collect();
+    assert_eq!(names, ["count", "local"]);
+    let count = &listed[0];
+    assert_eq!(count.msgs_published, 3);
+    assert_eq!(count.bytes_published, 3);
+    assert_eq!(count.last_size, Some(1));
+    assert!(count.last_update.is_some());
+    assert!(count.data_type.is_some());
+    assert!(count.rate > 0.0);
+    // Only the present state is known
+    assert!(topics.request_nth_back(1).is_err());
+    assert_eq!(host.topic_info().unwrap()[1].msgs_published, 1);
+
+    let stats = NodeConfig::<Blocking, Tcp, HostStats>::new(STATS_TOPIC)
+        .with_config(tcp_cfg)
+        .build()
+        .unwrap()
+        .subscribe(Duration::from_millis(10))
+        .unwrap();
+    thread::sleep(Duration::from_millis(100));
+    let summary = stats.get_subscribed_data().unwrap().data;
+    assert_eq!(summary.connections, 3);
+    assert_eq!(summary.topics, 2);
+    assert_eq!(summary.msgs_published, 4);
+    assert!(summary.msgs_received >= 4 && summary.msgs_sent >= 4);
+    assert!(summary.bytes_received > 0 && summary.bytes_sent > 0);
+    assert!(summary.publish_rate > 0.0);
+    assert_eq!(host.stats().unwrap().msgs_published, 4);
+
+    // System topics are read-only
+    let publisher = NodeConfig::<Blocking, Tcp, HostStats>::new(STATS_TOPIC)
+        .with_config(NetworkConfig::<Blocking, Tcp>::default().set_host_addr(addr))
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+    assert!(matches!(
+        publisher.publish(summary),
+        Err(Error::HostOperation(HostError::ReadOnlyTopic))
+    ));
+    // As is the rest of the namespace, such as the schema registry
+    let schema = NodeConfig::<Blocking, Tcp, usize>::new("_meadow/schemas/x")
+        .with_config(NetworkConfig::<Blocking, Tcp>::default().set_host_addr(addr))
+        .build()
+        .unwrap()
+        .activate()
+        .unwrap();
+    assert!(matches!(
+        schema.publish(1),
+        Err(Error::HostOperation(HostError::ReadOnlyTopic))
+    ));
+}
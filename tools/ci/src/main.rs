@@ -42,6 +42,30 @@ fn main() {
     )
     .run()
     .expect("Please fix failing tests in output above.");
+    cmd!(
+        sh,
+        "cargo test --workspace --features=shm shm -- --nocapture --test-threads=1"
+    )
+    .run()
+    .expect("Please fix failing tests in output above.");
+    cmd!(
+        sh,
+        "cargo test --workspace --features=tls tls -- --nocapture --test-threads=1"
+    )
+    .run()
+    .expect("Please fix failing tests in output above.");
+    cmd!(
+        sh,
+        "cargo test --workspace --features=json,cbor,msgpack,bincode encoding -- --nocapture --test-threads=1"
+    )
+    .run()
+    .expect("Please fix failing tests in output above.");
+    cmd!(
+        sh,
+        "cargo test --workspace --features=zerocopy raw -- --nocapture --test-threads=1"
+    )
+    .run()
+    .expect("Please fix failing tests in output above.");
     // Run certain examples
     let examples = vec!["host_and_single_node", "stress", "host"];
     for example in examples {